@@ -0,0 +1,141 @@
+use crate::scan::{Point, Scan};
+
+/// A point in meters, x along angle 0 and y along angle π/2.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct CartesianPoint {
+    pub x: f64,
+    pub y: f64,
+}
+
+impl CartesianPoint {
+    pub fn new(x: f64, y: f64) -> CartesianPoint {
+        CartesianPoint { x, y }
+    }
+
+    pub fn from_polar(point: &Point) -> CartesianPoint {
+        let (x, y) = point.to_xy();
+        CartesianPoint { x, y }
+    }
+
+    /// The polar form, with an angle in `(-π, π]`.
+    pub fn to_polar(&self) -> Point {
+        Point::new(self.y.atan2(self.x), self.x.hypot(self.y))
+    }
+
+    pub fn distance(&self, other: &CartesianPoint) -> f64 {
+        (self.x - other.x).hypot(self.y - other.y)
+    }
+
+    // missing returns, with a zero range, all end up at the origin
+    pub fn is_origin(&self) -> bool {
+        self.x == 0.0 && self.y == 0.0
+    }
+}
+
+/// Axis aligned box around a set of points.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct BoundingBox {
+    pub min: CartesianPoint,
+    pub max: CartesianPoint,
+}
+
+impl BoundingBox {
+    pub fn width(&self) -> f64 {
+        self.max.x - self.min.x
+    }
+
+    pub fn height(&self) -> f64 {
+        self.max.y - self.min.y
+    }
+
+    pub fn center(&self) -> CartesianPoint {
+        CartesianPoint::new(
+            (self.min.x + self.max.x) / 2.0,
+            (self.min.y + self.max.y) / 2.0,
+        )
+    }
+
+    pub fn contains(&self, point: &CartesianPoint) -> bool {
+        (self.min.x..=self.max.x).contains(&point.x) && (self.min.y..=self.max.y).contains(&point.y)
+    }
+}
+
+/// The points of a scan converted to x/y once, in the same order,
+/// for processing that works on positions rather than angles.
+/// Every scan keeps its own in [`Scan::cartesian`].
+/// Missing returns are kept at the origin so indices match the scan,
+/// [`CartesianScan::returns`] skips them.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct CartesianScan {
+    pub points: Vec<CartesianPoint>,
+}
+
+impl CartesianScan {
+    pub fn from_points(points: &[Point]) -> CartesianScan {
+        CartesianScan {
+            points: points.iter().map(CartesianPoint::from_polar).collect(),
+        }
+    }
+
+    /// A copy of the scan's cached [`Scan::cartesian`] view.
+    pub fn from_scan(scan: &Scan) -> CartesianScan {
+        scan.cartesian().clone()
+    }
+
+    /// Back to polar points, in the same order.
+    pub fn to_points(&self) -> Vec<Point> {
+        self.points.iter().map(|point| point.to_polar()).collect()
+    }
+
+    pub fn len(&self) -> usize {
+        self.points.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.points.is_empty()
+    }
+
+    /// Points with an actual return, leaving out those at the origin.
+    pub fn returns(&self) -> impl Iterator<Item = &CartesianPoint> + '_ {
+        self.points.iter().filter(|point| !point.is_origin())
+    }
+
+    /// Box around the returns, None if there's none.
+    pub fn bounding_box(&self) -> Option<BoundingBox> {
+        let mut returns = self.returns();
+        let first = *returns.next()?;
+        Some(returns.fold(
+            BoundingBox {
+                min: first,
+                max: first,
+            },
+            |bounds, point| BoundingBox {
+                min: CartesianPoint::new(bounds.min.x.min(point.x), bounds.min.y.min(point.y)),
+                max: CartesianPoint::new(bounds.max.x.max(point.x), bounds.max.y.max(point.y)),
+            },
+        ))
+    }
+
+    /// Mean position of the returns, None if there's none.
+    pub fn centroid(&self) -> Option<CartesianPoint> {
+        let (count, sum_x, sum_y) = self
+            .returns()
+            .fold((0usize, 0.0, 0.0), |(count, x, y), point| {
+                (count + 1, x + point.x, y + point.y)
+            });
+        if count == 0 {
+            None
+        } else {
+            Some(CartesianPoint::new(
+                sum_x / count as f64,
+                sum_y / count as f64,
+            ))
+        }
+    }
+}
+
+impl From<&Scan> for CartesianScan {
+    fn from(scan: &Scan) -> CartesianScan {
+        CartesianScan::from_scan(scan)
+    }
+}
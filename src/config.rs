@@ -1,36 +1,70 @@
-use std::path::Path;
+use crate::paths::PathExpander;
+use crate::validate::ValidationMode;
 
 pub struct Config {
     pub filenames: Vec<String>,
     pub help: bool,
     pub no_prompt: bool,
+    pub lenient: bool,
+    pub validation: Option<ValidationMode>,
+    pub follow: Vec<String>,
 }
 
 impl Config {
     pub fn new(args: &[String]) -> Result<Config, String> {
-        let mut filenames: Vec<String> = Vec::new();
+        let mut inputs: Vec<String> = Vec::new();
+        let mut expander = PathExpander::new();
         let mut no_prompt = false;
-        for i in &args[1..] {
+        let mut lenient = false;
+        let mut validation = None;
+        let mut follow = Vec::new();
+        let mut args = args[1..].iter();
+        while let Some(i) = args.next() {
             if !i.starts_with("--") {
-                if !Path::new(i).exists() {
-                    return Result::Err(format!("Given filepath {} do not exist", i));
-                }
-                filenames.push(i.to_string());
+                inputs.push(i.to_string());
             } else if i == "--help" {
                 return Result::Ok(Config {
-                    filenames,
+                    filenames: Vec::new(),
                     help: true,
                     no_prompt,
+                    lenient,
+                    validation,
+                    follow,
                 });
             } else if i == "--no-prompt" {
                 no_prompt = true;
+            } else if i == "--lenient" {
+                lenient = true;
+            } else if i == "--follow" {
+                match args.next() {
+                    Some(path) => follow.push(path.to_string()),
+                    None => return Result::Err(format!("Expect file after {}", i)),
+                }
+            } else if i == "--validate" {
+                validation = match args.next() {
+                    Some(mode) => Some(mode.parse()?),
+                    None => return Result::Err(format!("Expect mode after {}", i)),
+                };
+            } else if i == "--include" || i == "--exclude" {
+                let pattern = match args.next() {
+                    Some(pattern) => pattern,
+                    None => return Result::Err(format!("Expect pattern after {}", i)),
+                };
+                expander = if i == "--include" {
+                    expander.include(pattern)
+                } else {
+                    expander.exclude(pattern)
+                };
             }
         }
 
         Result::Ok(Config {
-            filenames,
+            filenames: expander.expand(&inputs)?,
             help: false,
             no_prompt,
+            lenient,
+            validation,
+            follow,
         })
     }
 }
@@ -0,0 +1,114 @@
+use crate::cartesian::CartesianPoint;
+use crate::scan::Scan;
+use crate::spatial::KdTree;
+
+// Points with a zero range are missing returns, the filters here
+// neither look at nor remove them.
+
+/// Replaces every range with the median of the `window` points around it,
+/// ordered by angle, which flattens single point spikes.
+/// The window is cut short at the ends of the scan,
+/// and the points are left in the order they're stored in.
+/// Gives how many ranges were changed.
+pub fn median_filter(scan: &mut Scan, window: usize) -> usize {
+    let half = window / 2;
+    let mut order: Vec<usize> = (0..scan.points().len()).collect();
+    order.sort_by(|&a, &b| scan.points()[a].angle.total_cmp(&scan.points()[b].angle));
+    let ranges: Vec<f64> = order.iter().map(|&i| scan.points()[i].range).collect();
+    let mut neighbors = Vec::with_capacity(half * 2 + 1);
+    let mut changed = 0;
+    for (i, &index) in order.iter().enumerate() {
+        let point = &mut scan.points_mut()[index];
+        if point.range == 0.0 {
+            continue;
+        }
+        neighbors.clear();
+        let end = (i + half + 1).min(ranges.len());
+        neighbors.extend(
+            ranges[i.saturating_sub(half)..end]
+                .iter()
+                .filter(|&&range| range != 0.0),
+        );
+        neighbors.sort_by(f64::total_cmp);
+        let median = neighbors[neighbors.len() / 2];
+        if median != point.range {
+            point.range = median;
+            changed += 1;
+        }
+    }
+    changed
+}
+
+/// Removes points whose mean distance to their `k` nearest neighbors is
+/// more than `std_ratio` standard deviations above the scan's average.
+/// Gives how many points were removed.
+pub fn remove_statistical_outliers(scan: &mut Scan, k: usize, std_ratio: f64) -> usize {
+    let (indices, tree) = returns_tree(scan);
+    if indices.len() <= k || k == 0 {
+        return 0;
+    }
+
+    let mean_distances: Vec<f64> = (0..tree.len())
+        .map(|i| {
+            // the closest one is the point itself
+            let neighbors = tree.k_nearest(&tree.point(i), k + 1);
+            neighbors[1..]
+                .iter()
+                .map(|&(_, distance)| distance)
+                .sum::<f64>()
+                / k as f64
+        })
+        .collect();
+    let count = mean_distances.len() as f64;
+    let mean = mean_distances.iter().sum::<f64>() / count;
+    let variance = mean_distances
+        .iter()
+        .map(|distance| (distance - mean).powi(2))
+        .sum::<f64>()
+        / count;
+    let threshold = mean + std_ratio * variance.sqrt();
+
+    let outliers: Vec<usize> = indices
+        .iter()
+        .zip(&mean_distances)
+        .filter(|(_, &distance)| distance > threshold)
+        .map(|(&index, _)| index)
+        .collect();
+    remove_points(scan, &outliers)
+}
+
+/// Removes points with fewer than `min_neighbors` other points
+/// within `radius` meters. Gives how many points were removed.
+pub fn remove_isolated(scan: &mut Scan, radius: f64, min_neighbors: usize) -> usize {
+    let (indices, tree) = returns_tree(scan);
+    let isolated: Vec<usize> = (0..tree.len())
+        .filter(|&i| tree.within(&tree.point(i), radius).len() <= min_neighbors)
+        .map(|i| indices[i])
+        .collect();
+    remove_points(scan, &isolated)
+}
+
+// the tree of the returns, with the index of each in the scan
+fn returns_tree(scan: &Scan) -> (Vec<usize>, KdTree) {
+    let (indices, points): (Vec<usize>, Vec<CartesianPoint>) = scan
+        .cartesian()
+        .points
+        .iter()
+        .enumerate()
+        .filter(|(_, point)| !point.is_origin())
+        .map(|(i, point)| (i, *point))
+        .unzip();
+    (indices, KdTree::new(&points))
+}
+
+// remove the points at the sorted indices
+fn remove_points(scan: &mut Scan, indices: &[usize]) -> usize {
+    let mut removing = indices.iter().peekable();
+    let mut i = 0;
+    scan.points_mut().retain(|_| {
+        let keep = removing.next_if_eq(&&i).is_none();
+        i += 1;
+        keep
+    });
+    indices.len()
+}
@@ -0,0 +1,223 @@
+use std::error::Error;
+use std::fmt;
+use std::io;
+
+/// Errors produced while reading a pointfile.
+/// Every variant carries the path of the file, and those
+/// raised by a specific line carry its 1-based line number.
+#[derive(Debug)]
+pub enum ParseError {
+    Io {
+        path: String,
+        source: io::Error,
+    },
+    // a header line that isn't a point count
+    BadCount {
+        path: String,
+        line: usize,
+        text: String,
+    },
+    ZeroCount {
+        path: String,
+        line: usize,
+    },
+    // a point line that doesn't split into angle, range
+    // and the optional intensity and flags
+    FieldCount {
+        path: String,
+        line: usize,
+        found: usize,
+        text: String,
+    },
+    // `column` is the 1-based column the offending field starts at
+    BadFloat {
+        path: String,
+        line: usize,
+        column: usize,
+        text: String,
+    },
+    // same as BadFloat, for the flags field
+    BadFlags {
+        path: String,
+        line: usize,
+        column: usize,
+        text: String,
+    },
+    // a `#key=value` line with a value that doesn't parse for its key
+    BadMetadata {
+        path: String,
+        line: usize,
+        text: String,
+    },
+    // a scan ended, by a new count header or the end of file,
+    // before it got all its points
+    Truncated {
+        path: String,
+        line: usize,
+        expected: usize,
+        found: usize,
+    },
+    // a scan kept in recovering mode with some of its point lines
+    // skipped, `line` is the last line of the scan
+    ShortScan {
+        path: String,
+        line: usize,
+        expected: usize,
+        found: usize,
+    },
+    // a file loaded along with another one whose scans get the same
+    // source name, like `23` and `23.gz`, it's left out to keep both apart
+    DuplicateSource {
+        path: String,
+        name: String,
+        other: String,
+    },
+    // binary pointfile written by a newer version
+    UnsupportedVersion {
+        path: String,
+        version: u16,
+    },
+    // binary pointfile that doesn't follow the format,
+    // `offset` is the byte offset the problem is found at
+    Corrupt {
+        path: String,
+        offset: u64,
+        reason: String,
+    },
+    ChecksumMismatch {
+        path: String,
+        offset: u64,
+        index: u32,
+    },
+}
+
+impl ParseError {
+    pub fn path(&self) -> &str {
+        match self {
+            ParseError::Io { path, .. }
+            | ParseError::BadCount { path, .. }
+            | ParseError::ZeroCount { path, .. }
+            | ParseError::FieldCount { path, .. }
+            | ParseError::BadFloat { path, .. }
+            | ParseError::BadFlags { path, .. }
+            | ParseError::BadMetadata { path, .. }
+            | ParseError::Truncated { path, .. }
+            | ParseError::ShortScan { path, .. }
+            | ParseError::DuplicateSource { path, .. }
+            | ParseError::UnsupportedVersion { path, .. }
+            | ParseError::Corrupt { path, .. }
+            | ParseError::ChecksumMismatch { path, .. } => path,
+        }
+    }
+
+    pub fn line(&self) -> Option<usize> {
+        match self {
+            ParseError::Io { .. }
+            | ParseError::DuplicateSource { .. }
+            | ParseError::UnsupportedVersion { .. }
+            | ParseError::Corrupt { .. }
+            | ParseError::ChecksumMismatch { .. } => None,
+            ParseError::BadCount { line, .. }
+            | ParseError::ZeroCount { line, .. }
+            | ParseError::FieldCount { line, .. }
+            | ParseError::BadFloat { line, .. }
+            | ParseError::BadFlags { line, .. }
+            | ParseError::BadMetadata { line, .. }
+            | ParseError::Truncated { line, .. }
+            | ParseError::ShortScan { line, .. } => Some(*line),
+        }
+    }
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ParseError::Io { path, source } => {
+                write!(f, "{path}: failed reading file, {source}")
+            }
+            ParseError::BadCount { path, line, text } => {
+                write!(f, "{path}:{line}: expected point count, got \"{text}\"")
+            }
+            ParseError::ZeroCount { path, line } => {
+                write!(f, "{path}:{line}: expected non-zero point count, got 0")
+            }
+            ParseError::FieldCount {
+                path,
+                line,
+                found,
+                text,
+            } => write!(
+                f,
+                "{path}:{line}: expected 2 to 4 comma separated fields, got {found} in \"{text}\""
+            ),
+            ParseError::BadFloat {
+                path,
+                line,
+                column,
+                text,
+            } => write!(f, "{path}:{line}:{column}: expected float, got \"{text}\""),
+            ParseError::BadFlags {
+                path,
+                line,
+                column,
+                text,
+            } => write!(
+                f,
+                "{path}:{line}:{column}: expected integer flags, got \"{text}\""
+            ),
+            ParseError::BadMetadata { path, line, text } => {
+                write!(f, "{path}:{line}: invalid metadata \"{text}\"")
+            }
+            ParseError::Truncated {
+                path,
+                line,
+                expected,
+                found,
+            } => write!(
+                f,
+                "{path}:{line}: scan ended early, expected {expected} points, got {found}"
+            ),
+            ParseError::ShortScan {
+                path,
+                line,
+                expected,
+                found,
+            } => write!(
+                f,
+                "{path}:{line}: scan kept with {found} of its {expected} points"
+            ),
+            ParseError::DuplicateSource { path, name, other } => write!(
+                f,
+                "{path}: scans would be named {name} like those of {other}"
+            ),
+            ParseError::UnsupportedVersion { path, version } => {
+                write!(f, "{path}: unsupported binary pointfile version {version}")
+            }
+            ParseError::Corrupt {
+                path,
+                offset,
+                reason,
+            } => write!(
+                f,
+                "{path}: corrupt binary pointfile at byte {offset}, {reason}"
+            ),
+            ParseError::ChecksumMismatch {
+                path,
+                offset,
+                index,
+            } => write!(
+                f,
+                "{path}: checksum mismatch in scan {index} starting at byte {offset}"
+            ),
+        }
+    }
+}
+
+impl Error for ParseError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            ParseError::Io { source, .. } => Some(source),
+            _ => None,
+        }
+    }
+}
@@ -0,0 +1,73 @@
+use crate::geometry::normalize_angle_positive;
+use crate::scan::{Point, Scan};
+
+/// Keeps the points of a scan within a range window and an angular sector,
+/// for removing returns off the robot itself and far away noise.
+/// Everything is kept by default.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct PointFilter {
+    min_range: Option<f64>,
+    max_range: Option<f64>,
+    sector: Option<(f64, f64)>,
+    drop_zero: bool,
+}
+
+impl PointFilter {
+    pub fn new() -> PointFilter {
+        PointFilter {
+            min_range: None,
+            max_range: None,
+            sector: None,
+            drop_zero: false,
+        }
+    }
+
+    /// Drops returns closer than `min_range`.
+    pub fn min_range(mut self, min_range: f64) -> PointFilter {
+        self.min_range = Some(min_range);
+        self
+    }
+
+    /// Drops returns further than `max_range`.
+    pub fn max_range(mut self, max_range: f64) -> PointFilter {
+        self.max_range = Some(max_range);
+        self
+    }
+
+    /// Keeps the sector going counterclockwise from `from` to `to` radians,
+    /// a sector with `from` above `to` wraps around through ±π.
+    /// Ends a whole number of turns apart, like -π and π, keep the full circle.
+    pub fn sector(mut self, from: f64, to: f64) -> PointFilter {
+        self.sector = Some((from, to));
+        self
+    }
+
+    /// Drops points with a zero range (no return). The range window
+    /// only applies to actual returns, so they're kept otherwise.
+    pub fn drop_zero(mut self, drop_zero: bool) -> PointFilter {
+        self.drop_zero = drop_zero;
+        self
+    }
+
+    pub fn keeps(&self, point: &Point) -> bool {
+        if let Some((from, to)) = self.sector {
+            let width = normalize_angle_positive(to - from);
+            let full_circle = width == 0.0 && from != to;
+            if !full_circle && normalize_angle_positive(point.angle - from) > width {
+                return false;
+            }
+        }
+        if point.range == 0.0 {
+            return !self.drop_zero;
+        }
+        self.min_range.is_none_or(|min| point.range >= min)
+            && self.max_range.is_none_or(|max| point.range <= max)
+    }
+
+    /// Removes the points not kept, giving how many were removed.
+    pub fn apply(&self, scan: &mut Scan) -> usize {
+        let before = scan.points().len();
+        scan.points_mut().retain(|point| self.keeps(point));
+        before - scan.points().len()
+    }
+}
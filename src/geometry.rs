@@ -0,0 +1,114 @@
+use crate::scan::{Point, Pose, Scan};
+use std::f64::consts::PI;
+use std::f64::consts::TAU;
+
+/// Wraps an angle into `(-π, π]`, the range pointfiles are recorded in.
+pub fn normalize_angle(angle: f64) -> f64 {
+    let wrapped = (angle + PI).rem_euclid(TAU) - PI;
+    // rem_euclid gives [-π, π), move the closed end over
+    if wrapped <= -PI {
+        PI
+    } else {
+        wrapped
+    }
+}
+
+/// Wraps an angle into `[0, 2π)`.
+pub fn normalize_angle_positive(angle: f64) -> f64 {
+    let wrapped = angle.rem_euclid(TAU);
+    // rem_euclid can round up to exactly 2π for tiny negative angles
+    if wrapped >= TAU {
+        0.0
+    } else {
+        wrapped
+    }
+}
+
+/// The signed shortest turn from `from` to `to`, in `(-π, π]`.
+pub fn angle_difference(from: f64, to: f64) -> f64 {
+    normalize_angle(to - from)
+}
+
+/// Sorts points by angle, points at the same angle keep their order.
+pub fn sort_by_angle(points: &mut [Point]) {
+    points.sort_by(|a, b| a.angle.total_cmp(&b.angle));
+}
+
+impl Point {
+    /// Position in meters, x along angle 0 and y along angle π/2.
+    pub fn to_xy(&self) -> (f64, f64) {
+        (self.range * self.angle.cos(), self.range * self.angle.sin())
+    }
+
+    /// Moves the point to the polar form of `(x, y)`,
+    /// keeping its intensity and flags.
+    pub fn set_xy(&mut self, x: f64, y: f64) {
+        self.angle = y.atan2(x);
+        self.range = x.hypot(y);
+    }
+}
+
+impl Pose {
+    /// The pose `relative`, given in the frame of this one,
+    /// in the frame this one is given in.
+    pub fn compose(&self, relative: Pose) -> Pose {
+        let (sin, cos) = self.theta.sin_cos();
+        Pose::new(
+            cos * relative.x - sin * relative.y + self.x,
+            sin * relative.x + cos * relative.y + self.y,
+            normalize_angle(self.theta + relative.theta),
+        )
+    }
+}
+
+// Transforms keep points sorted by angle, and leave points with a zero
+// range alone, as they are missing returns rather than points at the origin.
+impl Scan {
+    /// Rotates counterclockwise by `angle` radians.
+    pub fn rotate(&mut self, angle: f64) {
+        for point in self.points_mut().iter_mut() {
+            point.angle = normalize_angle(point.angle + angle);
+        }
+        self.sort_by_angle();
+    }
+
+    /// Moves every point by `(dx, dy)` meters,
+    /// as if the sensor had moved by `(-dx, -dy)`.
+    pub fn translate(&mut self, dx: f64, dy: f64) {
+        for point in self.points_mut().iter_mut() {
+            if point.range != 0.0 {
+                let (x, y) = point.to_xy();
+                point.set_xy(x + dx, y + dy);
+            }
+        }
+        self.sort_by_angle();
+    }
+
+    /// Mirrors over the line through the sensor at `axis` radians,
+    /// `0.0` flips the scan upside down and `π/2` left to right.
+    pub fn mirror(&mut self, axis: f64) {
+        for point in self.points_mut().iter_mut() {
+            point.angle = normalize_angle(2.0 * axis - point.angle);
+        }
+        self.sort_by_angle();
+    }
+
+    /// Rotates by `pose.theta` then moves by `(pose.x, pose.y)`, bringing
+    /// a scan taken at the pose into the frame the pose is given in.
+    pub fn transform(&mut self, pose: Pose) {
+        let (sin, cos) = pose.theta.sin_cos();
+        for point in self.points_mut().iter_mut() {
+            if point.range != 0.0 {
+                let (x, y) = point.to_xy();
+                point.set_xy(cos * x - sin * y + pose.x, sin * x + cos * y + pose.y);
+            } else {
+                point.angle = normalize_angle(point.angle + pose.theta);
+            }
+        }
+        self.sort_by_angle();
+    }
+
+    pub fn sort_by_angle(&mut self) {
+        sort_by_angle(self.points_mut());
+    }
+}
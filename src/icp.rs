@@ -0,0 +1,318 @@
+use crate::cartesian::CartesianPoint;
+use crate::scan::{Pose, Scan};
+use crate::spatial::KdTree;
+use rayon::prelude::*;
+
+// neighbors used to fit the line through a target point
+const NORMAL_NEIGHBORS: usize = 5;
+
+/// What the distance between matched points is measured as.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum IcpMethod {
+    /// Straight distance between the points.
+    PointToPoint,
+    /// Distance to the line through the target point, which lets
+    /// points slide along walls and usually converges in fewer steps.
+    PointToLine,
+}
+
+/// Outcome of [`Icp::align`].
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct IcpResult {
+    /// Brings the source scan onto the target with [`Scan::transform`].
+    pub pose: Pose,
+    /// Root mean square distance between matched points at `pose`.
+    pub residual: f64,
+    /// Points matched at `pose`.
+    pub matches: usize,
+    pub iterations: usize,
+    /// The last step was below the tolerance, or the residual stopped improving.
+    /// A step that made the residual worse by more than the tolerance stops the
+    /// search without converging, leaving `pose` where the residual was lowest.
+    pub converged: bool,
+}
+
+/// Iterative closest point registration between two scans.
+#[derive(Clone, Copy, Debug)]
+pub struct Icp {
+    method: IcpMethod,
+    max_iterations: usize,
+    max_distance: f64,
+    tolerance: f64,
+    initial: Pose,
+}
+
+impl Default for Icp {
+    fn default() -> Icp {
+        Icp::new()
+    }
+}
+
+impl Icp {
+    pub fn new() -> Icp {
+        Icp {
+            method: IcpMethod::PointToPoint,
+            max_iterations: 50,
+            max_distance: 0.5,
+            tolerance: 1e-6,
+            initial: Pose::default(),
+        }
+    }
+
+    pub fn method(mut self, method: IcpMethod) -> Icp {
+        self.method = method;
+        self
+    }
+
+    /// 50 by default.
+    pub fn max_iterations(mut self, max_iterations: usize) -> Icp {
+        self.max_iterations = max_iterations;
+        self
+    }
+
+    /// Points further than this from their closest target point
+    /// in meters are left unmatched, 0.5 by default.
+    pub fn max_distance(mut self, max_distance: f64) -> Icp {
+        self.max_distance = max_distance;
+        self
+    }
+
+    /// Steps smaller than this, in meters and radians, or a residual
+    /// improving by less than this in meters, count as converged, 1e-6 by default.
+    pub fn tolerance(mut self, tolerance: f64) -> Icp {
+        self.tolerance = tolerance;
+        self
+    }
+
+    /// Where to start searching, no motion by default.
+    pub fn initial(mut self, initial: Pose) -> Icp {
+        self.initial = initial;
+        self
+    }
+
+    /// Estimates the pose of `source` relative to `target`.
+    /// Missing returns are left out of both.
+    pub fn align(&self, source: &Scan, target: &Scan) -> IcpResult {
+        let source: Vec<CartesianPoint> = source.cartesian().returns().copied().collect();
+        let target: Vec<CartesianPoint> = target.cartesian().returns().copied().collect();
+        let tree = KdTree::new(&target);
+        let normals = match self.method {
+            IcpMethod::PointToPoint => Vec::new(),
+            IcpMethod::PointToLine => target
+                .par_iter()
+                .map(|point| normal(&tree, point))
+                .collect(),
+        };
+
+        let mut pose = self.initial;
+        let mut matches = self.matches(&tree, &source, pose);
+        let mut residual = self.residual(&tree, &normals, &matches);
+        let mut iterations = 0;
+        let mut converged = false;
+        while iterations < self.max_iterations {
+            iterations += 1;
+            let step = match self.method {
+                IcpMethod::PointToPoint => point_to_point_step(&tree, &matches),
+                IcpMethod::PointToLine => point_to_line_step(&tree, &normals, &matches),
+            };
+            let Some(step) = step else {
+                break;
+            };
+            let next = step.compose(pose);
+            let next_matches = self.matches(&tree, &source, next);
+            let next_residual = self.residual(&tree, &normals, &next_matches);
+
+            // a step making the fit worse is not taken, rising by less than the
+            // tolerance is noise around the best fit, more means the guess was
+            // too far off for the matches to lead to it
+            if next_residual > residual {
+                converged = next_residual - residual < self.tolerance;
+                break;
+            }
+            let improvement = residual - next_residual;
+            pose = next;
+            matches = next_matches;
+            residual = next_residual;
+            // points switching between equally close targets can keep the
+            // pose cycling around the best fit, where the residual stops improving
+            if improvement < self.tolerance
+                || step.x.hypot(step.y) < self.tolerance && step.theta.abs() < self.tolerance
+            {
+                converged = true;
+                break;
+            }
+        }
+
+        IcpResult {
+            pose,
+            residual,
+            matches: matches.len(),
+            iterations,
+            converged,
+        }
+    }
+
+    // root mean square distance of the matches, infinite without any
+    fn residual(
+        &self,
+        tree: &KdTree,
+        normals: &[CartesianPoint],
+        matches: &[(CartesianPoint, usize, f64)],
+    ) -> f64 {
+        if matches.is_empty() {
+            return f64::INFINITY;
+        }
+        let squared: f64 = matches
+            .iter()
+            .map(|&(point, index, distance)| match self.method {
+                IcpMethod::PointToPoint => distance * distance,
+                IcpMethod::PointToLine => line_distance(tree, normals, point, index).powi(2),
+            })
+            .sum();
+        (squared / matches.len() as f64).sqrt()
+    }
+
+    // the moved source points within max_distance of a target point,
+    // with the index of that point and the distance to it
+    fn matches(
+        &self,
+        tree: &KdTree,
+        source: &[CartesianPoint],
+        pose: Pose,
+    ) -> Vec<(CartesianPoint, usize, f64)> {
+        source
+            .par_iter()
+            .filter_map(|point| {
+                let moved = apply(pose, point);
+                tree.nearest(&moved)
+                    .filter(|&(_, distance)| distance <= self.max_distance)
+                    .map(|(index, distance)| (moved, index, distance))
+            })
+            .collect()
+    }
+}
+
+fn apply(pose: Pose, point: &CartesianPoint) -> CartesianPoint {
+    let (sin, cos) = pose.theta.sin_cos();
+    CartesianPoint::new(
+        cos * point.x - sin * point.y + pose.x,
+        sin * point.x + cos * point.y + pose.y,
+    )
+}
+
+// unit normal of the line through the point and its neighbors
+fn normal(tree: &KdTree, point: &CartesianPoint) -> CartesianPoint {
+    let neighbors = tree.k_nearest(point, NORMAL_NEIGHBORS);
+    let count = neighbors.len() as f64;
+    let (mean_x, mean_y) = neighbors.iter().fold((0.0, 0.0), |(x, y), &(index, _)| {
+        (
+            x + tree.point(index).x / count,
+            y + tree.point(index).y / count,
+        )
+    });
+    let (xx, xy, yy) = neighbors
+        .iter()
+        .fold((0.0, 0.0, 0.0), |(xx, xy, yy), &(index, _)| {
+            let dx = tree.point(index).x - mean_x;
+            let dy = tree.point(index).y - mean_y;
+            (xx + dx * dx, xy + dx * dy, yy + dy * dy)
+        });
+    // the line runs along the direction of largest spread
+    let direction = 0.5 * (2.0 * xy).atan2(xx - yy);
+    CartesianPoint::new(-direction.sin(), direction.cos())
+}
+
+fn line_distance(
+    tree: &KdTree,
+    normals: &[CartesianPoint],
+    point: CartesianPoint,
+    index: usize,
+) -> f64 {
+    let target = tree.point(index);
+    let normal = normals[index];
+    normal.x * (point.x - target.x) + normal.y * (point.y - target.y)
+}
+
+// closed form rigid motion between the matched point pairs
+fn point_to_point_step(tree: &KdTree, matches: &[(CartesianPoint, usize, f64)]) -> Option<Pose> {
+    if matches.len() < 2 {
+        return None;
+    }
+    let count = matches.len() as f64;
+    let (mut px, mut py, mut qx, mut qy) = (0.0, 0.0, 0.0, 0.0);
+    for &(point, index, _) in matches {
+        let target = tree.point(index);
+        px += point.x / count;
+        py += point.y / count;
+        qx += target.x / count;
+        qy += target.y / count;
+    }
+    let (mut dot, mut cross) = (0.0, 0.0);
+    for &(point, index, _) in matches {
+        let target = tree.point(index);
+        let (ax, ay) = (point.x - px, point.y - py);
+        let (bx, by) = (target.x - qx, target.y - qy);
+        dot += ax * bx + ay * by;
+        cross += ax * by - ay * bx;
+    }
+    let theta = cross.atan2(dot);
+    let (sin, cos) = theta.sin_cos();
+    Some(Pose::new(
+        qx - (cos * px - sin * py),
+        qy - (sin * px + cos * py),
+        theta,
+    ))
+}
+
+// least squares step for the distances to the target lines,
+// linearized for a small rotation
+fn point_to_line_step(
+    tree: &KdTree,
+    normals: &[CartesianPoint],
+    matches: &[(CartesianPoint, usize, f64)],
+) -> Option<Pose> {
+    if matches.len() < 3 {
+        return None;
+    }
+    let mut a = [[0.0; 3]; 3];
+    let mut b = [0.0; 3];
+    for &(point, index, _) in matches {
+        let normal = normals[index];
+        let row = [normal.x, normal.y, normal.y * point.x - normal.x * point.y];
+        let error = -line_distance(tree, normals, point, index);
+        for i in 0..3 {
+            for j in 0..3 {
+                a[i][j] += row[i] * row[j];
+            }
+            b[i] += row[i] * error;
+        }
+    }
+    let [x, y, theta] = solve(a, b)?;
+    Some(Pose::new(x, y, theta))
+}
+
+// gaussian elimination with partial pivoting, None if singular
+pub(crate) fn solve(mut a: [[f64; 3]; 3], mut b: [f64; 3]) -> Option<[f64; 3]> {
+    for col in 0..3 {
+        let pivot = (col..3).max_by(|&i, &j| a[i][col].abs().total_cmp(&a[j][col].abs()))?;
+        if a[pivot][col].abs() < 1e-12 {
+            return None;
+        }
+        a.swap(col, pivot);
+        b.swap(col, pivot);
+        for row in col + 1..3 {
+            let factor = a[row][col] / a[col][col];
+            let pivot_row = a[col];
+            for (value, pivot) in a[row].iter_mut().zip(pivot_row).skip(col) {
+                *value -= factor * pivot;
+            }
+            b[row] -= factor * b[col];
+        }
+    }
+    let mut x = [0.0; 3];
+    for row in (0..3).rev() {
+        let rest: f64 = (row + 1..3).map(|k| a[row][k] * x[k]).sum();
+        x[row] = (b[row] - rest) / a[row][row];
+    }
+    Some(x)
+}
@@ -0,0 +1,184 @@
+use crate::ptfl_binary::MAGIC;
+use flate2::bufread::MultiGzDecoder;
+use ruzstd::{FrameDecoder, StreamingDecoder};
+use std::fs::File;
+use std::io;
+use std::io::{BufRead, BufReader, Read};
+use std::path::Path;
+
+/// Path meaning stdin on the command line and in the tui.
+pub const STDIN_PATH: &str = "-";
+/// Source name given to scans read from stdin.
+pub const STDIN_SOURCE: &str = "stdin";
+
+const GZIP_MAGIC: &[u8] = &[0x1f, 0x8b];
+const ZSTD_MAGIC: &[u8] = &[0x28, 0xb5, 0x2f, 0xfd];
+
+/// Compression a pointfile is stored with.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Compression {
+    None,
+    Gzip,
+    Zstd,
+}
+
+impl Compression {
+    /// Tells the compression by the magic bytes at the start of the reader,
+    /// falling back to the extension of the path when they're not recognized.
+    pub fn detect<R: BufRead>(path: &str, reader: &mut R) -> io::Result<Compression> {
+        let start = reader.fill_buf()?;
+        if start.starts_with(GZIP_MAGIC) {
+            Ok(Compression::Gzip)
+        } else if start.starts_with(ZSTD_MAGIC) {
+            Ok(Compression::Zstd)
+        } else {
+            Ok(Compression::from_extension(path))
+        }
+    }
+
+    pub fn from_extension(path: &str) -> Compression {
+        if path.ends_with(".gz") {
+            Compression::Gzip
+        } else if path.ends_with(".zst") || path.ends_with(".zstd") {
+            Compression::Zstd
+        } else {
+            Compression::None
+        }
+    }
+
+    /// Wraps the reader so it reads decompressed data.
+    pub fn decompress<R>(self, reader: R) -> io::Result<Box<dyn BufRead + Send>>
+    where
+        R: BufRead + Send + 'static,
+    {
+        match self {
+            Compression::None => Ok(Box::new(reader)),
+            Compression::Gzip => Ok(Box::new(BufReader::new(MultiGzDecoder::new(reader)))),
+            Compression::Zstd => Ok(Box::new(BufReader::new(ZstdDecoder::new(reader)?))),
+        }
+    }
+}
+
+/// Opens a file for reading, decompressing it on the fly if it's gzip or zstd compressed.
+pub fn open_input(path: &str) -> io::Result<Box<dyn BufRead + Send>> {
+    let mut file = BufReader::new(File::open(path)?);
+    Compression::detect(path, &mut file)?.decompress(file)
+}
+
+/// Streams a pointfile piped to stdin. Compressed and binary data is read
+/// to the end, text until the end or a line holding only `.`,
+/// so commands can follow the scans on the same stdin.
+/// Stdin is shared by the whole process, so only one reader should be open at a time.
+pub fn open_stdin() -> io::Result<Box<dyn BufRead + Send>> {
+    let start = io::stdin().lock().fill_buf()?.to_vec();
+    if start.starts_with(GZIP_MAGIC) || start.starts_with(ZSTD_MAGIC) || start.starts_with(MAGIC) {
+        return Ok(Box::new(BufReader::new(io::stdin())));
+    }
+    Ok(Box::new(StdinLines {
+        line: String::new(),
+        consumed: 0,
+        done: false,
+    }))
+}
+
+/// Named pipes are read as scans arrive instead of loaded at once.
+#[cfg(unix)]
+pub fn is_fifo(path: &str) -> bool {
+    use std::os::unix::fs::FileTypeExt;
+    Path::new(path)
+        .metadata()
+        .is_ok_and(|metadata| metadata.file_type().is_fifo())
+}
+
+#[cfg(not(unix))]
+pub fn is_fifo(_path: &str) -> bool {
+    false
+}
+
+// the file name without its compression extension, so `23.gz` is read as `23`
+pub(crate) fn strip_compression_extension(name: &str) -> &str {
+    for extension in [".gz", ".zst", ".zstd"] {
+        if let Some(stripped) = name.strip_suffix(extension) {
+            if !stripped.is_empty() {
+                return stripped;
+            }
+        }
+    }
+    name
+}
+
+// text lines of stdin up to a `.` line, taken from stdin one line
+// at a time so nothing after the `.` is read ahead
+struct StdinLines {
+    line: String,
+    consumed: usize,
+    done: bool,
+}
+
+impl Read for StdinLines {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let available = self.fill_buf()?;
+        let read = available.len().min(buf.len());
+        buf[..read].copy_from_slice(&available[..read]);
+        self.consume(read);
+        Ok(read)
+    }
+}
+
+impl BufRead for StdinLines {
+    fn fill_buf(&mut self) -> io::Result<&[u8]> {
+        if self.consumed == self.line.len() && !self.done {
+            self.line.clear();
+            self.consumed = 0;
+            let read = io::stdin().lock().read_line(&mut self.line)?;
+            if read == 0 || self.line.trim_end() == "." {
+                self.line.clear();
+                self.done = true;
+            }
+        }
+        Ok(&self.line.as_bytes()[self.consumed..])
+    }
+
+    fn consume(&mut self, amount: usize) {
+        self.consumed = (self.consumed + amount).min(self.line.len());
+    }
+}
+
+// StreamingDecoder only reads a single frame,
+// this starts a new one for every frame concatenated after it
+struct ZstdDecoder<R: BufRead> {
+    decoder: Option<StreamingDecoder<R, FrameDecoder>>,
+}
+
+impl<R: BufRead> ZstdDecoder<R> {
+    fn new(reader: R) -> io::Result<ZstdDecoder<R>> {
+        Ok(ZstdDecoder {
+            decoder: Some(StreamingDecoder::new(reader).map_err(invalid_data)?),
+        })
+    }
+}
+
+impl<R: BufRead> Read for ZstdDecoder<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        loop {
+            let decoder = match &mut self.decoder {
+                Some(decoder) => decoder,
+                None => return Ok(0),
+            };
+            let read = decoder.read(buf)?;
+            if read > 0 || buf.is_empty() {
+                return Ok(read);
+            }
+
+            // the frame is finished, check for the next one
+            let mut reader = self.decoder.take().unwrap().into_inner();
+            if !reader.fill_buf()?.is_empty() {
+                self.decoder = Some(StreamingDecoder::new(reader).map_err(invalid_data)?);
+            }
+        }
+    }
+}
+
+fn invalid_data<E: std::fmt::Display>(err: E) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, err.to_string())
+}
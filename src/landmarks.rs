@@ -0,0 +1,267 @@
+use crate::cartesian::CartesianPoint;
+use crate::geometry::normalize_angle;
+use crate::icp::solve;
+use crate::lines::{LineExtractor, LineSegment};
+use crate::scan::Scan;
+use std::f64::consts::PI;
+
+/// What a [`Landmark`] is.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum LandmarkKind {
+    /// Where two walls meet.
+    Corner,
+    /// A round object like a pillar or a pole, with its radius in meters.
+    Circle { radius: f64 },
+}
+
+/// A feature that can be recognized again from another pose.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Landmark {
+    pub kind: LandmarkKind,
+    /// Corner point or circle center, in the frame of the scan.
+    pub position: CartesianPoint,
+    /// How sure the detection is, from 0 to 1.
+    pub confidence: f64,
+}
+
+/// Detects corners out of the line segments of a scan, and circles
+/// fitted over clusters of returns standing apart from their neighbors.
+#[derive(Clone, Copy, Debug)]
+pub struct LandmarkDetector {
+    lines: LineExtractor,
+    corner_angle: f64,
+    corner_distance: f64,
+    cluster_gap: f64,
+    min_radius: f64,
+    max_radius: f64,
+    circle_error: f64,
+    min_points: usize,
+}
+
+impl Default for LandmarkDetector {
+    fn default() -> LandmarkDetector {
+        LandmarkDetector::new()
+    }
+}
+
+impl LandmarkDetector {
+    pub fn new() -> LandmarkDetector {
+        LandmarkDetector {
+            lines: LineExtractor::new(),
+            corner_angle: PI / 4.0,
+            corner_distance: 0.1,
+            cluster_gap: 0.05,
+            min_radius: 0.02,
+            max_radius: 0.3,
+            circle_error: 0.01,
+            min_points: 5,
+        }
+    }
+
+    /// How line segments for corners are extracted.
+    pub fn lines(mut self, lines: LineExtractor) -> LandmarkDetector {
+        self.lines = lines;
+        self
+    }
+
+    /// Smallest angle in radians between two walls meeting
+    /// at a corner, 45 degree by default.
+    pub fn corner_angle(mut self, corner_angle: f64) -> LandmarkDetector {
+        self.corner_angle = corner_angle;
+        self
+    }
+
+    /// Furthest the ends of both walls can be from the corner
+    /// in meters, 0.1 by default.
+    pub fn corner_distance(mut self, corner_distance: f64) -> LandmarkDetector {
+        self.corner_distance = corner_distance;
+        self
+    }
+
+    /// Returns further apart than this in meters start a new
+    /// cluster, 0.05 by default.
+    pub fn cluster_gap(mut self, cluster_gap: f64) -> LandmarkDetector {
+        self.cluster_gap = cluster_gap;
+        self
+    }
+
+    /// Radius of circles kept in meters, 0.02 to 0.3 by default.
+    pub fn radius(mut self, min: f64, max: f64) -> LandmarkDetector {
+        self.min_radius = min;
+        self.max_radius = max;
+        self
+    }
+
+    /// Largest root mean square distance of a cluster to its circle
+    /// in meters, 0.01 by default.
+    pub fn circle_error(mut self, circle_error: f64) -> LandmarkDetector {
+        self.circle_error = circle_error;
+        self
+    }
+
+    /// Fewest returns a circle is fitted over, 5 by default.
+    pub fn min_points(mut self, min_points: usize) -> LandmarkDetector {
+        self.min_points = min_points.max(3);
+        self
+    }
+
+    /// Corners and circles in the scan, most confident first.
+    pub fn detect(&self, scan: &Scan) -> Vec<Landmark> {
+        self.detect_with_segments(scan).0
+    }
+
+    /// Like [`LandmarkDetector::detect`], also giving the
+    /// line segments the corners were found between.
+    pub fn detect_with_segments(&self, scan: &Scan) -> (Vec<Landmark>, Vec<LineSegment>) {
+        let segments = self.lines.extract(scan);
+        let mut landmarks = self.corners(&segments);
+        landmarks.append(&mut self.circles(scan));
+        landmarks.sort_by(|a, b| b.confidence.total_cmp(&a.confidence));
+        (landmarks, segments)
+    }
+
+    /// Corners where two of the segments meet, or nearly do.
+    pub fn corners(&self, segments: &[LineSegment]) -> Vec<Landmark> {
+        let mut corners: Vec<Landmark> = Vec::new();
+        for (i, a) in segments.iter().enumerate() {
+            for b in &segments[i + 1..] {
+                let angle = normalize_angle(a.angle() - b.angle()).abs();
+                let angle = angle.min(PI - angle);
+                if angle < self.corner_angle {
+                    continue;
+                }
+                let Some(position) = intersection(a, b) else {
+                    continue;
+                };
+                let gap = end_distance(a, &position).max(end_distance(b, &position));
+                if gap > self.corner_distance {
+                    continue;
+                }
+                let confidence = angle.sin() * (1.0 - gap / self.corner_distance);
+                // segments from RANSAC can overlap, keep the better one
+                match corners
+                    .iter_mut()
+                    .find(|corner| corner.position.distance(&position) <= self.corner_distance)
+                {
+                    Some(corner) if corner.confidence >= confidence => {}
+                    Some(corner) => {
+                        corner.position = position;
+                        corner.confidence = confidence;
+                    }
+                    None => corners.push(Landmark {
+                        kind: LandmarkKind::Corner,
+                        position,
+                        confidence,
+                    }),
+                }
+            }
+        }
+        corners
+    }
+
+    /// Circles fitted over clusters of neighboring returns, facing the sensor.
+    pub fn circles(&self, scan: &Scan) -> Vec<Landmark> {
+        let points: Vec<CartesianPoint> = scan.cartesian().returns().copied().collect();
+
+        let mut clusters: Vec<&[CartesianPoint]> = Vec::new();
+        let mut start = 0;
+        for i in 1..=points.len() {
+            if i == points.len() || points[i - 1].distance(&points[i]) > self.cluster_gap {
+                clusters.push(&points[start..i]);
+                start = i;
+            }
+        }
+
+        clusters
+            .into_iter()
+            .filter(|cluster| cluster.len() >= self.min_points)
+            .filter_map(|cluster| self.fit_circle(cluster))
+            .collect()
+    }
+
+    fn fit_circle(&self, cluster: &[CartesianPoint]) -> Option<Landmark> {
+        let (center, radius) = fit_circle(cluster)?;
+        if radius < self.min_radius || radius > self.max_radius {
+            return None;
+        }
+        let count = cluster.len() as f64;
+        let error = (cluster
+            .iter()
+            .map(|point| (point.distance(&center) - radius).powi(2))
+            .sum::<f64>()
+            / count)
+            .sqrt();
+        if error > self.circle_error {
+            return None;
+        }
+        // the sensor sees the near side of a pillar, not the inside of a bend
+        let distance = cluster
+            .iter()
+            .map(|point| point.x.hypot(point.y))
+            .sum::<f64>()
+            / count;
+        if center.x.hypot(center.y) <= distance {
+            return None;
+        }
+
+        // how much of the circle is seen, around the side facing the sensor
+        let facing = (-center.y).atan2(-center.x);
+        let (low, high) = cluster.iter().fold((PI, -PI), |(low, high), point| {
+            let angle = normalize_angle((point.y - center.y).atan2(point.x - center.x) - facing);
+            (low.min(angle), high.max(angle))
+        });
+        let coverage = ((high - low) / PI).min(1.0);
+        Some(Landmark {
+            kind: LandmarkKind::Circle { radius },
+            position: center,
+            confidence: (1.0 - error / self.circle_error) * coverage,
+        })
+    }
+}
+
+// where the lines through both segments cross, None if parallel
+fn intersection(a: &LineSegment, b: &LineSegment) -> Option<CartesianPoint> {
+    let (adx, ady) = (a.end.x - a.start.x, a.end.y - a.start.y);
+    let (bdx, bdy) = (b.end.x - b.start.x, b.end.y - b.start.y);
+    let cross = adx * bdy - ady * bdx;
+    if cross.abs() < 1e-12 {
+        return None;
+    }
+    let t = ((b.start.x - a.start.x) * bdy - (b.start.y - a.start.y) * bdx) / cross;
+    Some(CartesianPoint::new(
+        a.start.x + t * adx,
+        a.start.y + t * ady,
+    ))
+}
+
+fn end_distance(segment: &LineSegment, point: &CartesianPoint) -> f64 {
+    segment
+        .start
+        .distance(point)
+        .min(segment.end.distance(point))
+}
+
+// algebraic circle fit, x² + y² + dx + ey + f = 0 in least squares,
+// around the centroid to keep the sums small
+fn fit_circle(points: &[CartesianPoint]) -> Option<(CartesianPoint, f64)> {
+    let count = points.len() as f64;
+    let (mx, my) = points.iter().fold((0.0, 0.0), |(x, y), point| {
+        (x + point.x / count, y + point.y / count)
+    });
+    let mut a = [[0.0; 3]; 3];
+    let mut b = [0.0; 3];
+    for point in points {
+        let row = [point.x - mx, point.y - my, 1.0];
+        let squared = row[0] * row[0] + row[1] * row[1];
+        for i in 0..3 {
+            for j in 0..3 {
+                a[i][j] += row[i] * row[j];
+            }
+            b[i] -= row[i] * squared;
+        }
+    }
+    let [d, e, f] = solve(a, b)?;
+    let (x, y) = (-d / 2.0, -e / 2.0);
+    let squared = x * x + y * y - f;
+    (squared > 0.0).then(|| (CartesianPoint::new(x + mx, y + my), squared.sqrt()))
+}
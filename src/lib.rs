@@ -1,10 +1,89 @@
+mod cartesian;
 mod config;
+mod denoise;
+mod error;
+mod filter;
+mod geometry;
+mod icp;
+mod input;
+mod landmarks;
+mod lines;
+mod loader;
+mod occupancy;
+mod odometry;
 mod output;
+mod paths;
+mod ptfl_binary;
 mod ptfl_parse;
+mod ptfl_write;
+mod resample;
+mod scan;
+mod spatial;
+mod stream;
 mod tev_wrapper;
+mod validate;
 
+pub use crate::cartesian::BoundingBox;
+pub use crate::cartesian::CartesianPoint;
+pub use crate::cartesian::CartesianScan;
 pub use crate::config::Config;
+pub use crate::denoise::median_filter;
+pub use crate::denoise::remove_isolated;
+pub use crate::denoise::remove_statistical_outliers;
+pub use crate::error::ParseError;
+pub use crate::filter::PointFilter;
+pub use crate::geometry::angle_difference;
+pub use crate::geometry::normalize_angle;
+pub use crate::geometry::normalize_angle_positive;
+pub use crate::geometry::sort_by_angle;
+pub use crate::icp::Icp;
+pub use crate::icp::IcpMethod;
+pub use crate::icp::IcpResult;
+pub use crate::input::is_fifo;
+pub use crate::input::open_input;
+pub use crate::input::open_stdin;
+pub use crate::input::Compression;
+pub use crate::input::STDIN_PATH;
+pub use crate::input::STDIN_SOURCE;
+pub use crate::landmarks::Landmark;
+pub use crate::landmarks::LandmarkDetector;
+pub use crate::landmarks::LandmarkKind;
+pub use crate::lines::LineExtractor;
+pub use crate::lines::LineMethod;
+pub use crate::lines::LineSegment;
+pub use crate::loader::FileReport;
+pub use crate::loader::Loader;
+pub use crate::occupancy::Occupancy;
+pub use crate::occupancy::OccupancyGrid;
+pub use crate::odometry::Trajectory;
+pub use crate::odometry::TrajectoryPoint;
+pub use crate::output::IntensityStyle;
 pub use crate::output::PNGOutput;
 pub use crate::output::SVGOutput;
+pub use crate::paths::glob_match;
+pub use crate::paths::natural_cmp;
+pub use crate::paths::PathExpander;
+pub use crate::ptfl_binary::BinaryScanReader;
+pub use crate::ptfl_binary::BinaryWriter;
+pub use crate::ptfl_parse::open_scans;
+pub use crate::ptfl_parse::read_scans;
+pub use crate::ptfl_parse::ParseReport;
 pub use crate::ptfl_parse::PtflParser;
+pub use crate::ptfl_parse::ScanReader;
+pub use crate::ptfl_write::PtflWriter;
+pub use crate::resample::average;
+pub use crate::resample::Interpolation;
+pub use crate::resample::Resampler;
+pub use crate::resample::DEFAULT_RESOLUTION;
+pub use crate::scan::Point;
+pub use crate::scan::Pose;
+pub use crate::scan::Scan;
+pub use crate::scan::ScanSet;
+pub use crate::spatial::KdTree;
+pub use crate::stream::ScanStream;
 pub use crate::tev_wrapper::TevWrappedClient;
+pub use crate::validate::Issue;
+pub use crate::validate::ScanReport;
+pub use crate::validate::ValidationMode;
+pub use crate::validate::Validator;
+pub use crate::validate::TYPICAL_POINT_COUNT;
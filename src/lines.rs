@@ -0,0 +1,385 @@
+use crate::cartesian::CartesianPoint;
+use crate::scan::Scan;
+
+/// A straight piece of wall found in a scan.
+#[derive(Clone, Debug, PartialEq)]
+pub struct LineSegment {
+    pub start: CartesianPoint,
+    pub end: CartesianPoint,
+    /// Root mean square distance of the supporting points to the line.
+    pub error: f64,
+    /// Indices of the supporting points in the scan.
+    pub points: Vec<usize>,
+}
+
+impl LineSegment {
+    pub fn length(&self) -> f64 {
+        self.start.distance(&self.end)
+    }
+
+    /// Direction from start to end in radians.
+    pub fn angle(&self) -> f64 {
+        (self.end.y - self.start.y).atan2(self.end.x - self.start.x)
+    }
+
+    /// Distance from the point to the segment, not just its line.
+    pub fn distance_to(&self, point: &CartesianPoint) -> f64 {
+        let (dx, dy) = (self.end.x - self.start.x, self.end.y - self.start.y);
+        let length_squared = dx * dx + dy * dy;
+        if length_squared == 0.0 {
+            return self.start.distance(point);
+        }
+        let t = (((point.x - self.start.x) * dx + (point.y - self.start.y) * dy) / length_squared)
+            .clamp(0.0, 1.0);
+        point.distance(&CartesianPoint::new(
+            self.start.x + t * dx,
+            self.start.y + t * dy,
+        ))
+    }
+}
+
+/// How line segments are searched for.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum LineMethod {
+    /// Splits runs of neighboring points at the point furthest from the
+    /// line between their ends, then merges neighbors on the same line.
+    /// Fast and follows the scan order.
+    SplitAndMerge,
+    /// Repeatedly takes the line through two random points with the most
+    /// points near it. Copes better with clutter in front of walls.
+    Ransac,
+}
+
+/// Extracts line segments out of scans, missing returns are left out.
+#[derive(Clone, Copy, Debug)]
+pub struct LineExtractor {
+    method: LineMethod,
+    distance: f64,
+    max_gap: f64,
+    min_points: usize,
+    min_length: f64,
+    iterations: usize,
+    seed: u64,
+}
+
+impl Default for LineExtractor {
+    fn default() -> LineExtractor {
+        LineExtractor::new()
+    }
+}
+
+impl LineExtractor {
+    pub fn new() -> LineExtractor {
+        LineExtractor {
+            method: LineMethod::SplitAndMerge,
+            distance: 0.03,
+            max_gap: 0.1,
+            min_points: 8,
+            min_length: 0.2,
+            iterations: 200,
+            seed: 0x2545_f491_4f6c_dd1d,
+        }
+    }
+
+    pub fn method(mut self, method: LineMethod) -> LineExtractor {
+        self.method = method;
+        self
+    }
+
+    /// How far in meters a point can be from a line and still be on it,
+    /// 0.03 by default.
+    pub fn distance(mut self, distance: f64) -> LineExtractor {
+        self.distance = distance;
+        self
+    }
+
+    /// Points further apart than this in meters don't belong to
+    /// the same segment, 0.1 by default.
+    pub fn max_gap(mut self, max_gap: f64) -> LineExtractor {
+        self.max_gap = max_gap;
+        self
+    }
+
+    /// Fewest points supporting a segment, 8 by default.
+    pub fn min_points(mut self, min_points: usize) -> LineExtractor {
+        self.min_points = min_points.max(2);
+        self
+    }
+
+    /// Shortest segment in meters, 0.2 by default.
+    pub fn min_length(mut self, min_length: f64) -> LineExtractor {
+        self.min_length = min_length;
+        self
+    }
+
+    /// Lines tried for every segment found with RANSAC, 200 by default.
+    pub fn iterations(mut self, iterations: usize) -> LineExtractor {
+        self.iterations = iterations;
+        self
+    }
+
+    /// Seed of the random picks of RANSAC, the same seed
+    /// finds the same segments.
+    pub fn seed(mut self, seed: u64) -> LineExtractor {
+        self.seed = seed;
+        self
+    }
+
+    pub fn extract(&self, scan: &Scan) -> Vec<LineSegment> {
+        let (indices, points): (Vec<usize>, Vec<CartesianPoint>) = scan
+            .cartesian()
+            .points
+            .iter()
+            .enumerate()
+            .filter(|(_, point)| !point.is_origin())
+            .map(|(i, point)| (i, *point))
+            .unzip();
+
+        let segments = match self.method {
+            LineMethod::SplitAndMerge => self.split_and_merge(&points),
+            LineMethod::Ransac => self.ransac(&points),
+        };
+        segments
+            .into_iter()
+            .map(|members| {
+                let mut segment = fit_segment(&points, &members);
+                segment.points = members.iter().map(|&i| indices[i]).collect();
+                segment
+            })
+            .filter(|segment| segment.length() >= self.min_length)
+            .collect()
+    }
+
+    // runs of points, as indices into points, in scan order
+    fn split_and_merge(&self, points: &[CartesianPoint]) -> Vec<Vec<usize>> {
+        let count = points.len();
+        if count < self.min_points {
+            return Vec::new();
+        }
+        // start after a gap, so a wall across ±π isn't cut in two
+        let start = (0..count)
+            .find(|&i| points[(i + count - 1) % count].distance(&points[i]) > self.max_gap)
+            .unwrap_or(0);
+        let order: Vec<usize> = (0..count).map(|i| (start + i) % count).collect();
+
+        let mut runs: Vec<Vec<usize>> = vec![Vec::new()];
+        for pair in order.windows(2) {
+            runs.last_mut().unwrap().push(pair[0]);
+            if points[pair[0]].distance(&points[pair[1]]) > self.max_gap {
+                runs.push(Vec::new());
+            }
+        }
+        runs.last_mut().unwrap().push(order[count - 1]);
+
+        let mut pieces = Vec::new();
+        for run in runs {
+            self.split(points, &run, &mut pieces);
+        }
+
+        // merge neighbors that still fit on one line
+        let mut merged: Vec<Vec<usize>> = Vec::new();
+        for piece in pieces {
+            if let Some(last) = merged.last_mut() {
+                // pieces split apart share the point they were split at
+                let shared = usize::from(piece[0] == last[last.len() - 1]);
+                let joined: Vec<usize> = last.iter().chain(&piece[shared..]).copied().collect();
+                let close =
+                    points[last[last.len() - 1]].distance(&points[piece[0]]) <= self.max_gap;
+                if close && max_line_distance(points, &joined) <= self.distance {
+                    *last = joined;
+                    continue;
+                }
+            }
+            merged.push(piece);
+        }
+        // a closed loop without gaps is still cut at ±π, join it back
+        if merged.len() > 1 {
+            let (first, last) = (&merged[0], &merged[merged.len() - 1]);
+            let joined: Vec<usize> = last.iter().chain(first).copied().collect();
+            let close = points[last[last.len() - 1]].distance(&points[first[0]]) <= self.max_gap;
+            if close && max_line_distance(points, &joined) <= self.distance {
+                merged[0] = joined;
+                merged.pop();
+            }
+        }
+        merged.retain(|piece| piece.len() >= self.min_points);
+        merged
+    }
+
+    fn split(&self, points: &[CartesianPoint], run: &[usize], pieces: &mut Vec<Vec<usize>>) {
+        if run.len() < self.min_points {
+            return;
+        }
+        let (first, last) = (points[run[0]], points[run[run.len() - 1]]);
+        let (furthest, distance) = run
+            .iter()
+            .enumerate()
+            .map(|(i, &point)| (i, chord_distance(&first, &last, &points[point])))
+            .max_by(|a, b| a.1.total_cmp(&b.1))
+            .unwrap();
+        if distance > self.distance && furthest > 0 && furthest < run.len() - 1 {
+            self.split(points, &run[..=furthest], pieces);
+            self.split(points, &run[furthest..], pieces);
+        } else {
+            pieces.push(run.to_vec());
+        }
+    }
+
+    fn ransac(&self, points: &[CartesianPoint]) -> Vec<Vec<usize>> {
+        let mut random = XorShift::new(self.seed);
+        let mut remaining: Vec<usize> = (0..points.len()).collect();
+        let mut segments = Vec::new();
+        while remaining.len() >= self.min_points {
+            let mut best: Vec<usize> = Vec::new();
+            for _ in 0..self.iterations {
+                let a = points[remaining[random.below(remaining.len())]];
+                let b = points[remaining[random.below(remaining.len())]];
+                if a.distance(&b) == 0.0 {
+                    continue;
+                }
+                let inliers: Vec<usize> = remaining
+                    .iter()
+                    .copied()
+                    .filter(|&i| chord_distance(&a, &b, &points[i]) <= self.distance)
+                    .collect();
+                if inliers.len() > best.len() {
+                    best = inliers;
+                }
+            }
+            if best.len() < self.min_points {
+                break;
+            }
+
+            // refit, then cut the line where it crosses gaps
+            let (center, direction) = fit_line(points, &best);
+            let normal = CartesianPoint::new(-direction.y, direction.x);
+            let mut inliers: Vec<(f64, usize)> = remaining
+                .iter()
+                .copied()
+                .filter(|&i| project(&points[i], &center, &normal).abs() <= self.distance)
+                .map(|i| (project(&points[i], &center, &direction), i))
+                .collect();
+            if inliers.len() < best.len() {
+                inliers = best
+                    .iter()
+                    .map(|&i| (project(&points[i], &center, &direction), i))
+                    .collect();
+            }
+            inliers.sort_by(|a, b| a.0.total_cmp(&b.0));
+            let mut run: Vec<usize> = Vec::new();
+            for (k, &(along, i)) in inliers.iter().enumerate() {
+                if k > 0 && along - inliers[k - 1].0 > self.max_gap {
+                    if run.len() >= self.min_points {
+                        segments.push(run.clone());
+                    }
+                    run.clear();
+                }
+                run.push(i);
+            }
+            if run.len() >= self.min_points {
+                segments.push(run);
+            }
+
+            let used: Vec<usize> = inliers.iter().map(|&(_, i)| i).collect();
+            remaining.retain(|i| !used.contains(i));
+        }
+        // scan order, like split and merge gives them
+        for segment in segments.iter_mut() {
+            segment.sort_unstable();
+        }
+        segments.sort_by_key(|segment| segment[0]);
+        segments
+    }
+}
+
+// small fast generator, good enough for picking points
+struct XorShift(u64);
+
+impl XorShift {
+    fn new(seed: u64) -> XorShift {
+        // xorshift never leaves zero
+        XorShift(seed.max(1))
+    }
+
+    fn next(&mut self) -> u64 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        self.0
+    }
+
+    fn below(&mut self, bound: usize) -> usize {
+        (self.next() % bound as u64) as usize
+    }
+}
+
+fn project(point: &CartesianPoint, origin: &CartesianPoint, axis: &CartesianPoint) -> f64 {
+    (point.x - origin.x) * axis.x + (point.y - origin.y) * axis.y
+}
+
+// distance from the point to the line through a and b
+fn chord_distance(a: &CartesianPoint, b: &CartesianPoint, point: &CartesianPoint) -> f64 {
+    let length = a.distance(b);
+    if length == 0.0 {
+        return a.distance(point);
+    }
+    ((b.x - a.x) * (a.y - point.y) - (a.x - point.x) * (b.y - a.y)).abs() / length
+}
+
+// total least squares line, as its centroid and unit direction
+fn fit_line(points: &[CartesianPoint], members: &[usize]) -> (CartesianPoint, CartesianPoint) {
+    let count = members.len() as f64;
+    let (x, y) = members.iter().fold((0.0, 0.0), |(x, y), &i| {
+        (x + points[i].x / count, y + points[i].y / count)
+    });
+    let (xx, xy, yy) = members.iter().fold((0.0, 0.0, 0.0), |(xx, xy, yy), &i| {
+        let (dx, dy) = (points[i].x - x, points[i].y - y);
+        (xx + dx * dx, xy + dx * dy, yy + dy * dy)
+    });
+    let angle = 0.5 * (2.0 * xy).atan2(xx - yy);
+    (
+        CartesianPoint::new(x, y),
+        CartesianPoint::new(angle.cos(), angle.sin()),
+    )
+}
+
+fn max_line_distance(points: &[CartesianPoint], members: &[usize]) -> f64 {
+    let (center, direction) = fit_line(points, members);
+    let normal = CartesianPoint::new(-direction.y, direction.x);
+    members
+        .iter()
+        .map(|&i| project(&points[i], &center, &normal).abs())
+        .fold(0.0, f64::max)
+}
+
+// the fitted line cut at the projections of the outermost members,
+// point indices are left to the caller
+fn fit_segment(points: &[CartesianPoint], members: &[usize]) -> LineSegment {
+    let (center, direction) = fit_line(points, members);
+    let normal = CartesianPoint::new(-direction.y, direction.x);
+    let along = members
+        .iter()
+        .map(|&i| project(&points[i], &center, &direction));
+    let (low, high) = along.fold((f64::INFINITY, f64::NEG_INFINITY), |(low, high), t| {
+        (low.min(t), high.max(t))
+    });
+    let squared: f64 = members
+        .iter()
+        .map(|&i| project(&points[i], &center, &normal).powi(2))
+        .sum();
+    let at = |t: f64| CartesianPoint::new(center.x + t * direction.x, center.y + t * direction.y);
+    // keep the ends in the order of the members
+    let (start, end) = if project(&points[members[0]], &center, &direction)
+        <= project(&points[members[members.len() - 1]], &center, &direction)
+    {
+        (at(low), at(high))
+    } else {
+        (at(high), at(low))
+    };
+    LineSegment {
+        start,
+        end,
+        error: (squared / members.len() as f64).sqrt(),
+        points: Vec::new(),
+    }
+}
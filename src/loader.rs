@@ -0,0 +1,281 @@
+use crate::error::ParseError;
+use crate::input::{open_stdin, Compression, STDIN_PATH, STDIN_SOURCE};
+use crate::ptfl_binary::is_binary;
+use crate::ptfl_parse::{collect_scans, read_scans, source_name, ParseReport, ScanReader};
+use crate::scan::{Scan, ScanSet};
+use rayon::prelude::*;
+use std::collections::HashMap;
+use std::fs::File;
+use std::io;
+use std::io::{BufRead, BufReader, Read, Seek, SeekFrom};
+
+/// How loading one file went. Scans read before a failure
+/// are still added, the same as [`crate::PtflParser::parse`] does.
+#[derive(Debug)]
+pub struct FileReport {
+    pub path: String,
+    pub result: Result<ParseReport, ParseError>,
+}
+
+/// Loads many pointfiles in parallel.
+/// Plain text files larger than the split threshold are also
+/// cut at scan boundaries and their pieces parsed in parallel.
+/// Scans are always added in the order of the given paths,
+/// and in file order within each file.
+/// A path of `-` reads stdin as described in [`crate::open_stdin`].
+///
+/// Scans are named after the file name of their path, so a path named
+/// the same as one before it, like `23.gz` after `23`, isn't loaded
+/// and is reported as [`ParseError::DuplicateSource`] instead.
+pub struct Loader {
+    recovering: bool,
+    split_threshold: u64,
+}
+
+impl Default for Loader {
+    fn default() -> Loader {
+        Loader::new()
+    }
+}
+
+impl Loader {
+    pub fn new() -> Loader {
+        Loader {
+            recovering: false,
+            split_threshold: 2 * 1024 * 1024,
+        }
+    }
+
+    /// Skip malformed lines like [`crate::PtflParser::parse_recovering`].
+    pub fn recovering(mut self, recovering: bool) -> Loader {
+        self.recovering = recovering;
+        self
+    }
+
+    /// Size in bytes above which a single file is split, 2 MiB by default.
+    pub fn split_threshold(mut self, split_threshold: u64) -> Loader {
+        self.split_threshold = split_threshold;
+        self
+    }
+
+    pub fn load(&self, paths: &[String], target: &mut ScanSet) -> Vec<FileReport> {
+        let mut named: HashMap<String, &str> = HashMap::new();
+        let duplicates: Vec<Option<ParseError>> = paths
+            .iter()
+            .map(|path| {
+                let name = match path.as_str() {
+                    STDIN_PATH => STDIN_SOURCE.to_string(),
+                    path => source_name(path),
+                };
+                match named.get(&name) {
+                    Some(other) => Some(ParseError::DuplicateSource {
+                        path: path.to_string(),
+                        name,
+                        other: other.to_string(),
+                    }),
+                    None => {
+                        named.insert(name, path);
+                        None
+                    }
+                }
+            })
+            .collect();
+
+        let loaded: Vec<(Vec<Scan>, FileReport)> = paths
+            .par_iter()
+            .zip(duplicates)
+            .map(|(path, duplicate)| {
+                let mut scans = Vec::new();
+                let result = match duplicate {
+                    Some(err) => Err(err),
+                    None => self.load_file(path, &mut scans),
+                };
+                (
+                    scans,
+                    FileReport {
+                        path: path.to_string(),
+                        result,
+                    },
+                )
+            })
+            .collect();
+
+        let mut reports = Vec::with_capacity(loaded.len());
+        for (scans, report) in loaded {
+            target.extend(scans);
+            reports.push(report);
+        }
+        reports
+    }
+
+    fn load_file(&self, path: &str, scans: &mut Vec<Scan>) -> Result<ParseReport, ParseError> {
+        if path == STDIN_PATH {
+            let stdin = open_stdin().map_err(|err| ParseError::Io {
+                path: STDIN_SOURCE.to_string(),
+                source: err,
+            })?;
+            let mut reader = read_scans(stdin, STDIN_SOURCE, self.recovering)?;
+            let (scans, diagnostics) = collect_scans(&mut reader, scans, self.recovering)?;
+            return Ok(ParseReport { scans, diagnostics });
+        }
+
+        let io_error = |err| ParseError::Io {
+            path: path.to_string(),
+            source: err,
+        };
+        let file = File::open(path).map_err(io_error)?;
+        let size = file.metadata().map_err(io_error)?.len();
+        let mut reader = BufReader::new(file);
+        // only plain text can be cut, tell it by the first bytes
+        if size > self.split_threshold
+            && Compression::detect(path, &mut reader).map_err(io_error)? == Compression::None
+            && !is_binary(&mut reader).map_err(io_error)?
+        {
+            if let Some(result) = self.load_split(path, &mut reader, size, scans) {
+                return result;
+            }
+            reader.rewind().map_err(io_error)?;
+        }
+
+        let mut reader = read_scans(reader, path, self.recovering)?;
+        let (scans, diagnostics) = collect_scans(&mut reader, scans, self.recovering)?;
+        Ok(ParseReport { scans, diagnostics })
+    }
+
+    // None if the text isn't well formed and can't be split,
+    // every chunk is read on its own straight from the file
+    fn load_split<R: BufRead>(
+        &self,
+        path: &str,
+        reader: &mut R,
+        size: u64,
+        scans: &mut Vec<Scan>,
+    ) -> Option<Result<ParseReport, ParseError>> {
+        let boundaries = match scan_boundaries(reader) {
+            Ok(boundaries) => boundaries?,
+            Err(err) => {
+                return Some(Err(ParseError::Io {
+                    path: path.to_string(),
+                    source: err,
+                }))
+            }
+        };
+
+        // group scans into a chunk per thread
+        let chunk_size = size / rayon::current_num_threads().max(1) as u64 + 1;
+        let mut chunks: Vec<(u64, usize, u32)> = Vec::new();
+        for (index, &(offset, line_num)) in boundaries.iter().enumerate() {
+            match chunks.last() {
+                Some(&(start, _, _)) if offset - start < chunk_size => {}
+                _ => chunks.push((offset, line_num, index as u32)),
+            }
+        }
+
+        let parsed: Vec<(Vec<Scan>, Result<_, _>)> = chunks
+            .par_iter()
+            .enumerate()
+            .map(|(i, &(start, line_num, index))| {
+                let end = match chunks.get(i + 1) {
+                    Some(&(end, _, _)) => end,
+                    None => size,
+                };
+                let mut chunk_scans = Vec::new();
+                let result = open_chunk(path, start, end).and_then(|chunk| {
+                    let mut reader = ScanReader::new(chunk, path)
+                        .recovering(self.recovering)
+                        .starting_at(line_num, index);
+                    collect_scans(&mut reader, &mut chunk_scans, self.recovering)
+                });
+                (chunk_scans, result)
+            })
+            .collect();
+
+        let mut report = ParseReport::default();
+        for (mut chunk_scans, result) in parsed {
+            report.scans += chunk_scans.len() as u32;
+            scans.append(&mut chunk_scans);
+            match result {
+                Ok((_, mut diagnostics)) => report.diagnostics.append(&mut diagnostics),
+                Err(err) => return Some(Err(err)),
+            }
+        }
+        Some(Ok(report))
+    }
+}
+
+// the bytes of the file from start to end
+fn open_chunk(path: &str, start: u64, end: u64) -> Result<impl BufRead, ParseError> {
+    let io_error = |err| ParseError::Io {
+        path: path.to_string(),
+        source: err,
+    };
+    let mut file = File::open(path).map_err(io_error)?;
+    file.seek(SeekFrom::Start(start)).map_err(io_error)?;
+    Ok(BufReader::new(file.take(end - start)))
+}
+
+// byte offset and number of lines before every scan and its metadata,
+// None if anything doesn't look like a well formed pointfile
+fn scan_boundaries<R: BufRead>(reader: R) -> io::Result<Option<Vec<(u64, usize)>>> {
+    let mut boundaries = Vec::new();
+    let mut lines = LineOffsets {
+        reader,
+        offset: 0,
+        line: Vec::new(),
+    };
+    let mut line_num: usize = 0;
+    // where the metadata lines before the next count header start
+    let mut header_start = None;
+    while let Some(offset) = lines.next_line()? {
+        let line = &lines.line;
+        if line.is_empty() || line == b"\r" {
+            line_num += 1;
+            continue;
+        }
+        if line.starts_with(b"#") {
+            header_start.get_or_insert((offset, line_num));
+            line_num += 1;
+            continue;
+        }
+        let count = std::str::from_utf8(line)
+            .ok()
+            .and_then(|line| line.trim().parse::<usize>().ok());
+        let count = match count {
+            Some(0) | None => return Ok(None),
+            Some(count) => count,
+        };
+        boundaries.push(header_start.take().unwrap_or((offset, line_num)));
+        line_num += 1;
+        for _ in 0..count {
+            if lines.next_line()?.is_none() {
+                break;
+            }
+            line_num += 1;
+        }
+    }
+    Ok(Some(boundaries))
+}
+
+// reads lines without their '\n', keeping track of their byte offset
+struct LineOffsets<R: BufRead> {
+    reader: R,
+    offset: u64,
+    line: Vec<u8>,
+}
+
+impl<R: BufRead> LineOffsets<R> {
+    // read the next line into self.line, returning its offset
+    fn next_line(&mut self) -> io::Result<Option<u64>> {
+        self.line.clear();
+        let start = self.offset;
+        let read = self.reader.read_until(b'\n', &mut self.line)?;
+        if read == 0 {
+            return Ok(None);
+        }
+        self.offset += read as u64;
+        if self.line.ends_with(b"\n") {
+            self.line.pop();
+        }
+        Ok(Some(start))
+    }
+}
@@ -1,14 +1,51 @@
-use indexmap::IndexMap;
+use ptfl_reader::average;
+use ptfl_reader::is_fifo;
+use ptfl_reader::median_filter;
+use ptfl_reader::open_scans;
+use ptfl_reader::remove_isolated;
+use ptfl_reader::remove_statistical_outliers;
+use ptfl_reader::BinaryWriter;
 use ptfl_reader::Config;
+use ptfl_reader::Icp;
+use ptfl_reader::IcpMethod;
+use ptfl_reader::IntensityStyle;
+use ptfl_reader::Interpolation;
+use ptfl_reader::LandmarkDetector;
+use ptfl_reader::LandmarkKind;
+use ptfl_reader::LineExtractor;
+use ptfl_reader::LineMethod;
+use ptfl_reader::Loader;
+use ptfl_reader::OccupancyGrid;
 use ptfl_reader::PNGOutput;
-use ptfl_reader::PtflParser;
+use ptfl_reader::PathExpander;
+use ptfl_reader::Point;
+use ptfl_reader::PointFilter;
+use ptfl_reader::Pose;
+use ptfl_reader::PtflWriter;
+use ptfl_reader::Resampler;
 use ptfl_reader::SVGOutput;
+use ptfl_reader::Scan;
+use ptfl_reader::ScanReport;
+use ptfl_reader::ScanSet;
+use ptfl_reader::ScanStream;
 use ptfl_reader::TevWrappedClient;
+use ptfl_reader::Trajectory;
+use ptfl_reader::ValidationMode;
+use ptfl_reader::Validator;
+use ptfl_reader::STDIN_SOURCE;
 use rayon::prelude::*;
-use std::cmp::Ordering;
 use std::env;
+use std::fs;
+use std::fs::File;
 use std::io;
+use std::io::BufWriter;
 use std::io::Write;
+use std::sync::mpsc;
+use std::thread;
+use std::time::Duration;
+
+// how often followed streams are merged while waiting for a command
+const COMMAND_POLL: Duration = Duration::from_millis(200);
 
 fn main() {
     // process command line arguments
@@ -28,50 +65,277 @@ fn main() {
     }
 
     // parse and load files specified in command line
-    let mut point_files: IndexMap<(String, u32), Vec<(f64, f64)>> = IndexMap::new();
-    let mut parser = PtflParser::new();
-    for path in &config.filenames {
-        match parser.parse(path.as_str(), &mut point_files) {
-            Ok(count) => {
-                println!("Read {count} from {}.", path.as_str());
-                println!("Currently {} regs!", point_files.len())
+    let mut point_files = ScanSet::new();
+    let mut streams = Vec::new();
+    let validator = config.validation.map(|mode| Validator::new().mode(mode));
+    load_files(
+        &config.filenames,
+        &mut point_files,
+        &mut streams,
+        config.lenient,
+        validator.as_ref(),
+    );
+
+    for path in &config.follow {
+        println!("Following {}.", path);
+        streams.push(ScanStream::follow(path, config.lenient));
+    }
+
+    tui_loop(point_files, streams, config);
+}
+
+// parse files in parallel into point_files, printing how each went
+// and validating the new scans if given a validator,
+// named pipes are added to streams to be read as scans arrive
+fn load_files(
+    paths: &[String],
+    point_files: &mut ScanSet,
+    streams: &mut Vec<ScanStream>,
+    lenient: bool,
+    validator: Option<&Validator>,
+) {
+    let (fifos, paths): (Vec<String>, Vec<String>) =
+        paths.iter().cloned().partition(|path| is_fifo(path));
+    for fifo in fifos {
+        println!("Reading {} as scans arrive.", fifo);
+        streams.push(ScanStream::open(&fifo, lenient));
+    }
+
+    let mut loaded = ScanSet::new();
+    let reports = Loader::new().recovering(lenient).load(&paths, &mut loaded);
+    for report in &reports {
+        match &report.result {
+            Ok(result) => {
+                println!("Read {} from {}.", result.scans, report.path);
+                if !result.diagnostics.is_empty() {
+                    println!("Skipped {} problem(s):", result.diagnostics.len());
+                    for diagnostic in &result.diagnostics {
+                        println!("\t{diagnostic}");
+                    }
+                }
             }
             Err(err) => {
-                // skip the file error occur
-                // and reset the state of parser
-                println!("Error happened parsing file {path}: ");
+                // scans before the error are kept
+                println!("Error happened parsing file {}: ", report.path);
                 println!("\t{err}");
-                parser.renew();
             }
         }
     }
 
-    tui_loop(point_files, config);
+    // stdin can be loaded many times, number its scans after the ones before
+    let stdin_start = point_files
+        .by_source(STDIN_SOURCE)
+        .map(|scan| scan.index() + 1)
+        .max()
+        .unwrap_or(0);
+    if stdin_start > 0 {
+        loaded = loaded
+            .into_iter()
+            .map(|scan| {
+                if scan.source() == STDIN_SOURCE {
+                    let index = scan.index() + stdin_start;
+                    scan.with_key(STDIN_SOURCE, index)
+                } else {
+                    scan
+                }
+            })
+            .collect();
+    }
+
+    if let Some(validator) = validator {
+        print_validation(&validator.validate_set(&mut loaded));
+    }
+    point_files.extend(loaded);
+    println!("Currently {} regs!", point_files.len())
+}
+
+// add the scans that arrived from streams since the last command
+// true if anything was printed
+fn receive_streams(streams: &mut Vec<ScanStream>, point_files: &mut ScanSet) -> bool {
+    let mut printed = false;
+    for stream in streams.iter_mut() {
+        let mut count: u32 = 0;
+        for scan in stream.receive() {
+            match scan {
+                Ok(scan) => {
+                    point_files.insert(scan);
+                    count += 1;
+                }
+                Err(err) => {
+                    println!("Error happened reading {}: ", stream.path());
+                    println!("\t{err}");
+                }
+            }
+        }
+        if count > 0 {
+            println!("Received {} from {}.", count, stream.path());
+            printed = true;
+        }
+        if stream.is_finished() {
+            println!("{} is closed.", stream.path());
+            printed = true;
+        }
+    }
+    streams.retain(|stream| !stream.is_finished());
+    printed
+}
+
+// reads a command line on its own thread whenever one is asked for,
+// so followed streams can be merged while waiting for it, and stdin
+// is left alone in between for commands that read scans from it
+struct CommandReader {
+    requests: mpsc::Sender<()>,
+    lines: mpsc::Receiver<Option<String>>,
+}
+
+impl CommandReader {
+    fn new() -> CommandReader {
+        let (requests, requested) = mpsc::channel::<()>();
+        let (sender, lines) = mpsc::channel();
+        thread::spawn(move || {
+            while requested.recv().is_ok() {
+                let mut line = String::new();
+                let read = io::stdin()
+                    .read_line(&mut line)
+                    .expect("Reading line from terminal failed");
+                if sender.send((read > 0).then_some(line)).is_err() {
+                    break;
+                }
+            }
+        });
+        CommandReader { requests, lines }
+    }
+
+    // None once stdin is closed, waiting is called regularly until the line arrives
+    fn read_line(&self, mut waiting: impl FnMut()) -> Option<String> {
+        self.requests.send(()).ok()?;
+        loop {
+            match self.lines.recv_timeout(COMMAND_POLL) {
+                Ok(line) => return line,
+                Err(mpsc::RecvTimeoutError::Timeout) => waiting(),
+                Err(mpsc::RecvTimeoutError::Disconnected) => return None,
+            }
+        }
+    }
+}
+
+// shows every scan it's called with in tev, for follow --tev,
+// rendering happens on its own thread and skips to the newest scan
+fn tev_follower() -> impl FnMut(&Scan) + Send + 'static {
+    let (sender, receiver) = mpsc::channel::<Scan>();
+    thread::spawn(move || {
+        let mut tev = TevWrappedClient::new();
+        let mut opened = false;
+        while let Ok(mut scan) = receiver.recv() {
+            while let Ok(newer) = receiver.try_recv() {
+                scan = newer;
+            }
+
+            let path = format!("/tmp/{}-follow.png", scan.source());
+            let mut png_output = PNGOutput::new();
+            png_output.add_points(&scan, 2.0, 500.0, 222.0, 50);
+            if let Err(err) = png_output.to_pixmap(2.0, 500.0).save_png(&path) {
+                println!("Failed saving to file {}:\n\t{}", path, err);
+                continue;
+            }
+            let result = if opened {
+                tev.reload_image(path)
+            } else {
+                tev.open_image(path)
+            };
+            match result {
+                Ok(_) => opened = true,
+                Err(err) => println!("Failed opening image:\n\t{}", err),
+            }
+        }
+    });
+    move |scan: &Scan| {
+        let _ = sender.send(scan.clone());
+    }
+}
+
+// print the scans with issues and a summary
+fn print_validation(reports: &[ScanReport]) {
+    const SHOWN_ISSUES: usize = 5;
+    let mut with_issues = 0;
+    let mut rejected = 0;
+    let mut fixed = 0;
+    for report in reports {
+        fixed += report.fixed;
+        if report.rejected {
+            rejected += 1;
+        }
+        if report.issues.is_empty() {
+            continue;
+        }
+        with_issues += 1;
+        println!(
+            "{}-{:04}: {} issue(s){}",
+            report.source,
+            report.index,
+            report.issues.len(),
+            if report.rejected { ", rejected" } else { "" }
+        );
+        for issue in report.issues.iter().take(SHOWN_ISSUES) {
+            println!("\t{issue}");
+        }
+        if report.issues.len() > SHOWN_ISSUES {
+            println!("\t... and {} more", report.issues.len() - SHOWN_ISSUES);
+        }
+    }
+    println!(
+        "Validated {} entries, {} with issues, {} rejected, {} points fixed",
+        reports.len(),
+        with_issues,
+        rejected,
+        fixed
+    );
 }
 
 fn print_args_help() {
-    println!("ptfl_reader [files] [--help]");
-    println!("    files: one or multiple file path as input");
+    println!("ptfl_reader [paths] [--help]");
+    println!("    paths: one or multiple file path as input, may be gzip or zstd compressed");
+    println!("           directories are loaded recursively, glob patterns are expanded");
+    println!("           - reads stdin until its end or a line with only .");
+    println!("           named pipes are read in the background as scans arrive");
     println!("    --help: print this message");
     println!("    --no-prompt: prevent gui loop outputing \t and \"> \" ");
-    
+    println!("    --lenient: skip malformed lines instead of dropping the rest of the file");
+    println!("    --include PATTERN: only load matching files from directories and patterns");
+    println!("    --exclude PATTERN: don't load matching files from directories and patterns");
+    println!("    --validate MODE: check loaded entries, MODE is report, reject, clamp or drop");
+    println!("    --follow FILE: keep adding scans appended to a file being recorded");
 }
 
 // the main loop of tui interface
-fn tui_loop(mut point_files: IndexMap<(String, u32), Vec<(f64, f64)>>, config: Config) {
+// commands are dispatched by their length first, then by name
+#[allow(clippy::collapsible_match)]
+fn tui_loop(mut point_files: ScanSet, mut streams: Vec<ScanStream>, config: Config) {
     let mut tev: TevWrappedClient = TevWrappedClient::new();
-    loop {
-        // prompt the user to input something
+    let commands = CommandReader::new();
+    let prompt = || {
         if !config.no_prompt {
             print!("> ");
         }
         io::stdout().flush().unwrap();
+    };
+    loop {
+        // prompt the user to input something
+        prompt();
 
-        // read in the command
-        let mut input = String::new();
-        io::stdin()
-            .read_line(&mut input)
-            .expect("Reading line from terminal failed");
+        // read in the command, merging scans as they arrive meanwhile
+        let input = commands.read_line(|| {
+            if receive_streams(&mut streams, &mut point_files) {
+                prompt();
+            }
+        });
+        // stdin is closed, there will be no more commands
+        let input = match input {
+            Some(input) => input,
+            None => break,
+        };
+        // scans arrived since the last poll are ready for the command
+        receive_streams(&mut streams, &mut point_files);
 
         // break down the input
         let input: Vec<&str> = input.trim().split(' ').collect();
@@ -98,7 +362,7 @@ fn tui_loop(mut point_files: IndexMap<(String, u32), Vec<(f64, f64)>>, config: C
                 if command == "tev" {
                     fn prompt() {
                         println!("tev entry_name entry_num");
-                        println!("");
+                        println!();
                     }
 
                     match tev.start_client() {
@@ -115,19 +379,16 @@ fn tui_loop(mut point_files: IndexMap<(String, u32), Vec<(f64, f64)>>, config: C
                                 Ok(entry_num) => entry_num,
                                 Err(err) => {
                                     prompt();
-                                    println!(
-                                        "Error happened parsing entry_num: \n\t{}",
-                                        err.to_string()
-                                    );
+                                    println!("Error happened parsing entry_num: \n\t{}", err);
                                     continue;
                                 }
                             },
                         );
 
-                        match point_files.get(&key) {
+                        match point_files.get(&key.0, key.1) {
                             Some(entry) => {
                                 let mut png_output = PNGOutput::new();
-                                png_output.add_points(&entry, 2.0, 500.0, 222.0, 50);
+                                png_output.add_points(entry, 2.0, 500.0, 222.0, 50);
                                 match png_output
                                     .to_pixmap(2.0, 500.0)
                                     .save_png(format!("/tmp/{}-{:04}.png", key.0, key.1))
@@ -143,9 +404,7 @@ fn tui_loop(mut point_files: IndexMap<(String, u32), Vec<(f64, f64)>>, config: C
                                     Err(err) => {
                                         println!(
                                             "Failed saving to file {}-{:04}.png:\n\t{}",
-                                            key.0,
-                                            key.1,
-                                            err.to_string()
+                                            key.0, key.1, err
                                         );
                                     }
                                 }
@@ -167,6 +426,269 @@ fn tui_loop(mut point_files: IndexMap<(String, u32), Vec<(f64, f64)>>, config: C
                     } else {
                         prompt();
                     }
+                } else if command == "map" {
+                    fn prompt() {
+                        println!("map [options] entry_name entry_num file_name");
+                        println!("\tentry_num can be * for every entry of entry_name");
+                        println!("\tentries are placed at the pose in their metadata, or");
+                        println!("\tat the origin without one");
+                        println!("\ta map_server style file_name.yaml is written next to the map");
+                        println!("options:");
+                        println!("\t--pgm:\t\t\t(DEFAULT)output in PGM format");
+                        println!("\t--png:\t\t\toutput in PNG format");
+                        println!("\t--resolution SIZE:\t(DEFAULT=0.05)cell size in meters");
+                        println!(
+                            "\t--max-range RANGE:\t(DEFAULT=8)how far missing returns are free"
+                        );
+                        println!("\t--odometry:\t\tplace entries by registering them in order");
+                        println!();
+                    }
+
+                    let mut grid = OccupancyGrid::new();
+                    let mut png = false;
+                    let mut odometry = false;
+                    let mut next: usize = 1;
+                    let mut bad_option = false;
+                    while next < input.len() && input[next].starts_with("--") {
+                        match input[next] {
+                            "--pgm" => png = false,
+                            "--png" => png = true,
+                            "--odometry" => odometry = true,
+                            "--resolution" | "--max-range" if next + 1 < input.len() => {
+                                next += 1;
+                                match input[next].parse::<f64>() {
+                                    Ok(value) if value > 0.0 => {
+                                        grid = if input[next - 1] == "--resolution" {
+                                            grid.resolution(value)
+                                        } else {
+                                            grid.max_range(value)
+                                        }
+                                    }
+                                    Ok(_) => {
+                                        println!("Expect {} above zero", input[next - 1]);
+                                        bad_option = true;
+                                    }
+                                    Err(err) => {
+                                        println!("Expect f64 after {}, {}", input[next - 1], err);
+                                        bad_option = true;
+                                    }
+                                }
+                            }
+                            _ => bad_option = true,
+                        }
+                        if bad_option {
+                            break;
+                        }
+                        next += 1;
+                    }
+                    if bad_option || input.len() != next + 3 {
+                        prompt();
+                        continue;
+                    }
+
+                    let keys = match tui_entry_keys(&point_files, input[next], input[next + 1]) {
+                        Ok(keys) => keys,
+                        Err(err) => {
+                            prompt();
+                            println!("{err}");
+                            continue;
+                        }
+                    };
+                    let mut entries: Vec<&Scan> = keys
+                        .iter()
+                        .map(|(name, num)| point_files.get(name, *num).unwrap())
+                        .collect();
+                    entries.sort_by_key(|entry| entry.index());
+                    let poses: Vec<Pose> = if odometry {
+                        let icp = Icp::new().method(IcpMethod::PointToLine);
+                        Trajectory::from_scans(&icp, &entries)
+                            .points
+                            .iter()
+                            .map(|point| point.pose)
+                            .collect()
+                    } else {
+                        entries
+                            .iter()
+                            .map(|entry| entry.pose().unwrap_or_default())
+                            .collect()
+                    };
+                    for (entry, pose) in entries.iter().zip(poses) {
+                        grid.insert(entry, pose);
+                    }
+
+                    let file_name = input[next + 2];
+                    let result = if png {
+                        match grid.to_pixmap() {
+                            Some(pixmap) => {
+                                pixmap.save_png(file_name).map_err(|err| err.to_string())
+                            }
+                            None => Err("the map is empty".to_string()),
+                        }
+                    } else {
+                        File::create(file_name)
+                            .and_then(|file| grid.write_pgm(BufWriter::new(file)))
+                            .map_err(|err| err.to_string())
+                    };
+                    if let Err(err) = result {
+                        println!("Failed saving to file {}:\n\t{}", file_name, err);
+                        continue;
+                    }
+                    let yaml = std::path::Path::new(file_name).with_extension("yaml");
+                    // the image is looked up next to the descriptor
+                    let image = std::path::Path::new(file_name)
+                        .file_name()
+                        .map(|name| name.to_string_lossy().to_string())
+                        .unwrap_or_default();
+                    match File::create(&yaml)
+                        .and_then(|file| grid.write_yaml(BufWriter::new(file), &image))
+                    {
+                        Ok(_) => println!(
+                            "Saved {} and {}, {}x{} cells from {} entries",
+                            file_name,
+                            yaml.display(),
+                            grid.width(),
+                            grid.height(),
+                            entries.len()
+                        ),
+                        Err(err) => {
+                            println!("Failed saving to file {}:\n\t{}", yaml.display(), err)
+                        }
+                    }
+                } else if command == "icp" {
+                    fn prompt() {
+                        println!("icp [options] source_name source_num target_name target_num");
+                        println!("\testimates the pose of the source relative to the target");
+                        println!("options:");
+                        println!("\t--line:\t\t\tmatch points to lines instead of points");
+                        println!("\t--iterations COUNT:\t(DEFAULT=50)most iterations to run");
+                        println!("\t--max-distance RANGE:\t(DEFAULT=0.5)furthest points matched");
+                        println!("\t--guess X Y DEGREE:\tinitial pose to start from");
+                        println!("\t--into NAME NUM:\tsave the aligned source as NAME-NUM");
+                        println!();
+                    }
+
+                    let mut icp = Icp::new();
+                    let mut into: Option<(&str, &str)> = None;
+                    let mut next: usize = 1;
+                    let mut bad_option = false;
+                    while next < input.len() && input[next].starts_with("--") {
+                        match input[next] {
+                            "--line" => icp = icp.method(IcpMethod::PointToLine),
+                            "--iterations" if next + 1 < input.len() => {
+                                next += 1;
+                                match input[next].parse::<usize>() {
+                                    Ok(count) => icp = icp.max_iterations(count),
+                                    Err(err) => {
+                                        println!("Expect integer for COUNT, {}", err);
+                                        bad_option = true;
+                                    }
+                                }
+                            }
+                            "--max-distance" if next + 1 < input.len() => {
+                                next += 1;
+                                match input[next].parse::<f64>() {
+                                    Ok(range) => icp = icp.max_distance(range),
+                                    Err(err) => {
+                                        println!("Expect f64 for RANGE, {}", err);
+                                        bad_option = true;
+                                    }
+                                }
+                            }
+                            "--guess" if next + 3 < input.len() => {
+                                let values: Result<Vec<f64>, _> = input[next + 1..next + 4]
+                                    .iter()
+                                    .map(|value| value.parse::<f64>())
+                                    .collect();
+                                match values {
+                                    Ok(values) => {
+                                        icp = icp.initial(Pose::new(
+                                            values[0],
+                                            values[1],
+                                            values[2].to_radians(),
+                                        ))
+                                    }
+                                    Err(err) => {
+                                        println!("Expect f64 for X, Y and DEGREE, {}", err);
+                                        bad_option = true;
+                                    }
+                                }
+                                next += 3;
+                            }
+                            "--into" if next + 2 < input.len() => {
+                                into = Some((input[next + 1], input[next + 2]));
+                                next += 2;
+                            }
+                            _ => bad_option = true,
+                        }
+                        if bad_option {
+                            break;
+                        }
+                        next += 1;
+                    }
+                    if bad_option || input.len() != next + 4 {
+                        prompt();
+                        continue;
+                    }
+
+                    let mut entries = Vec::new();
+                    for (name, num) in [
+                        (input[next], input[next + 1]),
+                        (input[next + 2], input[next + 3]),
+                    ] {
+                        match num.parse::<u32>() {
+                            Ok(num) => match point_files.get(name, num) {
+                                Some(entry) => entries.push(entry),
+                                None => println!("Entry {}-{:04} didn't exist!", name, num),
+                            },
+                            Err(err) => {
+                                prompt();
+                                println!("Error happened parsing entry_num: \n\t{}", err);
+                            }
+                        }
+                    }
+                    if entries.len() != 2 {
+                        continue;
+                    }
+                    let into = match into.map(|(name, num)| (name, num.parse::<u32>())) {
+                        Some((name, Ok(num))) if point_files.contains(name, num) => {
+                            println!("Entry {}-{:04} already exist!", name, num);
+                            continue;
+                        }
+                        Some((name, Ok(num))) => Some((name, num)),
+                        Some((_, Err(err))) => {
+                            prompt();
+                            println!("Error happened parsing NUM: \n\t{}", err);
+                            continue;
+                        }
+                        None => None,
+                    };
+
+                    let result = icp.align(entries[0], entries[1]);
+                    println!(
+                        "Pose: dx {:.6} dy {:.6} theta {:.4} degree",
+                        result.pose.x,
+                        result.pose.y,
+                        result.pose.theta.to_degrees()
+                    );
+                    println!(
+                        "Residual {:.6} over {} matched points, {} after {} iterations",
+                        result.residual,
+                        result.matches,
+                        if result.converged {
+                            "converged"
+                        } else {
+                            "not converged"
+                        },
+                        result.iterations
+                    );
+                    if let Some((name, num)) = into {
+                        let mut aligned = entries[0].clone().with_key(name, num);
+                        aligned.transform(result.pose);
+                        point_files.insert(aligned);
+                        println!("Saved the aligned source as {}-{:04}", name, num);
+                    }
+                } else {
+                    print_tui_help();
                 }
             }
             4 => {
@@ -174,39 +696,91 @@ fn tui_loop(mut point_files: IndexMap<(String, u32), Vec<(f64, f64)>>, config: C
                     break;
                 } else if command == "list" {
                     println!("Listing entries:");
-                    for i in &point_files {
-                        println!("\t{}-{:04}: {:8} points", i.0 .0, i.0 .1, i.1.len());
+                    for scan in &point_files {
+                        let mut line = format!(
+                            "\t{}-{:04}: {:8} points",
+                            scan.source(),
+                            scan.index(),
+                            scan.len()
+                        );
+                        if let Some(timestamp) = scan.timestamp {
+                            line += &format!("  t={}", timestamp);
+                        }
+                        if let Some(sensor) = scan.sensor_id() {
+                            line += &format!("  sensor={}", sensor);
+                        }
+                        println!("{}", line);
                     }
-                    println!("");
+                    println!();
                 } else if command == "load" {
                     fn prompt() {
-                        println!("load filename1 [filename2] [filename3] ...");
-                        println!("");
+                        println!("load [options] path1 [path2] [path3] ...");
+                        println!("\tpaths can be files, directories or glob patterns");
+                        println!("\t- reads stdin until a line with only .");
+                        println!("options:");
+                        println!("\t--lenient:\t\tskip malformed lines");
+                        println!("\t--include PATTERN:\tonly load matching files");
+                        println!("\t--exclude PATTERN:\tdon't load matching files");
+                        println!("\t--validate MODE:\tcheck loaded entries, see validate");
+                        println!();
                     }
-                    if input.len() < 2 {
-                        prompt();
-                    } else {
-                        let mut parser = PtflParser::new();
-                        for filename in &input[1..] {
-                            match parser.parse(&filename, &mut point_files) {
-                                Ok(count) => {
-                                    println!("Read {count} from {}.", filename);
-                                    println!("Currently {} regs!", point_files.len())
-                                }
-                                Err(err) => {
-                                    // skip the file error occur
-                                    // and reset the state of parser
-                                    println!("Error happened parsing file {filename}: ");
-                                    println!("\t{err}");
-                                    parser.renew();
+
+                    let mut lenient = false;
+                    let mut validator = None;
+                    let mut expander = PathExpander::new();
+                    let mut next: usize = 1;
+                    let mut bad_option = false;
+                    while next < input.len() && input[next].starts_with("--") {
+                        match input[next] {
+                            "--lenient" => lenient = true,
+                            "--include" if next + 1 < input.len() => {
+                                next += 1;
+                                expander = expander.include(input[next]);
+                            }
+                            "--exclude" if next + 1 < input.len() => {
+                                next += 1;
+                                expander = expander.exclude(input[next]);
+                            }
+                            "--validate" if next + 1 < input.len() => {
+                                next += 1;
+                                match input[next].parse::<ValidationMode>() {
+                                    Ok(mode) => validator = Some(Validator::new().mode(mode)),
+                                    Err(err) => {
+                                        println!("{err}");
+                                        bad_option = true;
+                                    }
                                 }
                             }
+                            _ => break,
+                        }
+                        if bad_option {
+                            break;
+                        }
+                        next += 1;
+                    }
+                    let paths: Vec<String> =
+                        input[next..].iter().map(|path| path.to_string()).collect();
+                    if bad_option || paths.is_empty() {
+                        prompt();
+                        continue;
+                    }
+                    match expander.expand(&paths) {
+                        Ok(filenames) => load_files(
+                            &filenames,
+                            &mut point_files,
+                            &mut streams,
+                            lenient,
+                            validator.as_ref(),
+                        ),
+                        Err(err) => {
+                            prompt();
+                            println!("Error happened finding files:\n\t{err}");
                         }
                     }
                 } else if command == "show" {
                     fn prompt() {
                         println!("show entry_name entry_num");
-                        println!("");
+                        println!();
                     }
                     if input.len() != 3 {
                         prompt();
@@ -217,116 +791,444 @@ fn tui_loop(mut point_files: IndexMap<(String, u32), Vec<(f64, f64)>>, config: C
                                 Ok(entry_num) => entry_num,
                                 Err(err) => {
                                     prompt();
-                                    println!(
-                                        "Error happened parsing entry_num: \n\t{}",
-                                        err.to_string()
-                                    );
+                                    println!("Error happened parsing entry_num: \n\t{}", err);
                                     continue;
                                 }
                             },
                         );
 
-                        match point_files.get(&key) {
+                        match point_files.get(&key.0, key.1) {
                             Some(entry) => {
-                                println!("Yes, {}-{:04} has {} points", &key.0, &key.1, entry.len())
+                                println!(
+                                    "Yes, {}-{:04} has {} points",
+                                    &key.0,
+                                    &key.1,
+                                    entry.len()
+                                );
+                                if let Some(timestamp) = entry.timestamp {
+                                    println!("\ttimestamp: {}", timestamp);
+                                }
+                                for (meta_key, value) in &entry.metadata {
+                                    println!("\t{}: {}", meta_key, value);
+                                }
                             }
                             None => println!("No, {}-{:04} not found", &key.0, &key.1),
                         }
                     }
-                } else {
-                    print_tui_help();
-                }
-            }
-            6 => {
-                if command == "rotate" {
+                } else if command == "save" {
                     fn prompt() {
-                        println!("rotate entry_name entry_num degree");
-                        println!("");
+                        println!("save [--precision DIGITS] file_name [entry_name entry_num]");
+                        println!("\tentry_name entry_num");
+                        println!("\tentry_name entry_num");
+                        println!("\t...");
+                        println!();
                     }
 
-                    if input.len() != 4 {
-                        prompt();
-                    } else {
-                        let angle = match input[3].parse::<f64>() {
-                            Ok(angle) => angle / 180.0 * 3.1415926535,
+                    let mut next: usize = 1;
+                    let mut precision: usize = 6;
+                    if input.len() > 2 && input[1] == "--precision" {
+                        precision = match input[2].parse::<usize>() {
+                            Ok(precision) => precision,
                             Err(err) => {
                                 prompt();
-                                println!(
-                                    "Error happened parsing entry_num: \n\t{}",
-                                    err.to_string()
-                                );
+                                println!("Expect usize for DIGITS, {}", err);
                                 continue;
                             }
                         };
+                        next = 3;
+                    }
 
-                        if input[2] == "*" && {
-                            let mut iter = point_files.iter();
-                            loop {
-                                match iter.next() {
-                                    Some((key, _)) => {
-                                        if key.0 == input[1] {
-                                            break true;
-                                        }
-                                    }
-                                    None => {
-                                        break false;
-                                    }
+                    // save file_name, read entries from following lines
+                    // or save file_name entry_name entry_num|*
+                    let scans: Vec<&Scan> = if input.len() == next + 1 {
+                        tui_get_entry_keys(&point_files, prompt, config.no_prompt)
+                            .iter()
+                            .map(|key| point_files.get(&key.0, key.1).unwrap())
+                            .collect()
+                    } else if input.len() == next + 3 {
+                        if input[next + 2] == "*" {
+                            point_files.by_source(input[next + 1]).collect()
+                        } else {
+                            let entry_num = match input[next + 2].parse::<u32>() {
+                                Ok(entry_num) => entry_num,
+                                Err(err) => {
+                                    prompt();
+                                    println!("Error happened parsing entry_num: \n\t{}", err);
+                                    continue;
                                 }
-                            }
-                        } {
-                            fn rotate_entry(
-                                key: &(String, u32),
-                                entry: &mut Vec<(f64, f64)>,
-                                input: &Vec<&str>,
-                                angle: f64,
-                            ) {
-                                if key.0 == input[1] {
-                                    entry.par_iter_mut().for_each(|mut point| {
-                                        point.0 += angle;
-                                        if point.0 > 3.1415926535 {
-                                            point.0 -= 2.0 * 3.1415926535;
-                                        }
-                                    });
+                            };
+                            point_files
+                                .get(input[next + 1], entry_num)
+                                .into_iter()
+                                .collect()
+                        }
+                    } else {
+                        prompt();
+                        continue;
+                    };
+
+                    if scans.is_empty() {
+                        println!("No entry to save!");
+                        continue;
+                    }
+
+                    let file = match File::create(input[next]) {
+                        Ok(file) => file,
+                        Err(err) => {
+                            println!("Failed creating file {}:\n\t{}", input[next], err);
+                            continue;
+                        }
+                    };
+                    let mut writer = PtflWriter::new(BufWriter::new(file)).precision(precision);
+                    match writer
+                        .write_scans(scans.iter().copied())
+                        .and_then(|_| writer.flush())
+                    {
+                        Ok(_) => {
+                            println!("Saved {} entries to {}", scans.len(), input[next]);
+                        }
+                        Err(err) => {
+                            println!("Failed saving to file {}:\n\t{}", input[next], err);
+                        }
+                    }
+                } else {
+                    print_tui_help();
+                }
+            }
+            5 => {
+                if command == "lines" {
+                    fn prompt() {
+                        println!("lines [options] entry_name entry_num");
+                        println!("\tfinds straight walls in the entry");
+                        println!("options:");
+                        println!("\t--ransac:\t\tsearch with RANSAC instead of split and merge");
+                        println!(
+                            "\t--distance RANGE:\t(DEFAULT=0.03)furthest a point is off its line"
+                        );
+                        println!("\t--max-gap RANGE:\t(DEFAULT=0.1)widest gap within a line");
+                        println!("\t--min-points COUNT:\t(DEFAULT=8)fewest points on a line");
+                        println!("\t--min-length RANGE:\t(DEFAULT=0.2)shortest line");
+                        println!("\t--png FILE:\t\tdraw the entry and lines to FILE");
+                        println!("\t--svg FILE:\t\tdraw the entry and lines to FILE");
+                        println!("\t--scale SCALE:\t\t(DEFAULT=1000)how much pixel for a meter");
+                        println!("\t--clip POS:\t\t(DEFAULT=2)how far to include in the drawing");
+                        println!();
+                    }
+
+                    let mut extractor = LineExtractor::new();
+                    let mut png: Option<&str> = None;
+                    let mut svg: Option<&str> = None;
+                    let mut scale: f64 = 1000.0;
+                    let mut clip_pos: f64 = 2.0;
+                    let mut next: usize = 1;
+                    let mut bad_option = false;
+                    while next < input.len() && input[next].starts_with("--") {
+                        match input[next] {
+                            "--ransac" => extractor = extractor.method(LineMethod::Ransac),
+                            "--distance" | "--max-gap" | "--min-length" | "--scale" | "--clip"
+                                if next + 1 < input.len() =>
+                            {
+                                next += 1;
+                                match input[next].parse::<f64>() {
+                                    Ok(value) => match input[next - 1] {
+                                        "--distance" => extractor = extractor.distance(value),
+                                        "--max-gap" => extractor = extractor.max_gap(value),
+                                        "--min-length" => extractor = extractor.min_length(value),
+                                        "--scale" => scale = value,
+                                        _ => clip_pos = value,
+                                    },
+                                    Err(err) => {
+                                        println!("Expect f64 after {}, {}", input[next - 1], err);
+                                        bad_option = true;
+                                    }
                                 }
                             }
-                            point_files.par_iter_mut().for_each(|x| {
-                                rotate_entry(x.0, x.1, &input, angle);
-                            });
-                        } else {
-                            let key = (
-                                input[1].to_string(),
-                                match input[2].parse::<u32>() {
-                                    Ok(entry_num) => entry_num,
+                            "--min-points" if next + 1 < input.len() => {
+                                next += 1;
+                                match input[next].parse::<usize>() {
+                                    Ok(count) => extractor = extractor.min_points(count),
                                     Err(err) => {
-                                        prompt();
-                                        println!(
-                                            "Error happened parsing entry_num: \n\t{}",
-                                            err.to_string()
-                                        );
-                                        continue;
+                                        println!("Expect integer for COUNT, {}", err);
+                                        bad_option = true;
                                     }
-                                },
-                            );
-
-                            match point_files.get_mut(&key) {
-                                Some(entry) => {
-                                    entry.par_iter_mut().for_each(|mut point| {
-                                        point.0 += angle;
-                                        if point.0 > 3.1415926535 {
-                                            point.0 -= 2.0 * 3.1415926535;
-                                        }
-                                    });
-                                    println!(
-                                        "{}-{:04} has {} points rotated by {} radians",
-                                        &key.0,
-                                        &key.1,
-                                        entry.len(),
-                                        angle
-                                    );
                                 }
-                                None => println!("No, {}-{:04} not found", &key.0, &key.1),
                             }
-                        }
+                            "--png" | "--svg" if next + 1 < input.len() => {
+                                next += 1;
+                                if input[next - 1] == "--png" {
+                                    png = Some(input[next]);
+                                } else {
+                                    svg = Some(input[next]);
+                                }
+                            }
+                            _ => bad_option = true,
+                        }
+                        if bad_option {
+                            break;
+                        }
+                        next += 1;
+                    }
+                    if bad_option || input.len() != next + 2 {
+                        prompt();
+                        continue;
+                    }
+
+                    let entry = match input[next + 1].parse::<u32>() {
+                        Ok(num) => match point_files.get(input[next], num) {
+                            Some(entry) => entry,
+                            None => {
+                                println!("Entry {}-{:04} didn't exist!", input[next], num);
+                                continue;
+                            }
+                        },
+                        Err(err) => {
+                            prompt();
+                            println!("Error happened parsing entry_num: \n\t{}", err);
+                            continue;
+                        }
+                    };
+
+                    let segments = extractor.extract(entry);
+                    for segment in &segments {
+                        println!(
+                            "({:.3}, {:.3}) -> ({:.3}, {:.3}): length {:.3}, {} points, error {:.4}",
+                            segment.start.x,
+                            segment.start.y,
+                            segment.end.x,
+                            segment.end.y,
+                            segment.length(),
+                            segment.points.len(),
+                            segment.error
+                        );
+                    }
+                    println!(
+                        "Found {} lines over {} of {} points",
+                        segments.len(),
+                        segments
+                            .iter()
+                            .map(|segment| segment.points.len())
+                            .sum::<usize>(),
+                        entry.len()
+                    );
+
+                    if let Some(png) = png {
+                        let mut png_output = PNGOutput::new();
+                        png_output.add_points(entry, clip_pos, scale, 0.0, 50);
+                        png_output.add_segments(&segments, clip_pos, scale, 120.0);
+                        match png_output.to_pixmap(clip_pos, scale).save_png(png) {
+                            Ok(_) => println!("Saved {}", png),
+                            Err(err) => println!("Failed saving to file {}:\n\t{}", png, err),
+                        }
+                    }
+                    if let Some(svg) = svg {
+                        let mut svg_output = SVGOutput::new();
+                        svg_output.add_points(entry, clip_pos, scale, 0.0, 50);
+                        svg_output.add_segments(&segments, clip_pos, scale, 120.0);
+                        match svg::save(svg, &svg_output.output_to_empty_document(scale, clip_pos))
+                        {
+                            Ok(_) => println!("Saved {}", svg),
+                            Err(err) => println!("Failed saving to file {}:\n\t{}", svg, err),
+                        }
+                    }
+                } else {
+                    print_tui_help();
+                }
+            }
+            6 => {
+                if command == "follow" {
+                    fn prompt() {
+                        println!("follow [--tev] file_name");
+                        println!("\tadds the scans in the file, then every scan appended to it");
+                        println!("\twithout file_name, lists the files being followed");
+                        println!("options:");
+                        println!("\t--tev:\tshow the latest scan on tev as it arrives");
+                        println!();
+                    }
+
+                    let tev_preview = input.len() > 1 && input[1] == "--tev";
+                    let next = if tev_preview { 2 } else { 1 };
+                    if input.len() == 1 {
+                        println!("Following:");
+                        for stream in &streams {
+                            println!("\t{}", stream.path());
+                        }
+                        println!();
+                    } else if input.len() != next + 1 {
+                        prompt();
+                    } else if !std::path::Path::new(input[next]).is_file() {
+                        prompt();
+                        println!("Given filepath {} is not a file", input[next]);
+                    } else {
+                        let stream = if tev_preview {
+                            ScanStream::follow_with(input[next], config.lenient, tev_follower())
+                        } else {
+                            ScanStream::follow(input[next], config.lenient)
+                        };
+                        streams.push(stream);
+                        println!("Following {}.", input[next]);
+                    }
+                } else if command == "filter" {
+                    fn prompt() {
+                        println!("filter [options] entry_name entry_num");
+                        println!("\tentry_num can be * for every entry of entry_name");
+                        println!("options:");
+                        println!("\t--min-range RANGE:\tdrop returns closer than RANGE");
+                        println!("\t--max-range RANGE:\tdrop returns further than RANGE");
+                        println!(
+                            "\t--sector FROM TO:\tkeep from FROM to TO degree counterclockwise"
+                        );
+                        println!("\t--drop-zero:\t\tdrop points without a return");
+                        println!("\t--into NAME:\t\twrite into NAME-entry_num instead of in place");
+                        println!();
+                    }
+
+                    let mut filter = PointFilter::new();
+                    let mut into: Option<&str> = None;
+                    let mut next: usize = 1;
+                    let mut bad_option = false;
+                    while next < input.len() && input[next].starts_with("--") {
+                        match input[next] {
+                            "--min-range" | "--max-range" if next + 1 < input.len() => {
+                                next += 1;
+                                match input[next].parse::<f64>() {
+                                    Ok(range) if input[next - 1] == "--min-range" => {
+                                        filter = filter.min_range(range)
+                                    }
+                                    Ok(range) => filter = filter.max_range(range),
+                                    Err(err) => {
+                                        println!("Expect f64 after {}, {}", input[next - 1], err);
+                                        bad_option = true;
+                                    }
+                                }
+                            }
+                            "--sector" if next + 2 < input.len() => {
+                                match (
+                                    input[next + 1].parse::<f64>(),
+                                    input[next + 2].parse::<f64>(),
+                                ) {
+                                    (Ok(from), Ok(to)) => {
+                                        filter = filter.sector(from.to_radians(), to.to_radians())
+                                    }
+                                    (Err(err), _) | (_, Err(err)) => {
+                                        println!("Expect f64 for FROM and TO, {}", err);
+                                        bad_option = true;
+                                    }
+                                }
+                                next += 2;
+                            }
+                            "--drop-zero" => filter = filter.drop_zero(true),
+                            "--into" if next + 1 < input.len() => {
+                                next += 1;
+                                into = Some(input[next]);
+                            }
+                            _ => bad_option = true,
+                        }
+                        if bad_option {
+                            break;
+                        }
+                        next += 1;
+                    }
+                    if bad_option || input.len() != next + 2 {
+                        prompt();
+                        continue;
+                    }
+
+                    let keys = match tui_entry_keys(&point_files, input[next], input[next + 1]) {
+                        Ok(keys) => keys,
+                        Err(err) => {
+                            prompt();
+                            println!("{err}");
+                            continue;
+                        }
+                    };
+                    if let Some(into) = into {
+                        if let Some((_, num)) = keys
+                            .iter()
+                            .find(|(_, num)| point_files.contains(into, *num))
+                        {
+                            println!("Entry {}-{:04} already exist!", into, num);
+                            continue;
+                        }
+                    }
+
+                    let mut removed = 0;
+                    for (name, num) in &keys {
+                        match into {
+                            Some(into) => {
+                                let mut scan = point_files
+                                    .get(name, *num)
+                                    .unwrap()
+                                    .clone()
+                                    .with_key(into, *num);
+                                removed += filter.apply(&mut scan);
+                                point_files.insert(scan);
+                            }
+                            None => {
+                                removed += filter.apply(point_files.get_mut(name, *num).unwrap())
+                            }
+                        }
+                    }
+                    println!(
+                        "Filtered {} entries, removed {} points",
+                        keys.len(),
+                        removed
+                    );
+                } else if command == "rotate" {
+                    fn prompt() {
+                        println!("rotate entry_name entry_num degree");
+                        println!("\tentry_num can be * for every entry of entry_name");
+                        println!();
+                    }
+
+                    if input.len() != 4 {
+                        prompt();
+                        continue;
+                    }
+                    let angle = match input[3].parse::<f64>() {
+                        Ok(degree) => degree.to_radians(),
+                        Err(err) => {
+                            prompt();
+                            println!("Expect f64 for degree, {}", err);
+                            continue;
+                        }
+                    };
+                    match tui_for_entries(&mut point_files, input[1], input[2], |entry| {
+                        entry.rotate(angle)
+                    }) {
+                        Ok(count) => println!("Rotated {} entries by {} radians", count, angle),
+                        Err(err) => {
+                            prompt();
+                            println!("{err}");
+                        }
+                    }
+                } else if command == "mirror" {
+                    fn prompt() {
+                        println!("mirror entry_name entry_num axis_degree");
+                        println!("\tmirrors over the line through the sensor at axis_degree");
+                        println!("\tentry_num can be * for every entry of entry_name");
+                        println!();
+                    }
+
+                    if input.len() != 4 {
+                        prompt();
+                        continue;
+                    }
+                    let axis = match input[3].parse::<f64>() {
+                        Ok(degree) => degree.to_radians(),
+                        Err(err) => {
+                            prompt();
+                            println!("Expect f64 for axis_degree, {}", err);
+                            continue;
+                        }
+                    };
+                    match tui_for_entries(&mut point_files, input[1], input[2], |entry| {
+                        entry.mirror(axis)
+                    }) {
+                        Ok(count) => println!("Mirrored {} entries over {} radians", count, axis),
+                        Err(err) => {
+                            prompt();
+                            println!("{err}");
+                        }
                     }
                 } else if command == "output" {
                     fn prompt_multi_entry() {
@@ -339,7 +1241,7 @@ fn tui_loop(mut point_files: IndexMap<(String, u32), Vec<(f64, f64)>>, config: C
                     fn prompt() {
                         println!("output [options] entry_name entry_num [hue]");
                         prompt_multi_entry();
-                        println!("");
+                        println!();
                     }
 
                     fn prompt_options() {
@@ -348,25 +1250,30 @@ fn tui_loop(mut point_files: IndexMap<(String, u32), Vec<(f64, f64)>>, config: C
                         println!("\t--svg:\t\toutput in SVG(Scalable Vector Graphics) format");
                         println!("\t--scale SCALE:\t(DEFAULT=1000)how much pixel for a meter");
                         println!("\t--clip POS:\t(DEFAULT=2)how far to include in the output");
+                        println!("\t--min-intensity VALUE:\tdon't draw points below the intensity");
+                        println!("\t--max-intensity VALUE:\tdon't draw points above the intensity");
+                        println!("\t--color-intensity:\tshade points by their intensity");
                         println!("\t--help:\t\tprint this message");
                     }
 
                     enum OutputType {
-                        SVG,
-                        PNG,
+                        Svg,
+                        Png,
                     }
                     struct OutputOption {
                         output_type: OutputType,
                         scale: f64,
                         clip_pos: f64,
+                        intensity: IntensityStyle,
                         help: bool,
                     }
 
-                    fn parse_options(input: &Vec<&str>) -> Result<(OutputOption, usize), String> {
+                    fn parse_options(input: &[&str]) -> Result<(OutputOption, usize), String> {
                         let mut option: OutputOption = OutputOption {
-                            output_type: OutputType::PNG,
+                            output_type: OutputType::Png,
                             scale: 1000.0,
                             clip_pos: 2.0,
+                            intensity: IntensityStyle::default(),
                             help: false,
                         };
 
@@ -379,10 +1286,10 @@ fn tui_loop(mut point_files: IndexMap<(String, u32), Vec<(f64, f64)>>, config: C
                             if input[next] == "--" {
                                 return Ok((option, next + 1));
                             } else if input[next] == "--png" {
-                                option.output_type = OutputType::PNG;
+                                option.output_type = OutputType::Png;
                                 next += 1;
                             } else if input[next] == "--svg" {
-                                option.output_type = OutputType::SVG;
+                                option.output_type = OutputType::Svg;
                                 next += 1;
                             } else if input[next] == "--scale" {
                                 if input.len() <= next + 1 {
@@ -403,7 +1310,7 @@ fn tui_loop(mut point_files: IndexMap<(String, u32), Vec<(f64, f64)>>, config: C
                                         Err(err) => {
                                             return Err(format!(
                                                 "Expect f64 after --scale, {}",
-                                                err.to_string()
+                                                err
                                             ))
                                         }
                                     };
@@ -424,14 +1331,38 @@ fn tui_loop(mut point_files: IndexMap<(String, u32), Vec<(f64, f64)>>, config: C
                                             }
                                         }
                                         Err(err) => {
-                                            return Err(format!(
-                                                "Expect f64 after --clip, {}",
-                                                err.to_string()
-                                            ))
+                                            return Err(format!("Expect f64 after --clip, {}", err))
                                         }
                                     };
                                     next += 2;
                                 }
+                            } else if input[next] == "--min-intensity"
+                                || input[next] == "--max-intensity"
+                            {
+                                if input.len() <= next + 1 {
+                                    return Err(format!(
+                                        "Expect f64 after {}, getting None",
+                                        input[next]
+                                    ));
+                                }
+                                let value = match input[next + 1].parse() {
+                                    Ok(value) => value,
+                                    Err(err) => {
+                                        return Err(format!(
+                                            "Expect f64 after {}, {}",
+                                            input[next], err
+                                        ))
+                                    }
+                                };
+                                if input[next] == "--min-intensity" {
+                                    option.intensity.min = Some(value);
+                                } else {
+                                    option.intensity.max = Some(value);
+                                }
+                                next += 2;
+                            } else if input[next] == "--color-intensity" {
+                                option.intensity.color = true;
+                                next += 1;
                             } else if input[next] == "--help" {
                                 option.help = true;
                                 next += 1;
@@ -468,7 +1399,7 @@ fn tui_loop(mut point_files: IndexMap<(String, u32), Vec<(f64, f64)>>, config: C
 
                     if input.len() - 1 == next {
                         fn tui_get_entry_keys_and_hue(
-                            point_files: &IndexMap<(String, u32), Vec<(f64, f64)>>,
+                            point_files: &ScanSet,
                             prompt: fn(),
                             no_prompt: bool,
                         ) -> Vec<((String, u32), f64)> {
@@ -490,7 +1421,7 @@ fn tui_loop(mut point_files: IndexMap<(String, u32), Vec<(f64, f64)>>, config: C
                                 // break down the input
                                 let entry_input: Vec<&str> =
                                     entry_input.trim().split(' ').collect();
-                                if entry_input.len() == 1 && entry_input[0].len() == 0 {
+                                if entry_input.len() == 1 && entry_input[0].is_empty() {
                                     break;
                                 }
 
@@ -508,7 +1439,7 @@ fn tui_loop(mut point_files: IndexMap<(String, u32), Vec<(f64, f64)>>, config: C
                                             prompt();
                                             println!(
                                                 "Error happened parsing entry_num: \n\t{}",
-                                                err.to_string()
+                                                err
                                             );
                                             continue;
                                         }
@@ -519,7 +1450,7 @@ fn tui_loop(mut point_files: IndexMap<(String, u32), Vec<(f64, f64)>>, config: C
                                 let hue = if entry_input.len() == 3 {
                                     match entry_input[2].parse::<f64>() {
                                         Ok(hue) => {
-                                            if 0.0 <= hue && hue <= 360.0 {
+                                            if (0.0..=360.0).contains(&hue) {
                                                 hue
                                             } else {
                                                 no_hue += 1;
@@ -528,7 +1459,7 @@ fn tui_loop(mut point_files: IndexMap<(String, u32), Vec<(f64, f64)>>, config: C
                                         }
                                         Err(err) => {
                                             prompt();
-                                            println!("Expect f64 for hue, {}", err.to_string());
+                                            println!("Expect f64 for hue, {}", err);
                                             continue;
                                         }
                                     }
@@ -538,7 +1469,7 @@ fn tui_loop(mut point_files: IndexMap<(String, u32), Vec<(f64, f64)>>, config: C
                                 };
 
                                 // push if the entry exist in point_files
-                                if point_files.contains_key(&entry_key) {
+                                if point_files.contains(&entry_key.0, entry_key.1) {
                                     entry_keys.push((entry_key, hue));
                                 } else {
                                     prompt();
@@ -571,14 +1502,17 @@ fn tui_loop(mut point_files: IndexMap<(String, u32), Vec<(f64, f64)>>, config: C
                         //     combined_entry.append(&mut (point_files.get(&key).unwrap().clone()));
                         // }
 
-                        let keys_and_hues =
-                            tui_get_entry_keys_and_hue(&point_files, prompt_multi_entry, config.no_prompt);
+                        let keys_and_hues = tui_get_entry_keys_and_hue(
+                            &point_files,
+                            prompt_multi_entry,
+                            config.no_prompt,
+                        );
                         match option.output_type {
-                            OutputType::PNG => {
-                                let mut png_output = PNGOutput::new();
+                            OutputType::Png => {
+                                let mut png_output = PNGOutput::new().intensity(option.intensity);
                                 for (entry, hue) in keys_and_hues {
                                     png_output.add_points(
-                                        &point_files.get(&entry).unwrap(),
+                                        point_files.get(&entry.0, entry.1).unwrap(),
                                         option.clip_pos,
                                         option.scale,
                                         hue,
@@ -596,17 +1530,16 @@ fn tui_loop(mut point_files: IndexMap<(String, u32), Vec<(f64, f64)>>, config: C
                                     Err(err) => {
                                         println!(
                                             "Failed saving to file {}:\t\n{}",
-                                            input[next],
-                                            err.to_string()
+                                            input[next], err
                                         );
                                     }
                                 }
                             }
-                            OutputType::SVG => {
-                                let mut svg_output = SVGOutput::new();
+                            OutputType::Svg => {
+                                let mut svg_output = SVGOutput::new().intensity(option.intensity);
                                 for (entry, hue) in keys_and_hues {
                                     svg_output.add_points(
-                                        &point_files.get(&entry).unwrap(),
+                                        point_files.get(&entry.0, entry.1).unwrap(),
                                         option.clip_pos,
                                         option.scale,
                                         hue,
@@ -625,8 +1558,7 @@ fn tui_loop(mut point_files: IndexMap<(String, u32), Vec<(f64, f64)>>, config: C
                                     Err(err) => {
                                         println!(
                                             "Failed saving to file {}:\t\n{}",
-                                            input[next],
-                                            err.to_string()
+                                            input[next], err
                                         );
                                     }
                                 }
@@ -639,7 +1571,7 @@ fn tui_loop(mut point_files: IndexMap<(String, u32), Vec<(f64, f64)>>, config: C
                                 Ok(hue) => hue,
                                 Err(err) => {
                                     prompt();
-                                    println!("Expect f64 for hue, {}", err.to_string());
+                                    println!("Expect f64 for hue, {}", err);
                                     continue;
                                 }
                             }
@@ -647,35 +1579,22 @@ fn tui_loop(mut point_files: IndexMap<(String, u32), Vec<(f64, f64)>>, config: C
                             0.0
                         };
 
-                        if input[next + 1] == "*" && {
-                            let mut iter = point_files.iter();
-                            loop {
-                                match iter.next() {
-                                    Some((key, _)) => {
-                                        if key.0 == input[next] {
-                                            break true;
-                                        }
-                                    }
-                                    None => {
-                                        break false;
-                                    }
-                                }
-                            }
-                        } {
+                        if input[next + 1] == "*" && point_files.contains_source(input[next]) {
                             match option.output_type {
-                                OutputType::PNG => {
+                                OutputType::Png => {
                                     fn png_output_entry(
-                                        key: &(String, u32),
-                                        entry: &Vec<(f64, f64)>,
-                                        input: &Vec<&str>,
+                                        entry: &Scan,
+                                        input: &[&str],
                                         next: usize,
                                         hue: f64,
                                         option: &OutputOption,
                                     ) {
-                                            let mut png_output = PNGOutput::new();
+                                        let key = entry.key();
+                                        let mut png_output =
+                                            PNGOutput::new().intensity(option.intensity);
                                         if key.0 == input[next] {
                                             png_output.add_points(
-                                                &entry,
+                                                entry,
                                                 option.clip_pos,
                                                 option.scale,
                                                 hue,
@@ -696,41 +1615,37 @@ fn tui_loop(mut point_files: IndexMap<(String, u32), Vec<(f64, f64)>>, config: C
                                                 Err(err) => {
                                                     println!(
                                                         "Failed saving to file {}-{:04}.png:\t\n{}",
-                                                        key.0,
-                                                        key.1,
-                                                        err.to_string()
+                                                        key.0, key.1, err
                                                     );
                                                 }
                                             }
                                         }
                                     }
                                     point_files.par_iter().for_each(|x| {
-                                        png_output_entry(x.0, x.1, &input, next, hue, &option)
+                                        png_output_entry(x, &input, next, hue, &option)
                                     });
                                 }
-                                OutputType::SVG => {
+                                OutputType::Svg => {
                                     fn svg_output_entry(
-                                        key: &(String, u32),
-                                        entry: &Vec<(f64, f64)>,
-                                        input: &Vec<&str>,
+                                        entry: &Scan,
+                                        input: &[&str],
                                         next: usize,
                                         hue: f64,
                                         option: &OutputOption,
                                     ) {
-                                            let mut svg_output = SVGOutput::new();
+                                        let key = entry.key();
+                                        let mut svg_output =
+                                            SVGOutput::new().intensity(option.intensity);
                                         if key.0 == input[next] {
                                             svg_output.add_points(
-                                                &entry,
+                                                entry,
                                                 option.clip_pos,
                                                 option.scale,
                                                 hue,
                                                 50,
                                             );
                                             match svg::save(
-                                                format!(
-                                                    "{}.svg",
-                                                    format!("{}-{:04}", key.0, key.1)
-                                                ),
+                                                format!("{}-{:04}.svg", key.0, key.1),
                                                 &svg_output.output_to_empty_document(
                                                     option.scale,
                                                     option.clip_pos,
@@ -747,16 +1662,14 @@ fn tui_loop(mut point_files: IndexMap<(String, u32), Vec<(f64, f64)>>, config: C
                                                 Err(err) => {
                                                     println!(
                                                         "Failed saving to file {}-{:04}.svg:\t\n{}",
-                                                        key.0,
-                                                        key.1,
-                                                        err.to_string()
+                                                        key.0, key.1, err
                                                     );
                                                 }
                                             }
                                         }
                                     }
                                     point_files.par_iter().for_each(|x| {
-                                        svg_output_entry(x.0, x.1, &input, next, hue, &option)
+                                        svg_output_entry(x, &input, next, hue, &option)
                                     });
                                 }
                             }
@@ -767,21 +1680,19 @@ fn tui_loop(mut point_files: IndexMap<(String, u32), Vec<(f64, f64)>>, config: C
                                     Ok(entry_num) => entry_num,
                                     Err(err) => {
                                         prompt();
-                                        println!(
-                                            "Error happened parsing entry_num: \n\t{}",
-                                            err.to_string()
-                                        );
+                                        println!("Error happened parsing entry_num: \n\t{}", err);
                                         continue;
                                     }
                                 },
                             );
 
-                            match point_files.get(&key) {
+                            match point_files.get(&key.0, key.1) {
                                 Some(entry) => match option.output_type {
-                                    OutputType::PNG => {
-                                        let mut png_output = PNGOutput::new();
+                                    OutputType::Png => {
+                                        let mut png_output =
+                                            PNGOutput::new().intensity(option.intensity);
                                         png_output.add_points(
-                                            &entry,
+                                            entry,
                                             option.clip_pos,
                                             option.scale,
                                             hue,
@@ -802,24 +1713,23 @@ fn tui_loop(mut point_files: IndexMap<(String, u32), Vec<(f64, f64)>>, config: C
                                             Err(err) => {
                                                 println!(
                                                     "Failed saving to file {}-{:04}.png:\t\n{}",
-                                                    key.0,
-                                                    key.1,
-                                                    err.to_string()
+                                                    key.0, key.1, err
                                                 );
                                             }
                                         }
                                     }
-                                    OutputType::SVG => {
-                                        let mut svg_output = SVGOutput::new();
+                                    OutputType::Svg => {
+                                        let mut svg_output =
+                                            SVGOutput::new().intensity(option.intensity);
                                         svg_output.add_points(
-                                            &entry,
+                                            entry,
                                             option.clip_pos,
                                             option.scale,
                                             hue,
                                             50,
                                         );
                                         match svg::save(
-                                            format!("{}.svg", format!("{}-{:04}", key.0, key.1)),
+                                            format!("{}-{:04}.svg", key.0, key.1),
                                             &svg_output.output_to_empty_document(
                                                 option.scale,
                                                 option.clip_pos,
@@ -836,9 +1746,7 @@ fn tui_loop(mut point_files: IndexMap<(String, u32), Vec<(f64, f64)>>, config: C
                                             Err(err) => {
                                                 println!(
                                                     "Failed saving to file {}-{:04}.png:\t\n{}",
-                                                    key.0,
-                                                    key.1,
-                                                    err.to_string()
+                                                    key.0, key.1, err
                                                 );
                                             }
                                         }
@@ -867,7 +1775,7 @@ fn tui_loop(mut point_files: IndexMap<(String, u32), Vec<(f64, f64)>>, config: C
                         println!("\tentry_name entry_num");
                         println!("\tentry_name entry_num");
                         println!("\t...");
-                        println!("");
+                        println!();
                     }
                     if input.len() != 3 {
                         prompt()
@@ -878,44 +1786,840 @@ fn tui_loop(mut point_files: IndexMap<(String, u32), Vec<(f64, f64)>>, config: C
                                 Ok(target_num) => target_num,
                                 Err(err) => {
                                     prompt();
-                                    println!(
-                                        "Error happened parsing target_num: \n\t{}",
-                                        err.to_string()
-                                    );
+                                    println!("Error happened parsing target_num: \n\t{}", err);
                                     continue;
                                 }
                             },
                         );
 
-                        if point_files.contains_key(&key) {
+                        if point_files.contains(&key.0, key.1) {
                             prompt();
                             println!("Entry {}-{:04} already exist!", input[1], input[2]);
                             continue;
                         }
 
-                        let mut combined_entry: Vec<(f64, f64)> = Vec::new();
+                        let mut combined_entry: Vec<Point> = Vec::new();
                         for key in tui_get_entry_keys(&point_files, prompt, config.no_prompt) {
                             // get entry names ensure the keys are valid so we can safely unwrap
-                            combined_entry.append(&mut (point_files.get(&key).unwrap().clone()));
-                        }
-                        combined_entry.sort_by(|a, b| {
-                            if a.0 > b.0 {
-                                Ordering::Greater
-                            } else if a.0 == b.0 {
-                                if a.1 > b.1 {
-                                    Ordering::Greater
+                            combined_entry.extend_from_slice(
+                                point_files.get(&key.0, key.1).unwrap().points(),
+                            );
+                        }
+                        combined_entry.sort_by(Point::polar_cmp);
+                        point_files.insert(Scan::new(&key.0, key.1, combined_entry));
+                    }
+                } else if command == "denoise" {
+                    fn prompt() {
+                        println!("denoise [options] entry_name entry_num");
+                        println!("\tentry_num can be * for every entry of entry_name");
+                        println!("\tfilters run in the order listed below");
+                        println!("options:");
+                        println!(
+                            "\t--median WINDOW:\treplace ranges with the median of WINDOW points"
+                        );
+                        println!("\t--outliers K RATIO:\tremove points whose mean distance to K");
+                        println!("\t\t\t\tneighbors is RATIO deviations above average");
+                        println!("\t--isolated RADIUS COUNT:\tremove points with less than COUNT");
+                        println!("\t\t\t\tneighbors within RADIUS meters");
+                        println!();
+                    }
+
+                    let mut median: Option<usize> = None;
+                    let mut outliers: Option<(usize, f64)> = None;
+                    let mut isolated: Option<(f64, usize)> = None;
+                    let mut next: usize = 1;
+                    let mut bad_option = false;
+                    while next < input.len() && input[next].starts_with("--") {
+                        match input[next] {
+                            "--median" if next + 1 < input.len() => {
+                                next += 1;
+                                match input[next].parse::<usize>() {
+                                    Ok(window) => median = Some(window),
+                                    Err(err) => {
+                                        println!("Expect integer for WINDOW, {}", err);
+                                        bad_option = true;
+                                    }
+                                }
+                            }
+                            "--outliers" if next + 2 < input.len() => {
+                                match (
+                                    input[next + 1].parse::<usize>(),
+                                    input[next + 2].parse::<f64>(),
+                                ) {
+                                    (Ok(k), Ok(ratio)) => outliers = Some((k, ratio)),
+                                    (Err(err), _) => {
+                                        println!("Expect integer for K, {}", err);
+                                        bad_option = true;
+                                    }
+                                    (_, Err(err)) => {
+                                        println!("Expect f64 for RATIO, {}", err);
+                                        bad_option = true;
+                                    }
+                                }
+                                next += 2;
+                            }
+                            "--isolated" if next + 2 < input.len() => {
+                                match (
+                                    input[next + 1].parse::<f64>(),
+                                    input[next + 2].parse::<usize>(),
+                                ) {
+                                    (Ok(radius), Ok(count)) => isolated = Some((radius, count)),
+                                    (Err(err), _) => {
+                                        println!("Expect f64 for RADIUS, {}", err);
+                                        bad_option = true;
+                                    }
+                                    (_, Err(err)) => {
+                                        println!("Expect integer for COUNT, {}", err);
+                                        bad_option = true;
+                                    }
+                                }
+                                next += 2;
+                            }
+                            _ => bad_option = true,
+                        }
+                        if bad_option {
+                            break;
+                        }
+                        next += 1;
+                    }
+                    if bad_option
+                        || input.len() != next + 2
+                        || (median.is_none() && outliers.is_none() && isolated.is_none())
+                    {
+                        prompt();
+                        continue;
+                    }
+
+                    let keys = match tui_entry_keys(&point_files, input[next], input[next + 1]) {
+                        Ok(keys) => keys,
+                        Err(err) => {
+                            prompt();
+                            println!("{err}");
+                            continue;
+                        }
+                    };
+                    let (mut total_before, mut total_after) = (0, 0);
+                    for (name, num) in &keys {
+                        let entry = point_files.get_mut(name, *num).unwrap();
+                        let before = entry.points().len();
+                        let smoothed = median.map(|window| median_filter(entry, window));
+                        if let Some((k, ratio)) = outliers {
+                            remove_statistical_outliers(entry, k, ratio);
+                        }
+                        if let Some((radius, count)) = isolated {
+                            remove_isolated(entry, radius, count);
+                        }
+                        let after = entry.points().len();
+                        match smoothed {
+                            Some(smoothed) => println!(
+                                "{}-{:04}: {} -> {} points, {} ranges smoothed",
+                                name, num, before, after, smoothed
+                            ),
+                            None => println!("{}-{:04}: {} -> {} points", name, num, before, after),
+                        }
+                        total_before += before;
+                        total_after += after;
+                    }
+                    if keys.len() > 1 {
+                        println!(
+                            "Denoised {} entries, {} -> {} points",
+                            keys.len(),
+                            total_before,
+                            total_after
+                        );
+                    }
+                } else if command == "average" {
+                    fn prompt() {
+                        println!("average target_name target_num");
+                        println!("\tentry_name entry_num");
+                        println!("\tentry_name entry_num");
+                        println!("\t...");
+                        println!("\tentries have to be resampled onto the same grid first");
+                        println!();
+                    }
+                    if input.len() != 3 {
+                        prompt()
+                    } else {
+                        let key = (
+                            input[1].to_string(),
+                            match input[2].parse::<u32>() {
+                                Ok(target_num) => target_num,
+                                Err(err) => {
+                                    prompt();
+                                    println!("Error happened parsing target_num: \n\t{}", err);
+                                    continue;
+                                }
+                            },
+                        );
+
+                        if point_files.contains(&key.0, key.1) {
+                            prompt();
+                            println!("Entry {}-{:04} already exist!", input[1], input[2]);
+                            continue;
+                        }
+
+                        let keys = tui_get_entry_keys(&point_files, prompt, config.no_prompt);
+                        let entries: Vec<&Scan> = keys
+                            .iter()
+                            .map(|(name, num)| point_files.get(name, *num).unwrap())
+                            .collect();
+                        match average(&entries) {
+                            Some(points) => {
+                                println!("Averaged {} entries", entries.len());
+                                point_files.insert(Scan::new(&key.0, key.1, points));
+                            }
+                            None if entries.is_empty() => println!("No entries to average"),
+                            None => {
+                                println!("Entries aren't on the same grid, resample them first")
+                            }
+                        }
+                    }
+                } else if command == "convert" {
+                    fn prompt() {
+                        println!("convert [options] input_file output_file");
+                        println!("options:");
+                        println!("\t--text:\t\t\twrite the text format instead of binary");
+                        println!("\t--f32:\t\t\tstore binary values in single precision");
+                        println!("\t--no-checksum:\t\tleave out binary checksums");
+                        println!("\t--precision DIGITS:\t(DEFAULT=6)digits written in text");
+                        println!();
+                    }
+
+                    let mut text = false;
+                    let mut single_precision = false;
+                    let mut checksum = true;
+                    let mut precision: usize = 6;
+                    let mut next: usize = 1;
+                    let mut bad_option = false;
+                    while next < input.len() && input[next].starts_with("--") {
+                        match input[next] {
+                            "--text" => text = true,
+                            "--f32" => single_precision = true,
+                            "--no-checksum" => checksum = false,
+                            "--precision" if next + 1 < input.len() => {
+                                next += 1;
+                                match input[next].parse::<usize>() {
+                                    Ok(digits) => precision = digits,
+                                    Err(err) => {
+                                        println!("Expect usize for DIGITS, {}", err);
+                                        bad_option = true;
+                                    }
+                                }
+                            }
+                            _ => bad_option = true,
+                        }
+                        if bad_option {
+                            break;
+                        }
+                        next += 1;
+                    }
+                    if bad_option || input.len() != next + 2 {
+                        prompt();
+                        continue;
+                    }
+                    // creating the output would truncate the input before it's read
+                    let same_file = match (
+                        fs::canonicalize(input[next]),
+                        fs::canonicalize(input[next + 1]),
+                    ) {
+                        (Ok(input_path), Ok(output_path)) => input_path == output_path,
+                        _ => false,
+                    };
+                    if same_file {
+                        println!("Can't convert {} onto itself", input[next]);
+                        continue;
+                    }
+
+                    let scans = match open_scans(input[next], false) {
+                        Ok(scans) => scans,
+                        Err(err) => {
+                            println!("Error happened opening file {}: ", input[next]);
+                            println!("\t{err}");
+                            continue;
+                        }
+                    };
+                    let file = match File::create(input[next + 1]) {
+                        Ok(file) => BufWriter::new(file),
+                        Err(err) => {
+                            println!("Failed creating file {}:\n\t{}", input[next + 1], err);
+                            continue;
+                        }
+                    };
+
+                    // scans are streamed through, so files of any size can be converted
+                    enum Writer {
+                        Text(PtflWriter<BufWriter<File>>),
+                        Binary(BinaryWriter<BufWriter<File>>),
+                    }
+                    let mut writer = if text {
+                        Writer::Text(PtflWriter::new(file).precision(precision))
+                    } else {
+                        Writer::Binary(
+                            BinaryWriter::new(file)
+                                .single_precision(single_precision)
+                                .checksum(checksum),
+                        )
+                    };
+                    let mut count: u32 = 0;
+                    let mut failed = false;
+                    for scan in scans {
+                        let scan = match scan {
+                            Ok(scan) => scan,
+                            Err(err) => {
+                                println!("Error happened parsing file {}: ", input[next]);
+                                println!("\t{err}");
+                                failed = true;
+                                break;
+                            }
+                        };
+                        let result = match &mut writer {
+                            Writer::Text(writer) => writer.write_scan(&scan),
+                            Writer::Binary(writer) => writer.write_scan(&scan),
+                        };
+                        if let Err(err) = result {
+                            println!("Failed writing to file {}:\n\t{}", input[next + 1], err);
+                            failed = true;
+                            break;
+                        }
+                        count += 1;
+                    }
+                    let result = match writer {
+                        Writer::Text(mut writer) => writer.flush(),
+                        Writer::Binary(writer) => writer.finish().map(|_| ()),
+                    };
+                    match result {
+                        Ok(_) if !failed => {
+                            println!(
+                                "Converted {count} from {} to {}",
+                                input[next],
+                                input[next + 1]
+                            );
+                        }
+                        Ok(_) => {
+                            println!(
+                                "Converted {count} before stopping, {} is incomplete",
+                                input[next + 1]
+                            );
+                        }
+                        Err(err) => {
+                            println!("Failed writing to file {}:\n\t{}", input[next + 1], err);
+                        }
+                    }
+                } else {
+                    print_tui_help();
+                }
+            }
+            8 => {
+                if command == "validate" {
+                    fn prompt() {
+                        println!("validate [options] [entry_name entry_num]");
+                        println!("\tchecks every entry if none is given");
+                        println!("options:");
+                        println!("\t--reject:\t\tremove entries with any issue");
+                        println!("\t--clamp:\t\tbring bad values back into range");
+                        println!("\t--drop:\t\t\tremove offending points");
+                        println!("\t--max-range RANGE:\tranges above RANGE are out of range");
+                        println!("\t--expected COUNT:\t(DEFAULT=5290)usual points per entry");
+                        println!();
+                    }
+
+                    let mut validator = Validator::new();
+                    let mut next: usize = 1;
+                    let mut bad_option = false;
+                    while next < input.len() && input[next].starts_with("--") {
+                        match input[next] {
+                            "--reject" => validator = validator.mode(ValidationMode::Reject),
+                            "--clamp" => validator = validator.mode(ValidationMode::Clamp),
+                            "--drop" => validator = validator.mode(ValidationMode::Drop),
+                            "--max-range" if next + 1 < input.len() => {
+                                next += 1;
+                                match input[next].parse::<f64>() {
+                                    Ok(max_range) => validator = validator.max_range(max_range),
+                                    Err(err) => {
+                                        println!("Expect f64 after --max-range, {}", err);
+                                        bad_option = true;
+                                    }
+                                }
+                            }
+                            "--expected" if next + 1 < input.len() => {
+                                next += 1;
+                                match input[next].parse::<usize>() {
+                                    Ok(count) => validator = validator.expected_count(count),
+                                    Err(err) => {
+                                        println!("Expect integer after --expected, {}", err);
+                                        bad_option = true;
+                                    }
+                                }
+                            }
+                            _ => bad_option = true,
+                        }
+                        if bad_option {
+                            break;
+                        }
+                        next += 1;
+                    }
+                    if bad_option || (input.len() != next && input.len() != next + 2) {
+                        prompt();
+                        continue;
+                    }
+
+                    if input.len() == next {
+                        print_validation(&validator.validate_set(&mut point_files));
+                        continue;
+                    }
+                    let keys = match tui_entry_keys(&point_files, input[next], input[next + 1]) {
+                        Ok(keys) => keys,
+                        Err(err) => {
+                            prompt();
+                            println!("{err}");
+                            continue;
+                        }
+                    };
+
+                    print_validation(&validator.validate_entries(&mut point_files, &keys));
+                } else if command == "odometry" {
+                    fn prompt() {
+                        println!("odometry [options] entry_name");
+                        println!("\tregisters every entry of entry_name onto the one before it");
+                        println!("options:");
+                        println!("\t--line:\t\t\tmatch points to lines instead of points");
+                        println!("\t--max-distance RANGE:\t(DEFAULT=0.5)furthest points matched");
+                        println!("\t--csv FILE:\t\twrite the trajectory to FILE");
+                        println!("\t--png FILE:\t\tdraw the entries and trajectory to FILE");
+                        println!("\t--svg FILE:\t\tdraw the entries and trajectory to FILE");
+                        println!("\t--scale SCALE:\t\t(DEFAULT=200)how much pixel for a meter");
+                        println!("\t--clip POS:\t\t(DEFAULT=5)how far to include in the drawing");
+                        println!();
+                    }
+
+                    let mut icp = Icp::new();
+                    let mut csv: Option<&str> = None;
+                    let mut png: Option<&str> = None;
+                    let mut svg: Option<&str> = None;
+                    let mut scale: f64 = 200.0;
+                    let mut clip_pos: f64 = 5.0;
+                    let mut next: usize = 1;
+                    let mut bad_option = false;
+                    while next < input.len() && input[next].starts_with("--") {
+                        match input[next] {
+                            "--line" => icp = icp.method(IcpMethod::PointToLine),
+                            "--max-distance" | "--scale" | "--clip" if next + 1 < input.len() => {
+                                next += 1;
+                                match input[next].parse::<f64>() {
+                                    Ok(value) => match input[next - 1] {
+                                        "--max-distance" => icp = icp.max_distance(value),
+                                        "--scale" => scale = value,
+                                        _ => clip_pos = value,
+                                    },
+                                    Err(err) => {
+                                        println!("Expect f64 after {}, {}", input[next - 1], err);
+                                        bad_option = true;
+                                    }
+                                }
+                            }
+                            "--csv" | "--png" | "--svg" if next + 1 < input.len() => {
+                                next += 1;
+                                match input[next - 1] {
+                                    "--csv" => csv = Some(input[next]),
+                                    "--png" => png = Some(input[next]),
+                                    _ => svg = Some(input[next]),
+                                }
+                            }
+                            _ => bad_option = true,
+                        }
+                        if bad_option {
+                            break;
+                        }
+                        next += 1;
+                    }
+                    if bad_option || input.len() != next + 1 {
+                        prompt();
+                        continue;
+                    }
+
+                    let mut entries: Vec<&Scan> = point_files.by_source(input[next]).collect();
+                    entries.sort_by_key(|entry| entry.index());
+                    if entries.len() < 2 {
+                        prompt();
+                        println!(
+                            "{} needs at least 2 entries, got {}",
+                            input[next],
+                            entries.len()
+                        );
+                        continue;
+                    }
+
+                    let trajectory = Trajectory::from_scans(&icp, &entries);
+                    for point in &trajectory.points {
+                        if let Some(registration) = point.registration {
+                            println!(
+                                "{}-{:04}: x {:.4} y {:.4} theta {:.3} degree, residual {:.4}{}",
+                                input[next],
+                                point.index,
+                                point.pose.x,
+                                point.pose.y,
+                                point.pose.theta.to_degrees(),
+                                registration.residual,
+                                if registration.converged {
+                                    ""
                                 } else {
-                                    if a.1 == b.1 {
-                                        Ordering::Equal
-                                    } else {
-                                        Ordering::Less
+                                    ", not converged"
+                                }
+                            );
+                        }
+                    }
+                    let length: f64 = trajectory
+                        .positions()
+                        .windows(2)
+                        .map(|pair| pair[0].distance(&pair[1]))
+                        .sum();
+                    println!(
+                        "Registered {} entries over {:.3} meters, {} not converged",
+                        trajectory.len(),
+                        length,
+                        trajectory.unconverged()
+                    );
+
+                    if let Some(csv) = csv {
+                        match File::create(csv)
+                            .and_then(|file| trajectory.write_csv(BufWriter::new(file)))
+                        {
+                            Ok(_) => println!("Saved {}", csv),
+                            Err(err) => println!("Failed saving to file {}:\n\t{}", csv, err),
+                        }
+                    }
+
+                    if png.is_none() && svg.is_none() {
+                        continue;
+                    }
+                    // entries are drawn where the trajectory puts them
+                    let mut png_output = PNGOutput::new();
+                    let mut svg_output = SVGOutput::new();
+                    for (i, (entry, point)) in entries.iter().zip(&trajectory.points).enumerate() {
+                        let mut placed = (*entry).clone();
+                        placed.transform(point.pose);
+                        let hue = 360.0 * i as f64 / entries.len() as f64;
+                        if png.is_some() {
+                            png_output.add_points(&placed, clip_pos, scale, hue, 50);
+                        }
+                        if svg.is_some() {
+                            svg_output.add_points(&placed, clip_pos, scale, hue, 50);
+                        }
+                    }
+                    if let Some(png) = png {
+                        png_output.add_trajectory(&trajectory, clip_pos, scale, 60.0);
+                        match png_output.to_pixmap(clip_pos, scale).save_png(png) {
+                            Ok(_) => println!("Saved {}", png),
+                            Err(err) => println!("Failed saving to file {}:\n\t{}", png, err),
+                        }
+                    }
+                    if let Some(svg) = svg {
+                        svg_output.add_trajectory(&trajectory, clip_pos, scale, 60.0);
+                        match svg::save(svg, &svg_output.output_to_empty_document(scale, clip_pos))
+                        {
+                            Ok(_) => println!("Saved {}", svg),
+                            Err(err) => println!("Failed saving to file {}:\n\t{}", svg, err),
+                        }
+                    }
+                } else if command == "resample" {
+                    fn prompt() {
+                        println!("resample [options] entry_name entry_num");
+                        println!("\tentry_num can be * for every entry of entry_name");
+                        println!("options:");
+                        println!("\t--resolution DEGREE:\t(DEFAULT=0.1)spacing of the grid");
+                        println!("\t--linear:\t\tinterpolate instead of taking the nearest point");
+                        println!("\t--max-gap DEGREE:\t(DEFAULT=4 steps)widest gap filled in");
+                        println!("\t--into NAME:\t\twrite into NAME-entry_num instead of in place");
+                        println!();
+                    }
+
+                    let mut resampler = Resampler::new();
+                    let mut into: Option<&str> = None;
+                    let mut next: usize = 1;
+                    let mut bad_option = false;
+                    while next < input.len() && input[next].starts_with("--") {
+                        match input[next] {
+                            "--resolution" | "--max-gap" if next + 1 < input.len() => {
+                                next += 1;
+                                match input[next].parse::<f64>() {
+                                    Ok(degree) if degree > 0.0 => {
+                                        resampler = if input[next - 1] == "--resolution" {
+                                            resampler.resolution(degree.to_radians())
+                                        } else {
+                                            resampler.max_gap(degree.to_radians())
+                                        }
+                                    }
+                                    Ok(_) => {
+                                        println!("Expect {} above zero", input[next - 1]);
+                                        bad_option = true;
+                                    }
+                                    Err(err) => {
+                                        println!("Expect f64 after {}, {}", input[next - 1], err);
+                                        bad_option = true;
                                     }
                                 }
-                            } else {
-                                Ordering::Less
                             }
-                        });
-                        point_files.insert(key, combined_entry);
+                            "--linear" => {
+                                resampler = resampler.interpolation(Interpolation::Linear)
+                            }
+                            "--into" if next + 1 < input.len() => {
+                                next += 1;
+                                into = Some(input[next]);
+                            }
+                            _ => bad_option = true,
+                        }
+                        if bad_option {
+                            break;
+                        }
+                        next += 1;
+                    }
+                    if bad_option || input.len() != next + 2 {
+                        prompt();
+                        continue;
+                    }
+
+                    let keys = match tui_entry_keys(&point_files, input[next], input[next + 1]) {
+                        Ok(keys) => keys,
+                        Err(err) => {
+                            prompt();
+                            println!("{err}");
+                            continue;
+                        }
+                    };
+                    if let Some(into) = into {
+                        if let Some((_, num)) = keys
+                            .iter()
+                            .find(|(_, num)| point_files.contains(into, *num))
+                        {
+                            println!("Entry {}-{:04} already exist!", into, num);
+                            continue;
+                        }
+                    }
+
+                    for (name, num) in &keys {
+                        let mut resampled =
+                            resampler.resample(point_files.get(name, *num).unwrap());
+                        if let Some(into) = into {
+                            resampled = resampled.with_key(into, *num);
+                        }
+                        point_files.insert(resampled);
+                    }
+                    println!(
+                        "Resampled {} entries onto {} points",
+                        keys.len(),
+                        resampler.grid_len()
+                    );
+                } else if command == "unfollow" {
+                    if input.len() != 2 {
+                        println!("unfollow file_name");
+                        println!();
+                        continue;
+                    }
+                    match streams.iter().find(|stream| stream.path() == input[1]) {
+                        // the stream is removed once its thread notices
+                        Some(stream) => stream.stop(),
+                        None => println!("{} isn't followed", input[1]),
+                    }
+                } else {
+                    print_tui_help();
+                }
+            }
+            9 => {
+                if command == "translate" {
+                    fn prompt() {
+                        println!("translate entry_name entry_num dx dy");
+                        println!("\tmoves every point by dx, dy meters");
+                        println!("\tentry_num can be * for every entry of entry_name");
+                        println!();
+                    }
+
+                    if input.len() != 5 {
+                        prompt();
+                        continue;
+                    }
+                    let (dx, dy) = match (input[3].parse::<f64>(), input[4].parse::<f64>()) {
+                        (Ok(dx), Ok(dy)) => (dx, dy),
+                        (Err(err), _) | (_, Err(err)) => {
+                            prompt();
+                            println!("Expect f64 for dx and dy, {}", err);
+                            continue;
+                        }
+                    };
+                    match tui_for_entries(&mut point_files, input[1], input[2], |entry| {
+                        entry.translate(dx, dy)
+                    }) {
+                        Ok(count) => println!("Translated {} entries by ({}, {})", count, dx, dy),
+                        Err(err) => {
+                            prompt();
+                            println!("{err}");
+                        }
+                    }
+                } else if command == "transform" {
+                    fn prompt() {
+                        println!("transform entry_name entry_num x y theta_degree");
+                        println!("\trotates by theta_degree, then moves by x, y meters");
+                        println!("\tentry_num can be * for every entry of entry_name");
+                        println!();
+                    }
+
+                    if input.len() != 6 {
+                        prompt();
+                        continue;
+                    }
+                    let values: Result<Vec<f64>, _> = input[3..]
+                        .iter()
+                        .map(|value| value.parse::<f64>())
+                        .collect();
+                    let pose = match values {
+                        Ok(values) => Pose::new(values[0], values[1], values[2].to_radians()),
+                        Err(err) => {
+                            prompt();
+                            println!("Expect f64 for x, y and theta_degree, {}", err);
+                            continue;
+                        }
+                    };
+                    match tui_for_entries(&mut point_files, input[1], input[2], |entry| {
+                        entry.transform(pose)
+                    }) {
+                        Ok(count) => println!("Transformed {} entries by {}", count, pose),
+                        Err(err) => {
+                            prompt();
+                            println!("{err}");
+                        }
+                    }
+                } else if command == "landmarks" {
+                    fn prompt() {
+                        println!("landmarks [options] entry_name entry_num");
+                        println!("\tfinds corners between walls and round objects in the entry");
+                        println!("options:");
+                        println!(
+                            "\t--ransac:\t\tfind walls with RANSAC instead of split and merge"
+                        );
+                        println!(
+                            "\t--corner-angle DEGREE:\t(DEFAULT=45)smallest angle of a corner"
+                        );
+                        println!("\t--radius MIN MAX:\t(DEFAULT=0.02 0.3)radius of circles kept");
+                        println!("\t--png FILE:\t\tdraw the entry, walls and landmarks to FILE");
+                        println!("\t--svg FILE:\t\tdraw the entry, walls and landmarks to FILE");
+                        println!("\t--scale SCALE:\t\t(DEFAULT=1000)how much pixel for a meter");
+                        println!("\t--clip POS:\t\t(DEFAULT=2)how far to include in the drawing");
+                        println!();
+                    }
+
+                    let mut detector = LandmarkDetector::new();
+                    let mut png: Option<&str> = None;
+                    let mut svg: Option<&str> = None;
+                    let mut scale: f64 = 1000.0;
+                    let mut clip_pos: f64 = 2.0;
+                    let mut next: usize = 1;
+                    let mut bad_option = false;
+                    while next < input.len() && input[next].starts_with("--") {
+                        match input[next] {
+                            "--ransac" => {
+                                detector =
+                                    detector.lines(LineExtractor::new().method(LineMethod::Ransac))
+                            }
+                            "--corner-angle" | "--scale" | "--clip" if next + 1 < input.len() => {
+                                next += 1;
+                                match input[next].parse::<f64>() {
+                                    Ok(value) => match input[next - 1] {
+                                        "--corner-angle" => {
+                                            detector = detector.corner_angle(value.to_radians())
+                                        }
+                                        "--scale" => scale = value,
+                                        _ => clip_pos = value,
+                                    },
+                                    Err(err) => {
+                                        println!("Expect f64 after {}, {}", input[next - 1], err);
+                                        bad_option = true;
+                                    }
+                                }
+                            }
+                            "--radius" if next + 2 < input.len() => {
+                                match (
+                                    input[next + 1].parse::<f64>(),
+                                    input[next + 2].parse::<f64>(),
+                                ) {
+                                    (Ok(min), Ok(max)) => detector = detector.radius(min, max),
+                                    (Err(err), _) | (_, Err(err)) => {
+                                        println!("Expect f64 for MIN and MAX, {}", err);
+                                        bad_option = true;
+                                    }
+                                }
+                                next += 2;
+                            }
+                            "--png" | "--svg" if next + 1 < input.len() => {
+                                next += 1;
+                                if input[next - 1] == "--png" {
+                                    png = Some(input[next]);
+                                } else {
+                                    svg = Some(input[next]);
+                                }
+                            }
+                            _ => bad_option = true,
+                        }
+                        if bad_option {
+                            break;
+                        }
+                        next += 1;
+                    }
+                    if bad_option || input.len() != next + 2 {
+                        prompt();
+                        continue;
+                    }
+
+                    let entry = match input[next + 1].parse::<u32>() {
+                        Ok(num) => match point_files.get(input[next], num) {
+                            Some(entry) => entry,
+                            None => {
+                                println!("Entry {}-{:04} didn't exist!", input[next], num);
+                                continue;
+                            }
+                        },
+                        Err(err) => {
+                            prompt();
+                            println!("Error happened parsing entry_num: \n\t{}", err);
+                            continue;
+                        }
+                    };
+
+                    let (landmarks, segments) = detector.detect_with_segments(entry);
+                    for landmark in &landmarks {
+                        match landmark.kind {
+                            LandmarkKind::Corner => print!("corner"),
+                            LandmarkKind::Circle { radius } => print!("circle r {:.3}", radius),
+                        }
+                        println!(
+                            " at ({:.3}, {:.3}), confidence {:.2}",
+                            landmark.position.x, landmark.position.y, landmark.confidence
+                        );
+                    }
+                    println!(
+                        "Found {} landmarks along {} walls",
+                        landmarks.len(),
+                        segments.len()
+                    );
+
+                    if let Some(png) = png {
+                        let mut png_output = PNGOutput::new();
+                        png_output.add_points(entry, clip_pos, scale, 0.0, 50);
+                        png_output.add_segments(&segments, clip_pos, scale, 120.0);
+                        png_output.add_landmarks(&landmarks, clip_pos, scale, 200.0);
+                        match png_output.to_pixmap(clip_pos, scale).save_png(png) {
+                            Ok(_) => println!("Saved {}", png),
+                            Err(err) => println!("Failed saving to file {}:\n\t{}", png, err),
+                        }
+                    }
+                    if let Some(svg) = svg {
+                        let mut svg_output = SVGOutput::new();
+                        svg_output.add_points(entry, clip_pos, scale, 0.0, 50);
+                        svg_output.add_segments(&segments, clip_pos, scale, 120.0);
+                        svg_output.add_landmarks(&landmarks, clip_pos, scale, 200.0);
+                        match svg::save(svg, &svg_output.output_to_empty_document(scale, clip_pos))
+                        {
+                            Ok(_) => println!("Saved {}", svg),
+                            Err(err) => println!("Failed saving to file {}:\n\t{}", svg, err),
+                        }
                     }
                 } else {
                     print_tui_help();
@@ -929,22 +2633,93 @@ fn tui_loop(mut point_files: IndexMap<(String, u32), Vec<(f64, f64)>>, config: C
 }
 
 fn print_tui_help() {
+    println!("average:\taverage entries resampled onto the same grid into a new entry");
     println!("combine:\tcombine multiple entry into a new entry");
+    println!("convert:\tconvert a pointfile between text and binary format");
+    println!("denoise:\tremove noise from entry(es)");
     println!("exit:\t\texit the program");
+    println!("filter:\t\tkeep points in entry(es) within a range and sector");
+    println!("follow:\t\tkeep adding scans appended to a file being recorded");
     println!("help:\t\tprint this message");
+    println!("icp:\t\talign an entry onto another");
+    println!("landmarks:\tfind corners and round objects in an entry");
+    println!("lines:\t\tfind straight walls in an entry");
     println!("list:\t\tlist all entries with ammount of contained points");
     println!("load:\t\tread and parse a file to pointfiles");
+    println!("map:\t\tbuild an occupancy grid map out of entry(es)");
+    println!("mirror:\t\tmirror points in entry(es) over an axis");
     println!("rotate:\t\trotate points in entry(es)");
+    println!("odometry:\testimate the trajectory over the entries of a recording");
     println!("output:\t\toutput entry(es) into file");
+    println!("resample:\tmove points in entry(es) onto a uniform angle grid");
+    println!("save:\t\twrite entry(es) into a pointfile");
     println!("show:\t\tcheck if a entry exists");
     println!("tev:\t\tpreview a entry on tev");
+    println!("transform:\trotate then translate points in entry(es)");
+    println!("translate:\tmove points in entry(es)");
+    println!("unfollow:\tstop following a file");
+    println!("validate:\tcheck entry(es) for invalid points, optionally fixing them");
+}
+
+// apply f to the entry entry_name-entry_num, or to every entry of entry_name
+// in parallel if entry_num is *, giving the number of entries changed
+fn tui_for_entries<F>(
+    point_files: &mut ScanSet,
+    entry_name: &str,
+    entry_num: &str,
+    f: F,
+) -> Result<usize, String>
+where
+    F: Fn(&mut Scan) + Send + Sync,
+{
+    if entry_num == "*" && point_files.contains_source(entry_name) {
+        return Ok(point_files
+            .par_iter_mut()
+            .filter(|entry| entry.source() == entry_name)
+            .map(f)
+            .count());
+    }
+
+    let entry_num = match entry_num.parse::<u32>() {
+        Ok(entry_num) => entry_num,
+        Err(err) => return Err(format!("Error happened parsing entry_num: \n\t{}", err)),
+    };
+    match point_files.get_mut(entry_name, entry_num) {
+        Some(entry) => {
+            f(entry);
+            Ok(1)
+        }
+        None => Err(format!("No, {}-{:04} not found", entry_name, entry_num)),
+    }
+}
+
+// keys of the entry entry_name-entry_num, or of every entry of entry_name
+// if entry_num is *, checking they exist
+fn tui_entry_keys(
+    point_files: &ScanSet,
+    entry_name: &str,
+    entry_num: &str,
+) -> Result<Vec<(String, u32)>, String> {
+    if entry_num == "*" && point_files.contains_source(entry_name) {
+        return Ok(point_files
+            .by_source(entry_name)
+            .map(|scan| scan.key())
+            .collect());
+    }
+
+    match entry_num.parse::<u32>() {
+        Ok(entry_num) if point_files.contains(entry_name, entry_num) => {
+            Ok(vec![(entry_name.to_string(), entry_num)])
+        }
+        Ok(entry_num) => Err(format!(
+            "Entry {}-{:04} didn't exist!",
+            entry_name, entry_num
+        )),
+        Err(err) => Err(format!("Error happened parsing entry_num: \n\t{}", err)),
+    }
 }
 
-fn tui_get_entry_keys(
-    point_files: &IndexMap<(String, u32), Vec<(f64, f64)>>,
-    prompt: fn(),
-    no_prompt: bool,
-) -> Vec<(String, u32)> {
+fn tui_get_entry_keys(point_files: &ScanSet, prompt: fn(), no_prompt: bool) -> Vec<(String, u32)> {
     let mut entry_keys: Vec<(String, u32)> = Vec::new();
     loop {
         // prompt the user they are entering entries for combination
@@ -961,7 +2736,7 @@ fn tui_get_entry_keys(
 
         // break down the input
         let entry_input: Vec<&str> = entry_input.trim().split(' ').collect();
-        if entry_input.len() == 1 && entry_input[0].len() == 0 {
+        if entry_input.len() == 1 && entry_input[0].is_empty() {
             break;
         }
 
@@ -977,14 +2752,14 @@ fn tui_get_entry_keys(
                 Ok(entry_num) => entry_num,
                 Err(err) => {
                     prompt();
-                    println!("Error happened parsing entry_num: \n\t{}", err.to_string());
+                    println!("Error happened parsing entry_num: \n\t{}", err);
                     continue;
                 }
             },
         );
 
         // push if the entry exist in point_files
-        if point_files.contains_key(&entry_key) {
+        if point_files.contains(&entry_key.0, entry_key.1) {
             entry_keys.push(entry_key);
         } else {
             prompt();
@@ -0,0 +1,305 @@
+use crate::cartesian::CartesianPoint;
+use crate::scan::{Pose, Scan};
+use std::io;
+use std::io::Write;
+use tiny_skia::Pixmap;
+
+// cells added around the grid every time it has to grow,
+// so scans moving along don't copy it over every time
+const GROW_MARGIN: i64 = 32;
+
+// thresholds and pixel values used by ROS map_server maps
+const OCCUPIED_THRESHOLD: f64 = 0.65;
+const FREE_THRESHOLD: f64 = 0.196;
+const OCCUPIED_PIXEL: u8 = 0;
+const FREE_PIXEL: u8 = 254;
+const UNKNOWN_PIXEL: u8 = 205;
+
+/// What's known about a cell of an [`OccupancyGrid`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Occupancy {
+    Free,
+    Occupied,
+    Unknown,
+}
+
+/// Occupancy grid map built by tracing the rays of scans taken at known
+/// poses. Every ray marks the cells it passes through as more likely
+/// free and the cell it hits as more likely occupied, in log-odds.
+/// Missing returns and returns beyond the maximum range are traced
+/// as free up to the maximum range.
+///
+/// The grid grows to fit whatever is inserted, cell `(0, 0)` is
+/// the bottom left one, at [`OccupancyGrid::origin`].
+#[derive(Clone, Debug)]
+pub struct OccupancyGrid {
+    resolution: f64,
+    max_range: f64,
+    hit: f64,
+    miss: f64,
+    min_log_odds: f64,
+    max_log_odds: f64,
+    // index of the bottom left cell, counted from the world origin
+    min_cell: (i64, i64),
+    width: usize,
+    height: usize,
+    // log-odds, row by row from the bottom
+    cells: Vec<f64>,
+}
+
+impl Default for OccupancyGrid {
+    fn default() -> OccupancyGrid {
+        OccupancyGrid::new()
+    }
+}
+
+impl OccupancyGrid {
+    pub fn new() -> OccupancyGrid {
+        OccupancyGrid {
+            resolution: 0.05,
+            max_range: 8.0,
+            hit: 0.85,
+            miss: -0.4,
+            min_log_odds: -2.0,
+            max_log_odds: 3.5,
+            min_cell: (0, 0),
+            width: 0,
+            height: 0,
+            cells: Vec::new(),
+        }
+    }
+
+    /// Size of a cell in meters, 0.05 by default.
+    /// Has to be set before inserting scans.
+    pub fn resolution(mut self, resolution: f64) -> OccupancyGrid {
+        self.resolution = resolution;
+        self
+    }
+
+    /// How far missing returns are traced as free, in meters, 8 by default.
+    /// Returns further than this are treated the same.
+    pub fn max_range(mut self, max_range: f64) -> OccupancyGrid {
+        self.max_range = max_range;
+        self
+    }
+
+    /// Log-odds added to a cell on a hit and on a pass through,
+    /// 0.85 and -0.4 by default.
+    pub fn log_odds(mut self, hit: f64, miss: f64) -> OccupancyGrid {
+        self.hit = hit;
+        self.miss = miss;
+        self
+    }
+
+    /// Bounds the log-odds of a cell is kept within, so a cell can
+    /// change its mind, -2 and 3.5 by default.
+    pub fn clamp(mut self, min: f64, max: f64) -> OccupancyGrid {
+        self.min_log_odds = min;
+        self.max_log_odds = max;
+        self
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    pub fn cell_size(&self) -> f64 {
+        self.resolution
+    }
+
+    /// Position of the bottom left corner of the grid in meters.
+    pub fn origin(&self) -> CartesianPoint {
+        CartesianPoint::new(
+            self.min_cell.0 as f64 * self.resolution,
+            self.min_cell.1 as f64 * self.resolution,
+        )
+    }
+
+    pub fn log_odds_at(&self, column: usize, row: usize) -> f64 {
+        self.cells[row * self.width + column]
+    }
+
+    pub fn probability_at(&self, column: usize, row: usize) -> f64 {
+        1.0 - 1.0 / (1.0 + self.log_odds_at(column, row).exp())
+    }
+
+    pub fn occupancy_at(&self, column: usize, row: usize) -> Occupancy {
+        let probability = self.probability_at(column, row);
+        if probability >= OCCUPIED_THRESHOLD {
+            Occupancy::Occupied
+        } else if probability <= FREE_THRESHOLD {
+            Occupancy::Free
+        } else {
+            Occupancy::Unknown
+        }
+    }
+
+    /// The cell containing the position in meters, None if it's off the grid.
+    pub fn cell_at(&self, point: &CartesianPoint) -> Option<(usize, usize)> {
+        let (x, y) = self.cell_index(point);
+        let column = usize::try_from(x - self.min_cell.0).ok()?;
+        let row = usize::try_from(y - self.min_cell.1).ok()?;
+        (column < self.width && row < self.height).then_some((column, row))
+    }
+
+    /// Traces the rays of a scan taken at `pose`.
+    pub fn insert(&mut self, scan: &Scan, pose: Pose) {
+        let sensor = self.cell_index(&CartesianPoint::new(pose.x, pose.y));
+        let rays: Vec<((i64, i64), bool)> = scan
+            .points()
+            .iter()
+            .map(|point| {
+                let hit = point.range != 0.0 && point.range <= self.max_range;
+                let range = if hit { point.range } else { self.max_range };
+                let (sin, cos) = (pose.theta + point.angle).sin_cos();
+                let end = CartesianPoint::new(pose.x + range * cos, pose.y + range * sin);
+                (self.cell_index(&end), hit)
+            })
+            .collect();
+
+        let mut min = sensor;
+        let mut max = sensor;
+        for &((x, y), _) in &rays {
+            min = (min.0.min(x), min.1.min(y));
+            max = (max.0.max(x), max.1.max(y));
+        }
+        self.fit(min, max);
+
+        for (end, hit) in rays {
+            self.trace(sensor, end, hit);
+        }
+    }
+
+    /// The map as a binary PGM image, occupied cells black,
+    /// free ones white and the rest gray, top row first.
+    pub fn write_pgm<W: Write>(&self, mut writer: W) -> io::Result<()> {
+        write!(writer, "P5\n{} {}\n255\n", self.width, self.height)?;
+        writer.write_all(&self.pixels())?;
+        writer.flush()
+    }
+
+    /// The map drawn the same way as [`OccupancyGrid::write_pgm`],
+    /// None if the grid is empty.
+    pub fn to_pixmap(&self) -> Option<Pixmap> {
+        let mut pixmap = Pixmap::new(self.width as u32, self.height as u32)?;
+        for (pixel, value) in pixmap.pixels_mut().iter_mut().zip(self.pixels()) {
+            *pixel = tiny_skia::ColorU8::from_rgba(value, value, value, 255).premultiply();
+        }
+        Some(pixmap)
+    }
+
+    /// A map_server style descriptor for the map saved as `image`.
+    pub fn write_yaml<W: Write>(&self, mut writer: W, image: &str) -> io::Result<()> {
+        let origin = self.origin();
+        writeln!(writer, "image: {}", image)?;
+        writeln!(writer, "resolution: {}", self.resolution)?;
+        writeln!(writer, "origin: [{}, {}, 0.0]", origin.x, origin.y)?;
+        writeln!(writer, "negate: 0")?;
+        writeln!(writer, "occupied_thresh: {}", OCCUPIED_THRESHOLD)?;
+        writeln!(writer, "free_thresh: {}", FREE_THRESHOLD)?;
+        writer.flush()
+    }
+
+    // image rows go from the top, the grid's from the bottom
+    fn pixels(&self) -> Vec<u8> {
+        let mut pixels = Vec::with_capacity(self.width * self.height);
+        for row in (0..self.height).rev() {
+            for column in 0..self.width {
+                pixels.push(match self.occupancy_at(column, row) {
+                    Occupancy::Occupied => OCCUPIED_PIXEL,
+                    Occupancy::Free => FREE_PIXEL,
+                    Occupancy::Unknown => UNKNOWN_PIXEL,
+                });
+            }
+        }
+        pixels
+    }
+
+    fn cell_index(&self, point: &CartesianPoint) -> (i64, i64) {
+        (
+            (point.x / self.resolution).floor() as i64,
+            (point.y / self.resolution).floor() as i64,
+        )
+    }
+
+    // grow the grid to hold the cells from min to max
+    fn fit(&mut self, min: (i64, i64), max: (i64, i64)) {
+        let (old_min, old_width, old_height) = (self.min_cell, self.width, self.height);
+        let old_max = (
+            old_min.0 + old_width as i64 - 1,
+            old_min.1 + old_height as i64 - 1,
+        );
+        if !self.cells.is_empty()
+            && min.0 >= old_min.0
+            && min.1 >= old_min.1
+            && max.0 <= old_max.0
+            && max.1 <= old_max.1
+        {
+            return;
+        }
+
+        let (new_min, new_max) = if self.cells.is_empty() {
+            (min, max)
+        } else {
+            (
+                (min.0.min(old_min.0), min.1.min(old_min.1)),
+                (max.0.max(old_max.0), max.1.max(old_max.1)),
+            )
+        };
+        let new_min = (new_min.0 - GROW_MARGIN, new_min.1 - GROW_MARGIN);
+        let new_max = (new_max.0 + GROW_MARGIN, new_max.1 + GROW_MARGIN);
+        let width = (new_max.0 - new_min.0 + 1) as usize;
+        let height = (new_max.1 - new_min.1 + 1) as usize;
+
+        let mut cells = vec![0.0; width * height];
+        let offset = (
+            (old_min.0 - new_min.0) as usize,
+            (old_min.1 - new_min.1) as usize,
+        );
+        for row in 0..old_height {
+            let from = row * old_width;
+            let to = (row + offset.1) * width + offset.0;
+            cells[to..to + old_width].copy_from_slice(&self.cells[from..from + old_width]);
+        }
+        self.cells = cells;
+        self.min_cell = new_min;
+        self.width = width;
+        self.height = height;
+    }
+
+    fn update(&mut self, cell: (i64, i64), change: f64) {
+        let column = (cell.0 - self.min_cell.0) as usize;
+        let row = (cell.1 - self.min_cell.1) as usize;
+        let value = &mut self.cells[row * self.width + column];
+        *value = (*value + change).clamp(self.min_log_odds, self.max_log_odds);
+    }
+
+    // bresenham from start to end, marking the cells before end as free
+    // and end itself as occupied on a hit
+    fn trace(&mut self, start: (i64, i64), end: (i64, i64), hit: bool) {
+        let (dx, dy) = ((end.0 - start.0).abs(), -(end.1 - start.1).abs());
+        let step = (
+            if start.0 < end.0 { 1 } else { -1 },
+            if start.1 < end.1 { 1 } else { -1 },
+        );
+        let mut error = dx + dy;
+        let mut cell = start;
+        while cell != end {
+            self.update(cell, self.miss);
+            let doubled = 2 * error;
+            if doubled >= dy {
+                error += dy;
+                cell.0 += step.0;
+            }
+            if doubled <= dx {
+                error += dx;
+                cell.1 += step.1;
+            }
+        }
+        self.update(end, if hit { self.hit } else { self.miss });
+    }
+}
@@ -0,0 +1,109 @@
+use crate::cartesian::CartesianPoint;
+use crate::icp::{Icp, IcpResult};
+use crate::scan::{Pose, Scan};
+use rayon::prelude::*;
+use std::io;
+use std::io::Write;
+
+/// Where the sensor was when a scan was taken.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct TrajectoryPoint {
+    /// Index of the scan in its source.
+    pub index: u32,
+    pub timestamp: Option<f64>,
+    /// In the frame of the first scan.
+    pub pose: Pose,
+    /// Registration onto the scan before, None for the first scan.
+    pub registration: Option<IcpResult>,
+}
+
+/// Poses of a sequence of scans, estimated by registering each scan
+/// onto the one before it.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Trajectory {
+    pub points: Vec<TrajectoryPoint>,
+}
+
+impl Trajectory {
+    /// Registers every scan onto the one before it in parallel, then chains
+    /// the relative poses together starting from the first scan at the origin.
+    /// Scans are taken in the order given.
+    pub fn from_scans(icp: &Icp, scans: &[&Scan]) -> Trajectory {
+        let registrations: Vec<IcpResult> = scans
+            .par_windows(2)
+            .map(|pair| icp.align(pair[1], pair[0]))
+            .collect();
+
+        let mut points = Vec::with_capacity(scans.len());
+        let mut pose = Pose::default();
+        for (i, scan) in scans.iter().enumerate() {
+            let registration = i.checked_sub(1).map(|before| registrations[before]);
+            if let Some(registration) = registration {
+                pose = pose.compose(registration.pose);
+            }
+            points.push(TrajectoryPoint {
+                index: scan.index(),
+                timestamp: scan.timestamp,
+                pose,
+                registration,
+            });
+        }
+        Trajectory { points }
+    }
+
+    pub fn len(&self) -> usize {
+        self.points.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.points.is_empty()
+    }
+
+    /// Positions along the trajectory, for drawing it.
+    pub fn positions(&self) -> Vec<CartesianPoint> {
+        self.points
+            .iter()
+            .map(|point| CartesianPoint::new(point.pose.x, point.pose.y))
+            .collect()
+    }
+
+    /// Registrations that didn't converge.
+    pub fn unconverged(&self) -> usize {
+        self.points
+            .iter()
+            .filter_map(|point| point.registration)
+            .filter(|registration| !registration.converged)
+            .count()
+    }
+
+    /// Writes a line per scan, `index,timestamp,x,y,theta,residual,converged`
+    /// after a header, leaving out values the scan doesn't have.
+    pub fn write_csv<W: Write>(&self, mut writer: W) -> io::Result<()> {
+        writeln!(writer, "index,timestamp,x,y,theta,residual,converged")?;
+        for point in &self.points {
+            let timestamp = point
+                .timestamp
+                .map(|timestamp| timestamp.to_string())
+                .unwrap_or_default();
+            let (residual, converged) = match point.registration {
+                Some(registration) => (
+                    format!("{:.6}", registration.residual),
+                    registration.converged.to_string(),
+                ),
+                None => (String::new(), String::new()),
+            };
+            writeln!(
+                writer,
+                "{},{},{:.6},{:.6},{:.6},{},{}",
+                point.index,
+                timestamp,
+                point.pose.x,
+                point.pose.y,
+                point.pose.theta,
+                residual,
+                converged
+            )?;
+        }
+        writer.flush()
+    }
+}
@@ -1,4 +1,11 @@
+use crate::cartesian::CartesianPoint;
+use crate::landmarks::{Landmark, LandmarkKind};
+use crate::lines::LineSegment;
+use crate::odometry::Trajectory;
+use crate::scan::{Point, Scan};
 use coolor::*;
+use std::borrow::Cow;
+use std::f64::consts::PI;
 use svg::node::element::path::Data as SVGData;
 use svg::node::element::Path as SVGPath;
 use svg::node::element::Rectangle as SVGRectangle;
@@ -10,10 +17,112 @@ use tiny_skia::Rect;
 use tiny_skia::Stroke;
 use tiny_skia::Transform;
 
+/// How point intensities are used when drawing.
+/// Points without an intensity are always drawn, in the scan's own color.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct IntensityStyle {
+    /// Points below this intensity aren't drawn.
+    pub min: Option<f64>,
+    /// Points above this intensity aren't drawn.
+    pub max: Option<f64>,
+    /// Shade point markers from dark to bright by intensity, over `min..max`
+    /// or the range of intensities in the scan when they're not given.
+    pub color: bool,
+}
+
+const INTENSITY_LEVELS: u32 = 8;
+
+// length of the heading marks on trajectories, in meters
+const HEADING_LENGTH: f64 = 0.05;
+
+// size of the cross marking corners, and sides of the polygon
+// drawn for circles, in meters
+const CORNER_MARK: f64 = 0.04;
+const CIRCLE_SIDES: usize = 24;
+
+impl IntensityStyle {
+    // the points drawn, along with their positions from the scan's cartesian view
+    fn filter<'a>(&self, scan: &'a Scan) -> (Cow<'a, [Point]>, Cow<'a, [CartesianPoint]>) {
+        let positions = &scan.cartesian().points;
+        if self.min.is_none() && self.max.is_none() {
+            return (Cow::Borrowed(scan.points()), Cow::Borrowed(positions));
+        }
+        let keep = |point: &Point| match point.intensity {
+            Some(intensity) => {
+                self.min.is_none_or(|min| intensity >= min)
+                    && self.max.is_none_or(|max| intensity <= max)
+            }
+            None => true,
+        };
+        let (points, positions) = scan
+            .points()
+            .iter()
+            .zip(positions)
+            .filter(|(point, _)| keep(point))
+            .map(|(point, position)| (*point, *position))
+            .unzip();
+        (Cow::Owned(points), Cow::Owned(positions))
+    }
+
+    // positions of the returns grouped by the brightness they're drawn with,
+    // points without intensity get the given brightness
+    fn levels(
+        &self,
+        points: &[Point],
+        positions: &[CartesianPoint],
+        brightness: u32,
+    ) -> Vec<(u32, Vec<CartesianPoint>)> {
+        let intensities = points.iter().filter_map(|point| point.intensity);
+        let low = self
+            .min
+            .unwrap_or_else(|| intensities.clone().fold(f64::INFINITY, f64::min));
+        let high = self
+            .max
+            .unwrap_or_else(|| intensities.fold(f64::NEG_INFINITY, f64::max));
+
+        let mut levels: Vec<(u32, Vec<CartesianPoint>)> = Vec::new();
+        for (point, position) in points.iter().zip(positions) {
+            if position.is_origin() {
+                continue;
+            }
+            let level_brightness = match point.intensity {
+                Some(intensity) => {
+                    let scaled = if high > low {
+                        (intensity - low) / (high - low)
+                    } else {
+                        1.0
+                    };
+                    let level = (scaled * INTENSITY_LEVELS as f64)
+                        .clamp(0.0, (INTENSITY_LEVELS - 1) as f64)
+                        as u32;
+                    // from 20% to 80% lightness
+                    20 + level * 60 / (INTENSITY_LEVELS - 1)
+                }
+                None => brightness,
+            };
+            match levels.iter_mut().find(|(b, _)| *b == level_brightness) {
+                Some((_, level_positions)) => level_positions.push(*position),
+                None => levels.push((level_brightness, vec![*position])),
+            }
+        }
+        levels
+    }
+}
+
 pub struct SVGOutput {
     all_paths: Vec<SVGPath>,
     non_zero_paths: Vec<SVGPath>,
     points_paths: Vec<SVGPath>,
+    segment_paths: Vec<SVGPath>,
+    landmark_paths: Vec<SVGPath>,
+    trajectory_paths: Vec<SVGPath>,
+    intensity: IntensityStyle,
+}
+
+impl Default for SVGOutput {
+    fn default() -> SVGOutput {
+        SVGOutput::new()
+    }
 }
 
 impl SVGOutput {
@@ -22,23 +131,36 @@ impl SVGOutput {
             all_paths: Vec::new(),
             non_zero_paths: Vec::new(),
             points_paths: Vec::new(),
+            segment_paths: Vec::new(),
+            landmark_paths: Vec::new(),
+            trajectory_paths: Vec::new(),
+            intensity: IntensityStyle::default(),
         }
     }
 
+    pub fn intensity(mut self, intensity: IntensityStyle) -> SVGOutput {
+        self.intensity = intensity;
+        self
+    }
+
     pub fn add_points(
         &mut self,
-        points: &Vec<(f64, f64)>,
+        scan: &Scan,
         clip_pos: f64,
         scale: f64,
         hue: f64,
         brightness: u32,
     ) {
+        let (points, positions) = self.intensity.filter(scan);
+        if points.is_empty() {
+            return;
+        }
         self.all_paths.push(
             SVGPath::new()
                 .set("fill", "none")
                 .set("stroke", format!("hsla({hue},40%,{brightness}%, 0.3)"))
                 .set("stroke-width", scale * 0.0005)
-                .set("d", all_path_svgdata(&points, clip_pos, scale)),
+                .set("d", all_path_svgdata(&positions, clip_pos, scale)),
         );
 
         self.non_zero_paths.push(
@@ -46,18 +168,129 @@ impl SVGOutput {
                 .set("fill", "none")
                 .set("stroke", format!("hsla({hue},70%,{brightness}%, 0.6)"))
                 .set("stroke-width", scale * 0.003)
-                .set("d", non_zero_path_svgdata(&points, clip_pos, scale)),
+                .set("d", non_zero_path_svgdata(&positions, clip_pos, scale)),
+        );
+
+        let levels = if self.intensity.color {
+            self.intensity.levels(&points, &positions, brightness)
+        } else {
+            let returns = positions.iter().filter(|position| !position.is_origin());
+            vec![(brightness, returns.copied().collect())]
+        };
+        for (brightness, level_positions) in levels {
+            self.points_paths.push(
+                SVGPath::new()
+                    .set("fill", "none")
+                    .set("stroke", format!("hsla({hue},100%,{brightness}%, 0.8)"))
+                    .set("stroke-width", scale * 0.002)
+                    .set(
+                        "d",
+                        square_svgdata(level_positions.iter(), clip_pos, scale, 0.01),
+                    ),
+            );
+        }
+    }
+
+    /// Draws line segments over the scans, marking their ends.
+    pub fn add_segments(&mut self, segments: &[LineSegment], clip_pos: f64, scale: f64, hue: f64) {
+        if segments.is_empty() {
+            return;
+        }
+        let mut data = SVGData::new();
+        for segment in segments {
+            data = data
+                .move_to((
+                    scale * (segment.start.x + clip_pos),
+                    scale * (segment.start.y + clip_pos),
+                ))
+                .line_to((
+                    scale * (segment.end.x + clip_pos),
+                    scale * (segment.end.y + clip_pos),
+                ));
+        }
+        self.segment_paths.push(
+            SVGPath::new()
+                .set("fill", "none")
+                .set("stroke", format!("hsla({hue},100%,50%, 0.9)"))
+                .set("stroke-width", scale * 0.006)
+                .set("d", data),
         );
 
-        self.points_paths.push(
+        let ends = segments
+            .iter()
+            .flat_map(|segment| [&segment.start, &segment.end]);
+        self.segment_paths.push(
             SVGPath::new()
                 .set("fill", "none")
-                .set("stroke", format!("hsla({hue},100%,{brightness}%, 0.8)"))
+                .set("stroke", format!("hsla({hue},100%,80%, 0.9)"))
                 .set("stroke-width", scale * 0.002)
-                .set(
-                    "d",
-                    non_zero_path_square_svgdata(&points, clip_pos, scale, 0.01),
-                ),
+                .set("d", square_svgdata(ends, clip_pos, scale, 0.02)),
+        );
+    }
+
+    /// Marks corners with a cross and circles with their outline,
+    /// brighter the more confident.
+    pub fn add_landmarks(&mut self, landmarks: &[Landmark], clip_pos: f64, scale: f64, hue: f64) {
+        for landmark in landmarks {
+            let mut data = SVGData::new();
+            for line in landmark_lines(landmark) {
+                data = data.move_to((
+                    scale * (line[0].x + clip_pos),
+                    scale * (line[0].y + clip_pos),
+                ));
+                for j in &line[1..] {
+                    data = data.line_to((scale * (j.x + clip_pos), scale * (j.y + clip_pos)));
+                }
+            }
+            let brightness = landmark_brightness(landmark);
+            self.landmark_paths.push(
+                SVGPath::new()
+                    .set("fill", "none")
+                    .set("stroke", format!("hsla({hue},100%,{brightness}%, 0.9)"))
+                    .set("stroke-width", scale * 0.004)
+                    .set("d", data),
+            );
+        }
+    }
+
+    /// Draws the path along the trajectory over the scans,
+    /// marking every pose and the heading at it.
+    pub fn add_trajectory(&mut self, trajectory: &Trajectory, clip_pos: f64, scale: f64, hue: f64) {
+        let positions = trajectory.positions();
+        if positions.is_empty() {
+            return;
+        }
+        let mut data = SVGData::new().move_to((
+            scale * (positions[0].x + clip_pos),
+            scale * (positions[0].y + clip_pos),
+        ));
+        for j in &positions[1..] {
+            data = data.line_to((scale * (j.x + clip_pos), scale * (j.y + clip_pos)));
+        }
+        self.trajectory_paths.push(
+            SVGPath::new()
+                .set("fill", "none")
+                .set("stroke", format!("hsla({hue},100%,50%, 0.9)"))
+                .set("stroke-width", scale * 0.004)
+                .set("d", data),
+        );
+
+        let mut markers = square_svgdata(positions.iter(), clip_pos, scale, 0.03);
+        for point in &trajectory.points {
+            let (sin, cos) = point.pose.theta.sin_cos();
+            markers = markers
+                .move_to((
+                    scale * (point.pose.x + clip_pos),
+                    scale * (point.pose.y + clip_pos),
+                ))
+                .line_by((scale * HEADING_LENGTH * cos, scale * HEADING_LENGTH * sin));
+        }
+        self.trajectory_paths.push(
+            SVGPath::new()
+                .set("fill", "none")
+                .set("stroke", format!("hsla({hue},100%,80%, 0.9)"))
+                .set("stroke-width", scale * 0.002)
+                .set("d", markers),
         );
     }
 
@@ -65,10 +298,17 @@ impl SVGOutput {
         a.all_paths.append(&mut b.all_paths);
         a.non_zero_paths.append(&mut b.non_zero_paths);
         a.points_paths.append(&mut b.points_paths);
+        a.segment_paths.append(&mut b.segment_paths);
+        a.landmark_paths.append(&mut b.landmark_paths);
+        a.trajectory_paths.append(&mut b.trajectory_paths);
         SVGOutput {
             all_paths: a.all_paths,
             non_zero_paths: a.non_zero_paths,
             points_paths: a.points_paths,
+            segment_paths: a.segment_paths,
+            landmark_paths: a.landmark_paths,
+            trajectory_paths: a.trajectory_paths,
+            intensity: a.intensity,
         }
     }
 
@@ -86,6 +326,18 @@ impl SVGOutput {
             document = document.add(path.clone());
         }
 
+        for path in &self.segment_paths {
+            document = document.add(path.clone());
+        }
+
+        for path in &self.landmark_paths {
+            document = document.add(path.clone());
+        }
+
+        for path in &self.trajectory_paths {
+            document = document.add(path.clone());
+        }
+
         document
     }
 
@@ -102,6 +354,18 @@ impl SVGOutput {
             document = document.add(path.clone());
         }
 
+        for path in &self.segment_paths {
+            document = document.add(path.clone());
+        }
+
+        for path in &self.landmark_paths {
+            document = document.add(path.clone());
+        }
+
+        for path in &self.trajectory_paths {
+            document = document.add(path.clone());
+        }
+
         document
     }
 }
@@ -122,65 +386,45 @@ fn svg_empty_document(scale: f64, clip_pos: f64) -> SVGDocument {
         )
 }
 
-fn all_path_svgdata(points: &Vec<(f64, f64)>, clip_pos: f64, scale: f64) -> SVGData {
+fn all_path_svgdata(points: &[CartesianPoint], clip_pos: f64, scale: f64) -> SVGData {
     let mut data = SVGData::new().move_to((
-        scale * (&points[0].1 * &points[0].0.cos() + clip_pos),
-        scale * (&points[0].1 * &points[0].0.sin() + clip_pos),
+        scale * (points[0].x + clip_pos),
+        scale * (points[0].y + clip_pos),
     ));
     for j in points {
-        data = data.line_to((
-            scale * (j.1 * j.0.cos() + clip_pos),
-            scale * (j.1 * j.0.sin() + clip_pos),
-        ));
+        data = data.line_to((scale * (j.x + clip_pos), scale * (j.y + clip_pos)));
     }
     data.close()
 }
 
-fn non_zero_path_svgdata(points: &Vec<(f64, f64)>, clip_pos: f64, scale: f64) -> SVGData {
-    let mut data = SVGData::new().move_to({
-        let mut a = points.iter();
-        loop {
-            let j = match a.next() {
-                Some(next) => next,
-                None => break (0.0, 0.0),
-            };
-            if j.1 != 0.0 {
-                break (
-                    scale * (j.1 * (j.0.cos()) + clip_pos),
-                    scale * (j.1 * (j.0.sin()) + clip_pos),
-                );
-            }
-        }
+fn non_zero_path_svgdata(points: &[CartesianPoint], clip_pos: f64, scale: f64) -> SVGData {
+    let mut returns = points.iter().filter(|j| !j.is_origin()).peekable();
+    let mut data = SVGData::new().move_to(match returns.peek() {
+        Some(j) => (scale * (j.x + clip_pos), scale * (j.y + clip_pos)),
+        None => (0.0, 0.0),
     });
-    for j in points {
-        if j.1 != 0.0 {
-            data = data.line_to((
-                scale * (j.1 * (j.0.cos()) + clip_pos),
-                scale * (j.1 * (j.0.sin()) + clip_pos),
-            ));
-        }
+    for j in returns {
+        data = data.line_to((scale * (j.x + clip_pos), scale * (j.y + clip_pos)));
     }
     data.close()
 }
 
-fn non_zero_path_square_svgdata(
-    points: &Vec<(f64, f64)>,
+fn square_svgdata<'a>(
+    points: impl Iterator<Item = &'a CartesianPoint>,
     clip_pos: f64,
     scale: f64,
     square_size: f64,
 ) -> SVGData {
     let mut data = SVGData::new();
     for j in points {
-        if j.1 != 0.0 {
-            data = data.move_to((
-                scale * (j.1 * (j.0.cos()) + clip_pos + square_size / 2.0),
-                scale * (j.1 * (j.0.sin()) + clip_pos + square_size / 2.0),
-            ));
-            data = data.line_by((scale * -square_size, 0));
-            data = data.line_by((0, scale * -square_size));
-            data = data.line_by((scale * square_size, 0));
-            data = data.close();
-        }
+        data = data.move_to((
+            scale * (j.x + clip_pos + square_size / 2.0),
+            scale * (j.y + clip_pos + square_size / 2.0),
+        ));
+        data = data.line_by((scale * -square_size, 0));
+        data = data.line_by((0, scale * -square_size));
+        data = data.line_by((scale * square_size, 0));
+        data = data.close();
     }
     data.close()
 }
@@ -189,6 +433,16 @@ pub struct PNGOutput {
     all_paths: Vec<(tiny_skia::Path, f64, u32)>,
     non_zero_paths: Vec<(tiny_skia::Path, f64, u32)>,
     points_paths: Vec<(tiny_skia::Path, f64, u32)>,
+    segment_paths: Vec<(tiny_skia::Path, f64, u32)>,
+    landmark_paths: Vec<(tiny_skia::Path, f64, u32)>,
+    trajectory_paths: Vec<(tiny_skia::Path, f64, u32)>,
+    intensity: IntensityStyle,
+}
+
+impl Default for PNGOutput {
+    fn default() -> PNGOutput {
+        PNGOutput::new()
+    }
 }
 
 impl PNGOutput {
@@ -197,18 +451,28 @@ impl PNGOutput {
             all_paths: Vec::new(),
             non_zero_paths: Vec::new(),
             points_paths: Vec::new(),
+            segment_paths: Vec::new(),
+            landmark_paths: Vec::new(),
+            trajectory_paths: Vec::new(),
+            intensity: IntensityStyle::default(),
         }
     }
 
+    pub fn intensity(mut self, intensity: IntensityStyle) -> PNGOutput {
+        self.intensity = intensity;
+        self
+    }
+
     pub fn add_points(
         &mut self,
-        points: &Vec<(f64, f64)>,
+        scan: &Scan,
         clip_pos: f64,
         scale: f64,
         hue: f64,
         brightness: u32,
     ) {
-        if points.len() == 0 {
+        let (points, positions) = self.intensity.filter(scan);
+        if points.is_empty() {
             return;
         }
         let mut all_path_builder = PathBuilder::new();
@@ -216,10 +480,10 @@ impl PNGOutput {
         let mut points_path_builder = PathBuilder::new();
 
         all_path_builder.move_to(
-            (scale * ((&points)[0].1 * (&points)[0].0.cos() + clip_pos)) as f32,
-            (scale * ((&points)[0].1 * (&points)[0].0.sin() + clip_pos)) as f32,
+            (scale * (positions[0].x + clip_pos)) as f32,
+            (scale * (positions[0].y + clip_pos)) as f32,
         );
-        let mut entry_iter = points.iter();
+        let mut entry_iter = positions.iter();
         if loop {
             let j = match entry_iter.next() {
                 Some(some) => some,
@@ -227,13 +491,13 @@ impl PNGOutput {
                 None => break false,
             };
 
-            let x = scale * (j.1 * j.0.cos() + clip_pos);
-            let y = scale * (j.1 * j.0.sin() + clip_pos);
+            let x = scale * (j.x + clip_pos);
+            let y = scale * (j.y + clip_pos);
 
             // its possible to both move_to(x, y) and line_to(x, y),
             // but that's not a issue
             all_path_builder.line_to(x as f32, y as f32);
-            if j.1 != 0.0 {
+            if !j.is_origin() {
                 // this might never be executed if all point is (angle, 0)
                 // this is handled later by matching .finish()
                 non_zero_path_builder.move_to(x as f32, y as f32);
@@ -246,16 +510,11 @@ impl PNGOutput {
                 break true;
             }
         } {
-            loop {
-                let j = match entry_iter.next() {
-                    Some(some) => some,
-                    None => break,
-                };
-
-                let x = scale * (j.1 * j.0.cos() + clip_pos);
-                let y = scale * (j.1 * j.0.sin() + clip_pos);
+            for j in entry_iter {
+                let x = scale * (j.x + clip_pos);
+                let y = scale * (j.y + clip_pos);
                 all_path_builder.line_to(x as f32, y as f32);
-                if j.1 != 0.0 {
+                if !j.is_origin() {
                     non_zero_path_builder.line_to(x as f32, y as f32);
                     points_path_builder
                         .move_to((x + scale * 0.005) as f32, (y + scale * 0.005) as f32);
@@ -282,20 +541,128 @@ impl PNGOutput {
                 self.non_zero_paths.push((non_zero_path, hue, brightness));
             }
 
-            if let Some(points_path) = points_path_builder.finish() {
+            if self.intensity.color {
+                // markers are drawn again, one path per brightness
+                for (brightness, level_positions) in
+                    self.intensity.levels(&points, &positions, brightness)
+                {
+                    if let Some(points_path) =
+                        png_squares_path(level_positions.iter(), clip_pos, scale, 0.01)
+                    {
+                        self.points_paths.push((points_path, hue, brightness));
+                    }
+                }
+            } else if let Some(points_path) = points_path_builder.finish() {
                 self.points_paths.push((points_path, hue, brightness));
             }
         }
     }
 
+    /// Draws line segments over the scans, marking their ends.
+    pub fn add_segments(&mut self, segments: &[LineSegment], clip_pos: f64, scale: f64, hue: f64) {
+        let mut path_builder = PathBuilder::new();
+        for segment in segments {
+            path_builder.move_to(
+                (scale * (segment.start.x + clip_pos)) as f32,
+                (scale * (segment.start.y + clip_pos)) as f32,
+            );
+            path_builder.line_to(
+                (scale * (segment.end.x + clip_pos)) as f32,
+                (scale * (segment.end.y + clip_pos)) as f32,
+            );
+        }
+        if let Some(path) = path_builder.finish() {
+            self.segment_paths.push((path, hue, 50));
+        }
+
+        let ends = segments
+            .iter()
+            .flat_map(|segment| [&segment.start, &segment.end]);
+        if let Some(markers) = png_squares_path(ends, clip_pos, scale, 0.02) {
+            self.segment_paths.push((markers, hue, 80));
+        }
+    }
+
+    /// Marks corners with a cross and circles with their outline,
+    /// brighter the more confident.
+    pub fn add_landmarks(&mut self, landmarks: &[Landmark], clip_pos: f64, scale: f64, hue: f64) {
+        for landmark in landmarks {
+            let mut path_builder = PathBuilder::new();
+            for line in landmark_lines(landmark) {
+                path_builder.move_to(
+                    (scale * (line[0].x + clip_pos)) as f32,
+                    (scale * (line[0].y + clip_pos)) as f32,
+                );
+                for j in &line[1..] {
+                    path_builder.line_to(
+                        (scale * (j.x + clip_pos)) as f32,
+                        (scale * (j.y + clip_pos)) as f32,
+                    );
+                }
+            }
+            if let Some(path) = path_builder.finish() {
+                self.landmark_paths
+                    .push((path, hue, landmark_brightness(landmark)));
+            }
+        }
+    }
+
+    /// Draws the path along the trajectory over the scans,
+    /// marking every pose and the heading at it.
+    pub fn add_trajectory(&mut self, trajectory: &Trajectory, clip_pos: f64, scale: f64, hue: f64) {
+        let positions = trajectory.positions();
+        if positions.is_empty() {
+            return;
+        }
+        let mut path_builder = PathBuilder::new();
+        path_builder.move_to(
+            (scale * (positions[0].x + clip_pos)) as f32,
+            (scale * (positions[0].y + clip_pos)) as f32,
+        );
+        for j in &positions[1..] {
+            path_builder.line_to(
+                (scale * (j.x + clip_pos)) as f32,
+                (scale * (j.y + clip_pos)) as f32,
+            );
+        }
+        if let Some(path) = path_builder.finish() {
+            self.trajectory_paths.push((path, hue, 50));
+        }
+
+        let mut markers_builder = PathBuilder::new();
+        for point in &trajectory.points {
+            let (sin, cos) = point.pose.theta.sin_cos();
+            let x = scale * (point.pose.x + clip_pos);
+            let y = scale * (point.pose.y + clip_pos);
+            markers_builder.move_to(x as f32, y as f32);
+            markers_builder.line_to(
+                (x + scale * HEADING_LENGTH * cos) as f32,
+                (y + scale * HEADING_LENGTH * sin) as f32,
+            );
+        }
+        if let Some(markers) = markers_builder.finish() {
+            self.trajectory_paths.push((markers, hue, 80));
+        }
+        if let Some(markers) = png_squares_path(positions.iter(), clip_pos, scale, 0.03) {
+            self.trajectory_paths.push((markers, hue, 80));
+        }
+    }
+
     pub fn combine(mut a: PNGOutput, mut b: PNGOutput) -> PNGOutput {
         a.all_paths.append(&mut b.all_paths);
         a.non_zero_paths.append(&mut b.non_zero_paths);
         a.points_paths.append(&mut b.points_paths);
+        a.segment_paths.append(&mut b.segment_paths);
+        a.landmark_paths.append(&mut b.landmark_paths);
+        a.trajectory_paths.append(&mut b.trajectory_paths);
         PNGOutput {
             all_paths: a.all_paths,
             non_zero_paths: a.non_zero_paths,
             points_paths: a.points_paths,
+            segment_paths: a.segment_paths,
+            landmark_paths: a.landmark_paths,
+            trajectory_paths: a.trajectory_paths,
+            intensity: a.intensity,
         }
     }
 
@@ -306,8 +673,10 @@ impl PNGOutput {
         )
         .unwrap();
 
-        let mut paint = Paint::default();
-        paint.anti_alias = true;
+        let mut paint = Paint {
+            anti_alias: true,
+            ..Paint::default()
+        };
 
         paint.set_color_rgba8(0, 0, 0, 255);
         pixmap
@@ -325,30 +694,114 @@ impl PNGOutput {
             )
             .unwrap();
 
-        let mut stroke = Stroke::default();
-        stroke.width = (0.0005 * scale) as f32;
+        let stroke = Stroke {
+            width: (0.0005 * scale) as f32,
+            ..Stroke::default()
+        };
         for i in &self.all_paths {
             let rgba = Hsl::new(i.1 as f32, 0.4, (i.2 as f64 / 100.0) as f32).to_rgb();
             paint.set_color_rgba8(rgba.r, rgba.g, rgba.b, (0.3 * 255.0) as u8);
             pixmap.stroke_path(&i.0, &paint, &stroke, Transform::identity(), None);
         }
 
-        let mut stroke = Stroke::default();
-        stroke.width = (0.003 * scale) as f32;
+        let stroke = Stroke {
+            width: (0.003 * scale) as f32,
+            ..Stroke::default()
+        };
         for i in &self.non_zero_paths {
             let rgba = Hsl::new(i.1 as f32, 0.7, (i.2 as f64 / 100.0) as f32).to_rgb();
             paint.set_color_rgba8(rgba.r, rgba.g, rgba.b, (0.6 * 255.0) as u8);
             pixmap.stroke_path(&i.0, &paint, &stroke, Transform::identity(), None);
         }
 
-        let mut stroke = Stroke::default();
-        stroke.width = (0.002 * scale) as f32;
+        let stroke = Stroke {
+            width: (0.002 * scale) as f32,
+            ..Stroke::default()
+        };
         for i in &self.points_paths {
             let rgba = Hsl::new(i.1 as f32, 1.0, (i.2 as f64 / 100.0) as f32).to_rgb();
             paint.set_color_rgba8(rgba.r, rgba.g, rgba.b, (0.8 * 255.0) as u8);
             pixmap.stroke_path(&i.0, &paint, &stroke, Transform::identity(), None);
         }
 
+        let stroke = Stroke {
+            width: (0.006 * scale) as f32,
+            ..Stroke::default()
+        };
+        for i in &self.segment_paths {
+            let rgba = Hsl::new(i.1 as f32, 1.0, (i.2 as f64 / 100.0) as f32).to_rgb();
+            paint.set_color_rgba8(rgba.r, rgba.g, rgba.b, (0.9 * 255.0) as u8);
+            pixmap.stroke_path(&i.0, &paint, &stroke, Transform::identity(), None);
+        }
+
+        let stroke = Stroke {
+            width: (0.004 * scale) as f32,
+            ..Stroke::default()
+        };
+        for i in &self.landmark_paths {
+            let rgba = Hsl::new(i.1 as f32, 1.0, (i.2 as f64 / 100.0) as f32).to_rgb();
+            paint.set_color_rgba8(rgba.r, rgba.g, rgba.b, (0.9 * 255.0) as u8);
+            pixmap.stroke_path(&i.0, &paint, &stroke, Transform::identity(), None);
+        }
+
+        for i in &self.trajectory_paths {
+            let rgba = Hsl::new(i.1 as f32, 1.0, (i.2 as f64 / 100.0) as f32).to_rgb();
+            paint.set_color_rgba8(rgba.r, rgba.g, rgba.b, (0.9 * 255.0) as u8);
+            pixmap.stroke_path(&i.0, &paint, &stroke, Transform::identity(), None);
+        }
+
         pixmap
     }
 }
+
+// polylines marking a landmark, in meters
+fn landmark_lines(landmark: &Landmark) -> Vec<Vec<CartesianPoint>> {
+    let center = landmark.position;
+    match landmark.kind {
+        LandmarkKind::Corner => {
+            let half = CORNER_MARK / 2.0;
+            vec![
+                vec![
+                    CartesianPoint::new(center.x - half, center.y - half),
+                    CartesianPoint::new(center.x + half, center.y + half),
+                ],
+                vec![
+                    CartesianPoint::new(center.x - half, center.y + half),
+                    CartesianPoint::new(center.x + half, center.y - half),
+                ],
+            ]
+        }
+        LandmarkKind::Circle { radius } => vec![(0..=CIRCLE_SIDES)
+            .map(|i| {
+                let (sin, cos) = (2.0 * PI * i as f64 / CIRCLE_SIDES as f64).sin_cos();
+                CartesianPoint::new(center.x + radius * cos, center.y + radius * sin)
+            })
+            .collect()],
+    }
+}
+
+fn landmark_brightness(landmark: &Landmark) -> u32 {
+    30 + (50.0 * landmark.confidence.clamp(0.0, 1.0)) as u32
+}
+
+// square markers around every point
+fn png_squares_path<'a>(
+    points: impl Iterator<Item = &'a CartesianPoint>,
+    clip_pos: f64,
+    scale: f64,
+    square_size: f64,
+) -> Option<tiny_skia::Path> {
+    let half = scale * square_size / 2.0;
+    let mut builder = PathBuilder::new();
+    for j in points {
+        let x = scale * (j.x + clip_pos);
+        let y = scale * (j.y + clip_pos);
+        builder.move_to((x + half) as f32, (y + half) as f32);
+        builder.line_to((x - half) as f32, (y + half) as f32);
+        builder.line_to((x - half) as f32, (y - half) as f32);
+        builder.line_to((x + half) as f32, (y - half) as f32);
+        builder.line_to((x + half) as f32, (y + half) as f32);
+        builder.close();
+    }
+    builder.finish()
+}
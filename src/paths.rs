@@ -0,0 +1,325 @@
+use crate::input::STDIN_PATH;
+use std::cmp::Ordering;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Turns the paths given by the user into the list of files to load.
+/// Directories are walked recursively, and paths containing `*`, `?` or `[`
+/// that don't exist literally are expanded as glob patterns (`**` matches
+/// any number of directories). Files found this way are natural sorted,
+/// so `2` comes before `10`, and filtered by the include/exclude patterns.
+/// Files given explicitly, and `-` for stdin, are always kept, in the order given.
+/// Stdin can only be given once.
+#[derive(Clone, Debug, Default)]
+pub struct PathExpander {
+    include: Vec<String>,
+    exclude: Vec<String>,
+}
+
+impl PathExpander {
+    pub fn new() -> PathExpander {
+        PathExpander {
+            include: Vec::new(),
+            exclude: Vec::new(),
+        }
+    }
+
+    /// Only keep files matching at least one include pattern.
+    /// Patterns containing `/` are matched against the whole path,
+    /// others against the file name.
+    pub fn include(mut self, pattern: &str) -> PathExpander {
+        self.include.push(pattern.to_string());
+        self
+    }
+
+    /// Drop files matching any exclude pattern, matched like [`PathExpander::include`].
+    pub fn exclude(mut self, pattern: &str) -> PathExpander {
+        self.exclude.push(pattern.to_string());
+        self
+    }
+
+    pub fn expand(&self, inputs: &[String]) -> Result<Vec<String>, String> {
+        let mut files = Vec::new();
+        for input in inputs {
+            let path = Path::new(input);
+            if input == STDIN_PATH {
+                if files.iter().any(|file| file == STDIN_PATH) {
+                    return Err("Stdin can only be read once".to_string());
+                }
+                files.push(input.to_string());
+            } else if path.is_dir() {
+                let mut found = Vec::new();
+                walk_dir(path, &mut found);
+                self.push_found(found, &mut files);
+            } else if path.exists() {
+                files.push(input.to_string());
+            } else if is_glob(input) {
+                let found = expand_glob(input);
+                if found.is_empty() {
+                    return Err(format!("Given pattern {} matched no file", input));
+                }
+                self.push_found(found, &mut files);
+            } else {
+                return Err(format!("Given filepath {} do not exist", input));
+            }
+        }
+        Ok(files)
+    }
+
+    fn push_found(&self, found: Vec<PathBuf>, files: &mut Vec<String>) {
+        // directories matched by a pattern are loaded whole
+        let found = found.into_iter().fold(Vec::new(), |mut found, path| {
+            if path.is_dir() {
+                walk_dir(&path, &mut found);
+            } else if path.is_file() {
+                found.push(path);
+            }
+            found
+        });
+        let mut found: Vec<String> = found
+            .iter()
+            .filter_map(|path| path.to_str())
+            .filter(|path| self.keeps(path))
+            .map(|path| path.to_string())
+            .collect();
+        found.sort_by(|a, b| natural_cmp(a, b));
+        found.dedup();
+        files.append(&mut found);
+    }
+
+    fn keeps(&self, path: &str) -> bool {
+        let name = Path::new(path)
+            .file_name()
+            .and_then(|name| name.to_str())
+            .unwrap_or(path);
+        let matches = |pattern: &String| {
+            if pattern.contains('/') {
+                glob_match(pattern, path)
+            } else {
+                glob_match(pattern, name)
+            }
+        };
+        (self.include.is_empty() || self.include.iter().any(matches))
+            && !self.exclude.iter().any(matches)
+    }
+}
+
+pub fn is_glob(path: &str) -> bool {
+    path.contains(['*', '?', '['])
+}
+
+/// Matches text against a shell style pattern: `*` matches any run of
+/// characters, `?` any single character and `[...]` any character in the
+/// set, which can hold ranges like `a-z` and be negated with a leading `!`.
+pub fn glob_match(pattern: &str, text: &str) -> bool {
+    let pattern: Vec<char> = pattern.chars().collect();
+    let text: Vec<char> = text.chars().collect();
+
+    // position to retry from after the last `*`, in pattern and text
+    let mut backtrack: Option<(usize, usize)> = None;
+    let (mut p, mut t) = (0, 0);
+    while t < text.len() {
+        if p < pattern.len() {
+            match pattern[p] {
+                '*' => {
+                    backtrack = Some((p, t));
+                    p += 1;
+                    continue;
+                }
+                '?' => {
+                    p += 1;
+                    t += 1;
+                    continue;
+                }
+                '[' => {
+                    if let Some((matched, len)) = match_class(&pattern[p..], text[t]) {
+                        if matched {
+                            p += len;
+                            t += 1;
+                            continue;
+                        }
+                    } else if text[t] == '[' {
+                        // unclosed bracket is taken literally
+                        p += 1;
+                        t += 1;
+                        continue;
+                    }
+                }
+                c => {
+                    if c == text[t] {
+                        p += 1;
+                        t += 1;
+                        continue;
+                    }
+                }
+            }
+        }
+
+        // mismatch, let the last `*` eat one more character
+        match backtrack {
+            Some((star, star_t)) => {
+                backtrack = Some((star, star_t + 1));
+                p = star + 1;
+                t = star_t + 1;
+            }
+            None => return false,
+        }
+    }
+
+    pattern[p..].iter().all(|&c| c == '*')
+}
+
+// match c against the class at the start of pattern,
+// giving if it matched and the length of the class
+fn match_class(pattern: &[char], c: char) -> Option<(bool, usize)> {
+    let mut i = 1;
+    let negated = matches!(pattern.get(i), Some('!') | Some('^'));
+    if negated {
+        i += 1;
+    }
+
+    let mut matched = false;
+    let mut first = true;
+    while i < pattern.len() {
+        if pattern[i] == ']' && !first {
+            return Some((matched != negated, i + 1));
+        }
+        if i + 2 < pattern.len() && pattern[i + 1] == '-' && pattern[i + 2] != ']' {
+            matched |= pattern[i] <= c && c <= pattern[i + 2];
+            i += 3;
+        } else {
+            matched |= pattern[i] == c;
+            i += 1;
+        }
+        first = false;
+    }
+    None
+}
+
+/// Compares strings so that runs of digits are compared by their value,
+/// sorting `file2` before `file10`.
+pub fn natural_cmp(a: &str, b: &str) -> Ordering {
+    let mut a = a.chars().peekable();
+    let mut b = b.chars().peekable();
+    loop {
+        match (a.peek().copied(), b.peek().copied()) {
+            (None, None) => return Ordering::Equal,
+            (None, Some(_)) => return Ordering::Less,
+            (Some(_), None) => return Ordering::Greater,
+            (Some(x), Some(y)) if x.is_ascii_digit() && y.is_ascii_digit() => {
+                let mut x_digits = String::new();
+                while let Some(c) = a.next_if(|c| c.is_ascii_digit()) {
+                    x_digits.push(c);
+                }
+                let mut y_digits = String::new();
+                while let Some(c) = b.next_if(|c| c.is_ascii_digit()) {
+                    y_digits.push(c);
+                }
+                let x_trimmed = x_digits.trim_start_matches('0');
+                let y_trimmed = y_digits.trim_start_matches('0');
+                let ordering = x_trimmed
+                    .len()
+                    .cmp(&y_trimmed.len())
+                    .then_with(|| x_trimmed.cmp(y_trimmed))
+                    .then_with(|| x_digits.len().cmp(&y_digits.len()));
+                if ordering != Ordering::Equal {
+                    return ordering;
+                }
+            }
+            (Some(x), Some(y)) => {
+                if x != y {
+                    return x.cmp(&y);
+                }
+                a.next();
+                b.next();
+            }
+        }
+    }
+}
+
+// every non hidden file under dir
+fn walk_dir(dir: &Path, found: &mut Vec<PathBuf>) {
+    let entries = match fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(_) => return,
+    };
+    for entry in entries.flatten() {
+        if entry.file_name().to_string_lossy().starts_with('.') {
+            continue;
+        }
+        let path = entry.path();
+        if path.is_dir() {
+            walk_dir(&path, found);
+        } else {
+            found.push(path);
+        }
+    }
+}
+
+// every subdirectory under dir, including dir itself
+fn walk_subdirs(dir: &Path, found: &mut Vec<PathBuf>) {
+    found.push(dir.to_path_buf());
+    let entries = match fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(_) => return,
+    };
+    for entry in entries.flatten() {
+        let path = entry.path();
+        if path.is_dir() && !entry.file_name().to_string_lossy().starts_with('.') {
+            walk_subdirs(&path, found);
+        }
+    }
+}
+
+fn expand_glob(pattern: &str) -> Vec<PathBuf> {
+    let mut current: Vec<PathBuf> = if pattern.starts_with('/') {
+        vec![PathBuf::from("/")]
+    } else {
+        vec![PathBuf::new()]
+    };
+
+    for component in pattern.split('/').filter(|component| !component.is_empty()) {
+        let mut next = Vec::new();
+        for base in &current {
+            let dir = if base.as_os_str().is_empty() {
+                Path::new(".")
+            } else {
+                base.as_path()
+            };
+            if component == "**" {
+                walk_subdirs(dir, &mut next);
+                // keep paths relative when the pattern is
+                if base.as_os_str().is_empty() {
+                    for path in next.iter_mut() {
+                        if let Ok(stripped) = path.strip_prefix(".") {
+                            *path = stripped.to_path_buf();
+                        }
+                    }
+                }
+            } else if is_glob(component) {
+                let entries = match fs::read_dir(dir) {
+                    Ok(entries) => entries,
+                    Err(_) => continue,
+                };
+                for entry in entries.flatten() {
+                    let name = entry.file_name();
+                    let name = name.to_string_lossy();
+                    // hidden files have to be asked for explicitly
+                    if name.starts_with('.') && !component.starts_with('.') {
+                        continue;
+                    }
+                    if glob_match(component, &name) {
+                        next.push(base.join(name.as_ref()));
+                    }
+                }
+            } else {
+                let path = base.join(component);
+                if path.exists() {
+                    next.push(path);
+                }
+            }
+        }
+        current = next;
+    }
+    current
+}
@@ -0,0 +1,523 @@
+//! Binary pointfile container, much faster to load than the text format.
+//!
+//! ```text
+//! header:  b"PTFLBIN\0"  u16 version  u16 flags
+//! scan:    u32 point count
+//!          u8 has timestamp, followed by f64 timestamp if it's 1
+//!          u32 metadata count, followed by u32 length prefixed key and value strings
+//!          angles of all points, then ranges of all points
+//!          u8 extra columns: bit 0 intensities, bit 1 flags
+//!          for every column set, a bitmap of the points having a value
+//!            followed by the values of all points, 0 where they have none
+//!          u32 crc32 of everything above in the scan, if FLAG_CHECKSUM is set
+//! ```
+//!
+//! All numbers are little endian, angles, ranges and intensities are f32 if
+//! FLAG_SINGLE_PRECISION is set and f64 otherwise, flags are u32.
+//! Bitmaps take a bit per point, lowest bit of the first byte first.
+
+use crate::error::ParseError;
+use crate::ptfl_parse::source_name;
+use crate::scan::{Point, Scan};
+use std::io;
+use std::io::{BufRead, Read, Write};
+
+pub const MAGIC: &[u8; 8] = b"PTFLBIN\0";
+pub const VERSION: u16 = 1;
+
+const FLAG_SINGLE_PRECISION: u16 = 1;
+const FLAG_CHECKSUM: u16 = 1 << 1;
+
+const COLUMN_INTENSITY: u8 = 1;
+const COLUMN_FLAGS: u8 = 1 << 1;
+
+/// Checks if the reader starts with the binary magic bytes without consuming them.
+pub fn is_binary<R: BufRead>(reader: &mut R) -> io::Result<bool> {
+    Ok(reader.fill_buf()?.starts_with(MAGIC))
+}
+
+/// Writes scans into the binary container.
+/// The header is written along with the first scan, or by
+/// [`BinaryWriter::finish`] if no scan is written at all.
+pub struct BinaryWriter<W: Write> {
+    writer: W,
+    flags: u16,
+    header_written: bool,
+    buffer: Vec<u8>,
+}
+
+impl<W: Write> BinaryWriter<W> {
+    /// Writes f64 values with checksums by default.
+    pub fn new(writer: W) -> BinaryWriter<W> {
+        BinaryWriter {
+            writer,
+            flags: FLAG_CHECKSUM,
+            header_written: false,
+            buffer: Vec::new(),
+        }
+    }
+
+    /// Store angles and ranges as f32, halving the file size.
+    pub fn single_precision(mut self, single_precision: bool) -> BinaryWriter<W> {
+        self.set_flag(FLAG_SINGLE_PRECISION, single_precision);
+        self
+    }
+
+    pub fn checksum(mut self, checksum: bool) -> BinaryWriter<W> {
+        self.set_flag(FLAG_CHECKSUM, checksum);
+        self
+    }
+
+    fn set_flag(&mut self, flag: u16, set: bool) {
+        assert!(!self.header_written, "flags changed after writing header");
+        if set {
+            self.flags |= flag;
+        } else {
+            self.flags &= !flag;
+        }
+    }
+
+    fn write_header(&mut self) -> io::Result<()> {
+        if !self.header_written {
+            self.writer.write_all(MAGIC)?;
+            self.writer.write_all(&VERSION.to_le_bytes())?;
+            self.writer.write_all(&self.flags.to_le_bytes())?;
+            self.header_written = true;
+        }
+        Ok(())
+    }
+
+    pub fn write_scan(&mut self, scan: &Scan) -> io::Result<()> {
+        if scan.points().is_empty() {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!(
+                    "{}-{:04} has no points to write",
+                    scan.source(),
+                    scan.index()
+                ),
+            ));
+        }
+        self.write_header()?;
+
+        // the record is built in memory first so it can be checksummed
+        let buffer = &mut self.buffer;
+        buffer.clear();
+        buffer.extend_from_slice(&(scan.points().len() as u32).to_le_bytes());
+        match scan.timestamp {
+            Some(timestamp) => {
+                buffer.push(1);
+                buffer.extend_from_slice(&timestamp.to_le_bytes());
+            }
+            None => buffer.push(0),
+        }
+        buffer.extend_from_slice(&(scan.metadata.len() as u32).to_le_bytes());
+        for (key, value) in &scan.metadata {
+            for string in [key, value] {
+                buffer.extend_from_slice(&(string.len() as u32).to_le_bytes());
+                buffer.extend_from_slice(string.as_bytes());
+            }
+        }
+
+        let single_precision = self.flags & FLAG_SINGLE_PRECISION != 0;
+        for point in scan.points() {
+            push_value(buffer, point.angle, single_precision);
+        }
+        for point in scan.points() {
+            push_value(buffer, point.range, single_precision);
+        }
+
+        let mut columns = 0;
+        if scan.points().iter().any(|point| point.intensity.is_some()) {
+            columns |= COLUMN_INTENSITY;
+        }
+        if scan.points().iter().any(|point| point.flags.is_some()) {
+            columns |= COLUMN_FLAGS;
+        }
+        buffer.push(columns);
+        if columns & COLUMN_INTENSITY != 0 {
+            push_bitmap(
+                buffer,
+                scan.points().iter().map(|point| point.intensity.is_some()),
+            );
+            for point in scan.points() {
+                push_value(buffer, point.intensity.unwrap_or(0.0), single_precision);
+            }
+        }
+        if columns & COLUMN_FLAGS != 0 {
+            push_bitmap(
+                buffer,
+                scan.points().iter().map(|point| point.flags.is_some()),
+            );
+            for point in scan.points() {
+                buffer.extend_from_slice(&point.flags.unwrap_or(0).to_le_bytes());
+            }
+        }
+
+        if self.flags & FLAG_CHECKSUM != 0 {
+            let checksum = crc32(buffer);
+            buffer.extend_from_slice(&checksum.to_le_bytes());
+        }
+        self.writer.write_all(buffer)
+    }
+
+    pub fn write_scans<'a, I>(&mut self, scans: I) -> io::Result<()>
+    where
+        I: IntoIterator<Item = &'a Scan>,
+    {
+        for scan in scans {
+            self.write_scan(scan)?;
+        }
+        Ok(())
+    }
+
+    /// Makes sure the header is written and flushes the writer.
+    pub fn finish(mut self) -> io::Result<W> {
+        self.write_header()?;
+        self.writer.flush()?;
+        Ok(self.writer)
+    }
+}
+
+/// Reads scans one at a time from a binary pointfile,
+/// the counterpart of [`crate::ScanReader`] for the text format.
+///
+/// A scan failing its checksum is yielded as an `Err`
+/// and reading goes on, any other error ends the iteration.
+pub struct BinaryScanReader<R: Read> {
+    reader: R,
+    path: String,
+    source: String,
+    flags: u16,
+    offset: u64,
+    next_index: u32,
+    buffer: Vec<u8>,
+    done: bool,
+}
+
+impl<R: Read> BinaryScanReader<R> {
+    /// Reads and checks the header, `path` is used the same way as in [`crate::ScanReader::new`].
+    pub fn new(mut reader: R, path: &str) -> Result<BinaryScanReader<R>, ParseError> {
+        let mut header = [0u8; 12];
+        if let Err(err) = reader.read_exact(&mut header) {
+            return Err(io_or_corrupt(path, 0, err, "missing header"));
+        }
+        if &header[..8] != MAGIC {
+            return Err(ParseError::Corrupt {
+                path: path.to_string(),
+                offset: 0,
+                reason: "not a binary pointfile".to_string(),
+            });
+        }
+        let version = u16::from_le_bytes([header[8], header[9]]);
+        if version != VERSION {
+            return Err(ParseError::UnsupportedVersion {
+                path: path.to_string(),
+                version,
+            });
+        }
+        let flags = u16::from_le_bytes([header[10], header[11]]);
+        if flags & !(FLAG_SINGLE_PRECISION | FLAG_CHECKSUM) != 0 {
+            return Err(ParseError::Corrupt {
+                path: path.to_string(),
+                offset: 10,
+                reason: "unknown header flags".to_string(),
+            });
+        }
+
+        Ok(BinaryScanReader {
+            reader,
+            path: path.to_string(),
+            source: source_name(path),
+            flags,
+            offset: header.len() as u64,
+            next_index: 0,
+            buffer: Vec::new(),
+            done: false,
+        })
+    }
+
+    // read exactly len bytes onto the end of self.buffer, lengths come
+    // from the file so the buffer only grows as the data actually arrives
+    fn read_into_buffer(&mut self, len: usize) -> Result<(), ParseError> {
+        let start = self.buffer.len();
+        let read = (&mut self.reader)
+            .take(len as u64)
+            .read_to_end(&mut self.buffer)
+            .map_err(|err| ParseError::Io {
+                path: self.path.clone(),
+                source: err,
+            })?;
+        if read < len {
+            return Err(self.corrupt(start, "file ended inside a scan"));
+        }
+        Ok(())
+    }
+
+    fn read_u32(&mut self) -> Result<u32, ParseError> {
+        let start = self.buffer.len();
+        self.read_into_buffer(4)?;
+        Ok(u32::from_le_bytes(
+            self.buffer[start..start + 4].try_into().unwrap(),
+        ))
+    }
+
+    fn read_string(&mut self) -> Result<String, ParseError> {
+        let len = self.read_u32()? as usize;
+        let start = self.buffer.len();
+        self.read_into_buffer(len)?;
+        match std::str::from_utf8(&self.buffer[start..]) {
+            Ok(string) => Ok(string.to_string()),
+            Err(_) => Err(self.corrupt(start, "invalid utf-8 in metadata")),
+        }
+    }
+
+    fn corrupt(&self, at: usize, reason: &str) -> ParseError {
+        ParseError::Corrupt {
+            path: self.path.clone(),
+            offset: self.offset + at as u64,
+            reason: reason.to_string(),
+        }
+    }
+
+    fn read_columns(&mut self, points: &mut [Point]) -> Result<(), ParseError> {
+        let count = points.len();
+        let bitmap_len = count.div_ceil(8);
+        let value_len = if self.flags & FLAG_SINGLE_PRECISION != 0 {
+            4
+        } else {
+            8
+        };
+
+        let columns_at = self.buffer.len();
+        self.read_into_buffer(1)?;
+        let columns = self.buffer[columns_at];
+        if columns & !(COLUMN_INTENSITY | COLUMN_FLAGS) != 0 {
+            return Err(self.corrupt(columns_at, "unknown extra columns"));
+        }
+
+        if columns & COLUMN_INTENSITY != 0 {
+            let start = self.buffer.len();
+            self.read_into_buffer(bitmap_len + count * value_len)?;
+            let (bitmap, values) = self.buffer[start..].split_at(bitmap_len);
+            for (i, (point, value)) in points
+                .iter_mut()
+                .zip(values.chunks_exact(value_len))
+                .enumerate()
+            {
+                if bitmap[i / 8] & (1 << (i % 8)) != 0 {
+                    point.intensity = Some(if value_len == 4 {
+                        f32::from_le_bytes(value.try_into().unwrap()) as f64
+                    } else {
+                        f64::from_le_bytes(value.try_into().unwrap())
+                    });
+                }
+            }
+        }
+        if columns & COLUMN_FLAGS != 0 {
+            let start = self.buffer.len();
+            self.read_into_buffer(bitmap_len + count * 4)?;
+            let (bitmap, values) = self.buffer[start..].split_at(bitmap_len);
+            for (i, (point, value)) in points.iter_mut().zip(values.chunks_exact(4)).enumerate() {
+                if bitmap[i / 8] & (1 << (i % 8)) != 0 {
+                    point.flags = Some(u32::from_le_bytes(value.try_into().unwrap()));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    // None on a clean end of file right before a scan
+    fn read_scan(&mut self) -> Result<Option<Scan>, ParseError> {
+        self.buffer.clear();
+        let mut count = [0u8; 4];
+        match read_or_eof(&mut self.reader, &mut count) {
+            Ok(false) => return Ok(None),
+            Ok(true) => self.buffer.extend_from_slice(&count),
+            Err(err) => {
+                return Err(io_or_corrupt(
+                    &self.path,
+                    self.offset,
+                    err,
+                    "file ended inside a scan",
+                ))
+            }
+        }
+        let count = u32::from_le_bytes(count) as usize;
+        if count == 0 {
+            return Err(self.corrupt(0, "scan with zero points"));
+        }
+
+        self.read_into_buffer(1)?;
+        let timestamp = match self.buffer[4] {
+            0 => None,
+            1 => {
+                self.read_into_buffer(8)?;
+                Some(f64::from_le_bytes(self.buffer[5..13].try_into().unwrap()))
+            }
+            _ => return Err(self.corrupt(4, "invalid timestamp marker")),
+        };
+
+        // points are allocated once their values are read
+        let mut scan = Scan::new(&self.source, self.next_index, Vec::new());
+        scan.timestamp = timestamp;
+        for _ in 0..self.read_u32()? {
+            let key = self.read_string()?;
+            let value = self.read_string()?;
+            scan.metadata.insert(key, value);
+        }
+
+        let start = self.buffer.len();
+        if self.flags & FLAG_SINGLE_PRECISION != 0 {
+            self.read_into_buffer(count * 8)?;
+            let (angles, ranges) = self.buffer[start..].split_at(count * 4);
+            scan.points_mut()
+                .extend(angles.chunks_exact(4).zip(ranges.chunks_exact(4)).map(
+                    |(angle, range)| {
+                        Point::new(
+                            f32::from_le_bytes(angle.try_into().unwrap()) as f64,
+                            f32::from_le_bytes(range.try_into().unwrap()) as f64,
+                        )
+                    },
+                ));
+        } else {
+            self.read_into_buffer(count * 16)?;
+            let (angles, ranges) = self.buffer[start..].split_at(count * 8);
+            scan.points_mut()
+                .extend(angles.chunks_exact(8).zip(ranges.chunks_exact(8)).map(
+                    |(angle, range)| {
+                        Point::new(
+                            f64::from_le_bytes(angle.try_into().unwrap()),
+                            f64::from_le_bytes(range.try_into().unwrap()),
+                        )
+                    },
+                ));
+        }
+
+        self.read_columns(scan.points_mut())?;
+
+        let record_len = self.buffer.len();
+        let scan_offset = self.offset;
+        self.offset += record_len as u64;
+        self.next_index += 1;
+        if self.flags & FLAG_CHECKSUM != 0 {
+            let expected = crc32(&self.buffer);
+            let mut checksum = [0u8; 4];
+            if let Err(err) = self.reader.read_exact(&mut checksum) {
+                return Err(io_or_corrupt(
+                    &self.path,
+                    self.offset,
+                    err,
+                    "file ended inside a scan",
+                ));
+            }
+            self.offset += 4;
+            if u32::from_le_bytes(checksum) != expected {
+                return Err(ParseError::ChecksumMismatch {
+                    path: self.path.clone(),
+                    offset: scan_offset,
+                    index: scan.index(),
+                });
+            }
+        }
+        Ok(Some(scan))
+    }
+}
+
+impl<R: Read> Iterator for BinaryScanReader<R> {
+    type Item = Result<Scan, ParseError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+        match self.read_scan() {
+            Ok(Some(scan)) => Some(Ok(scan)),
+            Ok(None) => {
+                self.done = true;
+                None
+            }
+            Err(err @ ParseError::ChecksumMismatch { .. }) => Some(Err(err)),
+            Err(err) => {
+                self.done = true;
+                Some(Err(err))
+            }
+        }
+    }
+}
+
+fn push_value(buffer: &mut Vec<u8>, value: f64, single_precision: bool) {
+    if single_precision {
+        buffer.extend_from_slice(&(value as f32).to_le_bytes());
+    } else {
+        buffer.extend_from_slice(&value.to_le_bytes());
+    }
+}
+
+fn push_bitmap<I: ExactSizeIterator<Item = bool>>(buffer: &mut Vec<u8>, bits: I) {
+    let start = buffer.len();
+    buffer.resize(start + bits.len().div_ceil(8), 0);
+    for (i, bit) in bits.enumerate() {
+        if bit {
+            buffer[start + i / 8] |= 1 << (i % 8);
+        }
+    }
+}
+
+// fill buf, false if the reader ended before giving anything
+fn read_or_eof<R: Read>(reader: &mut R, buf: &mut [u8]) -> io::Result<bool> {
+    let mut read = 0;
+    while read < buf.len() {
+        match reader.read(&mut buf[read..]) {
+            Ok(0) if read == 0 => return Ok(false),
+            Ok(0) => return Err(io::ErrorKind::UnexpectedEof.into()),
+            Ok(n) => read += n,
+            Err(err) if err.kind() == io::ErrorKind::Interrupted => {}
+            Err(err) => return Err(err),
+        }
+    }
+    Ok(true)
+}
+
+// an unexpected end of file means the file is cut, anything else is a real io error
+fn io_or_corrupt(path: &str, offset: u64, err: io::Error, reason: &str) -> ParseError {
+    if err.kind() == io::ErrorKind::UnexpectedEof {
+        ParseError::Corrupt {
+            path: path.to_string(),
+            offset,
+            reason: reason.to_string(),
+        }
+    } else {
+        ParseError::Io {
+            path: path.to_string(),
+            source: err,
+        }
+    }
+}
+
+// crc32 (ieee 802.3), the one used by zip and png
+fn crc32(data: &[u8]) -> u32 {
+    const TABLE: [u32; 256] = {
+        let mut table = [0u32; 256];
+        let mut i = 0;
+        while i < 256 {
+            let mut crc = i as u32;
+            let mut bit = 0;
+            while bit < 8 {
+                crc = if crc & 1 != 0 {
+                    (crc >> 1) ^ 0xEDB8_8320
+                } else {
+                    crc >> 1
+                };
+                bit += 1;
+            }
+            table[i] = crc;
+            i += 1;
+        }
+        table
+    };
+
+    !data.iter().fold(!0u32, |crc, &byte| {
+        TABLE[((crc ^ byte as u32) & 0xFF) as usize] ^ (crc >> 8)
+    })
+}
@@ -1,124 +1,576 @@
-use indexmap::IndexMap;
-use std::fs;
+use crate::error::ParseError;
+use crate::input::{strip_compression_extension, Compression};
+use crate::ptfl_binary::{is_binary, BinaryScanReader};
+use crate::scan::{Point, Pose, Scan, ScanSet, POSE_KEY, RPM_KEY, TIMESTAMP_KEY};
+use std::collections::VecDeque;
+use std::fs::File;
+use std::io::{BufRead, BufReader};
 use std::path::Path;
 
+// a count header is untrusted input, points beyond this
+// are allocated as they are read instead of up front
+const MAX_PREALLOCATED_POINTS: usize = 1 << 16;
+
 enum ParsingState {
     None,
-    DuringReg(usize),
+    // (remaining, total) points of the current scan
+    DuringReg(usize, usize),
+    // skipping lines until the next count header
+    Resync,
 }
 
+/// The line by line state machine of the pointfile format.
+/// Lines are fed with [`PtflParser::feed_line`], which hands back
+/// a scan once its last line is fed.
+///
+/// A scan can be preceded by optional `#key=value` metadata lines,
+/// `timestamp` is read into [`Scan::timestamp`] and every other key
+/// into [`Scan::metadata`]. `#` lines without a `=` are comments.
 pub struct PtflParser {
     state: ParsingState,
+    current_reg: Vec<Point>,
+    // timestamp and metadata read for the next scan
+    header: Scan,
+    // count headers seen, including scans that were dropped
+    started: u32,
+    recovering: bool,
+    diagnostics: Vec<ParseError>,
+}
+
+/// Outcome of [`PtflParser::parse_recovering`]: the number of scans kept
+/// and every problem that was skipped over to keep them. A scan kept
+/// without some of its points is reported as [`ParseError::ShortScan`].
+#[derive(Debug, Default)]
+pub struct ParseReport {
+    pub scans: u32,
+    pub diagnostics: Vec<ParseError>,
+}
+
+impl Default for PtflParser {
+    fn default() -> PtflParser {
+        PtflParser::new()
+    }
 }
 
 impl PtflParser {
     pub fn new() -> PtflParser {
-        return PtflParser {
+        PtflParser {
             state: ParsingState::None,
-        };
+            current_reg: Vec::new(),
+            header: Scan::default(),
+            started: 0,
+            recovering: false,
+            diagnostics: Vec::new(),
+        }
     }
 
     pub fn renew(&mut self) {
         self.state = ParsingState::None;
+        self.current_reg.clear();
+        self.header = Scan::default();
+        self.started = 0;
+        self.diagnostics.clear();
+    }
+
+    /// In recovering mode malformed lines are skipped and the parser
+    /// resynchronizes on the next count header, the skipped problems
+    /// are kept until [`PtflParser::take_diagnostics`] is called.
+    pub fn set_recovering(&mut self, recovering: bool) {
+        self.recovering = recovering;
+    }
+
+    pub fn take_diagnostics(&mut self) -> Vec<ParseError> {
+        std::mem::take(&mut self.diagnostics)
+    }
+
+    /// Number of scans started so far, counting those dropped for
+    /// being malformed, so a scan is at `scans_started() - 1` in its file.
+    pub fn scans_started(&self) -> u32 {
+        self.started
+    }
+
+    /// Parses every scan in the file into `target`,
+    /// stopping at the first malformed line.
+    pub fn parse(&mut self, path: &str, target: &mut ScanSet) -> Result<u32, ParseError> {
+        let (scans, _) = self.parse_into(path, target, false)?;
+        Ok(scans)
     }
 
-    pub fn parse(
+    /// Parses the file like [`PtflParser::parse`], but skips malformed lines
+    /// and resynchronizes on the next count header instead of aborting.
+    /// Only failing to read the file is returned as an error.
+    pub fn parse_recovering(
         &mut self,
         path: &str,
-        target: &mut IndexMap<(String, u32), Vec<(f64, f64)>>,
-    ) -> Result<u32, String> {
-        let file = match fs::read_to_string(&path) {
-            Ok(file) => file,
-            Err(err) => {
-                return Err(format!("Failed reading file: \n\t\t{}", err.to_string()));
+        target: &mut ScanSet,
+    ) -> Result<ParseReport, ParseError> {
+        let (scans, diagnostics) = self.parse_into(path, target, true)?;
+        Ok(ParseReport { scans, diagnostics })
+    }
+
+    fn parse_into(
+        &mut self,
+        path: &str,
+        target: &mut ScanSet,
+        recovering: bool,
+    ) -> Result<(u32, Vec<ParseError>), ParseError> {
+        let mut scans = open_scans(path, recovering)?;
+        collect_scans(&mut scans, target, recovering)
+    }
+
+    /// Feeds one line (without its line ending) to the parser.
+    /// `path` and `line_num` are only used to describe errors.
+    /// Returns the scan this line completes, if any,
+    /// with its `source` and `index` left for the caller to fill in.
+    ///
+    /// On error the parser is left ready to resynchronize,
+    /// so feeding can go on after it.
+    pub fn feed_line(
+        &mut self,
+        path: &str,
+        line_num: usize,
+        line: &str,
+    ) -> Result<Option<Scan>, ParseError> {
+        match self.state {
+            ParsingState::None => {
+                if line.starts_with('#') {
+                    self.read_metadata(path, line_num, line)?;
+                } else if !line.is_empty() {
+                    match parse_count(path, line_num, line) {
+                        Ok(reg_length) => self.start_reg(reg_length),
+                        Err(err) => {
+                            self.resync();
+                            self.report(err)?;
+                        }
+                    }
+                }
+                Ok(None)
             }
-        };
-
-        let mut current_reg: Vec<(f64, f64)> = Vec::new();
-        let mut file_entry_num: u32 = 0;
-
-        for line in file.lines() {
-            self.state = match self.state {
-                ParsingState::None => {
-                    if line.is_empty() {
-                        ParsingState::None
-                    } else {
-                        let reg_length: usize = match line.parse() {
-                            Ok(length) => {
-                                if length > 0 {
-                                    length
-                                } else {
-                                    return Err(
-                                        "Expected non-zero u32 or empty when None, got Zero"
-                                            .to_string(),
-                                    );
-                                }
-                            }
-                            Err(err) => {
-                                return Err(format!(
-                                    "Expected non-zero u32 or empty when None, {}",
-                                    err.to_string()
-                                ))
+
+            ParsingState::Resync => {
+                if line.starts_with('#') {
+                    self.read_metadata(path, line_num, line)?;
+                } else if let Ok(reg_length) = parse_count(path, line_num, line) {
+                    self.start_reg(reg_length);
+                }
+                Ok(None)
+            }
+
+            ParsingState::DuringReg(remaining, total) => {
+                let next: usize = remaining - 1;
+                match parse_point(path, line_num, line) {
+                    Ok(point) => self.current_reg.push(point),
+                    Err(err) => {
+                        // a count header in the middle of a scan means the scan is cut
+                        // short, drop what we have and start over from the header
+                        if let Ok(reg_length) = line.trim().parse::<usize>() {
+                            self.header = Scan::default();
+                            if reg_length > 0 {
+                                self.start_reg(reg_length);
+                            } else {
+                                self.resync();
                             }
-                        };
-                        current_reg.clear();
-                        current_reg.reserve(reg_length);
-                        ParsingState::DuringReg(reg_length)
+                            self.report(ParseError::Truncated {
+                                path: path.to_string(),
+                                line: line_num - 1,
+                                expected: total,
+                                found: total - remaining,
+                            })?;
+                            return Ok(None);
+                        }
+                        // skipped lines still take their place in the scan
+                        if !self.recovering {
+                            self.resync();
+                        }
+                        self.report(err)?;
                     }
                 }
 
-                ParsingState::DuringReg(remaining) => {
-                    let next: usize = remaining - 1;
-                    let reg: Vec<&str> = line.split(',').collect();
-                    if reg.len() != 2 {
-                        return Err(format!(
-                            "Expected 2 comma separated when DuringReg, get {}",
-                            reg.len()
-                        ));
+                if next == 0 {
+                    self.state = ParsingState::None;
+                    let mut scan = std::mem::take(&mut self.header);
+                    *scan.points_mut() = std::mem::take(&mut self.current_reg);
+                    // every line of it was skipped
+                    if scan.points().is_empty() {
+                        return Ok(None);
+                    }
+                    if scan.points().len() < total {
+                        self.report(ParseError::ShortScan {
+                            path: path.to_string(),
+                            line: line_num,
+                            expected: total,
+                            found: scan.points().len(),
+                        })?;
                     }
+                    Ok(Some(scan))
+                } else {
+                    self.state = ParsingState::DuringReg(next, total);
+                    Ok(None)
+                }
+            }
+        }
+    }
 
-                    let reg: (f64, f64) = (
-                        match reg[0].trim().parse() {
-                            Ok(angle) => angle,
-                            Err(err) => {
-                                return Err(format!(
-                                    "Expected float when DuringReg, {}",
-                                    err.to_string()
-                                ))
-                            }
-                        },
-                        match reg[1].trim().parse() {
-                            Ok(range) => range,
-                            Err(err) => {
-                                return Err(format!(
-                                    "Expected float when DuringReg, {}",
-                                    err.to_string()
-                                ))
-                            }
-                        },
-                    );
-                    current_reg.push(reg);
-                    if next == 0 {
-                        target.insert(
-                            (
-                                Path::new(&path)
-                                    .file_name()
-                                    .expect("Given path isn't a file")
-                                    .to_str()
-                                    .expect("Invalid unicode in filename")
-                                    .to_string(),
-                                file_entry_num,
-                            ),
-                            current_reg.clone(),
-                        );
-                        file_entry_num += 1;
-                        ParsingState::None
-                    } else {
-                        ParsingState::DuringReg(remaining - 1)
+    /// Tells the parser the input ended after `line_num` lines,
+    /// failing if it ended in the middle of a scan.
+    pub fn finish(&mut self, path: &str, line_num: usize) -> Result<(), ParseError> {
+        let state = std::mem::replace(&mut self.state, ParsingState::None);
+        self.current_reg.clear();
+        self.header = Scan::default();
+        if let ParsingState::DuringReg(remaining, total) = state {
+            self.report(ParseError::Truncated {
+                path: path.to_string(),
+                line: line_num,
+                expected: total,
+                found: total - remaining,
+            })?;
+        }
+        Ok(())
+    }
+
+    // metadata read so far belonged to the broken scan
+    fn resync(&mut self) {
+        self.state = ParsingState::Resync;
+        self.header = Scan::default();
+    }
+
+    fn read_metadata(&mut self, path: &str, line_num: usize, line: &str) -> Result<(), ParseError> {
+        match parse_metadata(path, line_num, line) {
+            Ok(Some((key, value))) => {
+                if key == TIMESTAMP_KEY {
+                    self.header.timestamp = value.parse().ok();
+                } else {
+                    self.header.metadata.insert(key, value);
+                }
+                Ok(())
+            }
+            Ok(None) => Ok(()),
+            Err(err) => self.report(err),
+        }
+    }
+
+    fn start_reg(&mut self, reg_length: usize) {
+        self.started += 1;
+        self.current_reg = Vec::with_capacity(reg_length.min(MAX_PREALLOCATED_POINTS));
+        self.state = ParsingState::DuringReg(reg_length, reg_length);
+    }
+
+    // keep the error if recovering, otherwise hand it back
+    fn report(&mut self, err: ParseError) -> Result<(), ParseError> {
+        if self.recovering {
+            self.diagnostics.push(err);
+            Ok(())
+        } else {
+            Err(err)
+        }
+    }
+}
+
+/// Reads scans one at a time from any buffered reader.
+/// Only the scan being parsed is kept in memory, so the caller
+/// can stop early or stream recordings larger than memory.
+///
+/// In recovering mode the problems skipped over are yielded
+/// as `Err` items and reading goes on, otherwise the first
+/// `Err` ends the iteration. Scans are indexed by their position
+/// in the file, so dropped scans leave a gap in the indices.
+pub struct ScanReader<R: BufRead> {
+    reader: R,
+    path: String,
+    source: String,
+    parser: PtflParser,
+    line: String,
+    line_num: usize,
+    first_index: u32,
+    // scans the parser started before this reader
+    started_before: u32,
+    pending: VecDeque<ParseError>,
+    ready: Option<Scan>,
+    done: bool,
+}
+
+impl<R: BufRead> ScanReader<R> {
+    /// `path` is used in error messages, and its file name
+    /// as the source name of the scans.
+    pub fn new(reader: R, path: &str) -> ScanReader<R> {
+        ScanReader::with_parser(reader, path, PtflParser::new())
+    }
+
+    pub fn with_parser(reader: R, path: &str, parser: PtflParser) -> ScanReader<R> {
+        ScanReader {
+            reader,
+            path: path.to_string(),
+            source: source_name(path),
+            started_before: parser.scans_started(),
+            parser,
+            line: String::new(),
+            line_num: 0,
+            first_index: 0,
+            pending: VecDeque::new(),
+            ready: None,
+            done: false,
+        }
+    }
+
+    pub fn recovering(mut self, recovering: bool) -> ScanReader<R> {
+        self.parser.set_recovering(recovering);
+        self
+    }
+
+    /// For readers starting in the middle of a file: the number of lines
+    /// before the reader and the index to give the first scan.
+    pub fn starting_at(mut self, line_num: usize, index: u32) -> ScanReader<R> {
+        self.line_num = line_num;
+        self.first_index = index;
+        self
+    }
+
+    pub fn into_parser(self) -> PtflParser {
+        self.parser
+    }
+
+    // read the next line into self.line without its line ending,
+    // false on end of input
+    fn read_line(&mut self) -> Result<bool, ParseError> {
+        self.line.clear();
+        match self.reader.read_line(&mut self.line) {
+            Ok(0) => Ok(false),
+            Ok(_) => {
+                if self.line.ends_with('\n') {
+                    self.line.pop();
+                    if self.line.ends_with('\r') {
+                        self.line.pop();
                     }
                 }
+                self.line_num += 1;
+                Ok(true)
             }
+            Err(err) => Err(ParseError::Io {
+                path: self.path.clone(),
+                source: err,
+            }),
         }
-        Ok(file_entry_num)
+    }
+}
+
+impl<R: BufRead> Iterator for ScanReader<R> {
+    type Item = Result<Scan, ParseError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(err) = self.pending.pop_front() {
+                return Some(Err(err));
+            }
+            if let Some(scan) = self.ready.take() {
+                return Some(Ok(scan));
+            }
+            if self.done {
+                return None;
+            }
+
+            let result = match self.read_line() {
+                Ok(true) => self.parser.feed_line(&self.path, self.line_num, &self.line),
+                Ok(false) => {
+                    self.done = true;
+                    self.parser.finish(&self.path, self.line_num).map(|_| None)
+                }
+                Err(err) => Err(err),
+            };
+            self.pending.extend(self.parser.take_diagnostics());
+
+            match result {
+                Ok(Some(scan)) => {
+                    let index =
+                        self.first_index + self.parser.scans_started() - self.started_before - 1;
+                    self.ready = Some(scan.with_key(&self.source, index));
+                }
+                Ok(None) => {}
+                Err(err) => {
+                    self.done = true;
+                    self.pending.push_back(err);
+                }
+            }
+        }
+    }
+}
+
+/// Opens a text or binary pointfile, told apart by its magic bytes,
+/// and reads its scans one at a time.
+/// Gzip and zstd compressed files are decompressed on the fly.
+/// `recovering` is passed on to [`ScanReader::recovering`] for text files.
+pub fn open_scans(
+    path: &str,
+    recovering: bool,
+) -> Result<Box<dyn Iterator<Item = Result<Scan, ParseError>> + Send>, ParseError> {
+    match File::open(path) {
+        Ok(file) => read_scans(BufReader::new(file), path, recovering),
+        Err(err) => Err(ParseError::Io {
+            path: path.to_string(),
+            source: err,
+        }),
+    }
+}
+
+/// Reads scans from any reader the same way [`open_scans`] reads a file,
+/// `path` is used as in [`ScanReader::new`] and to tell the compression
+/// when the data doesn't start with magic bytes.
+pub fn read_scans<R>(
+    mut reader: R,
+    path: &str,
+    recovering: bool,
+) -> Result<Box<dyn Iterator<Item = Result<Scan, ParseError>> + Send>, ParseError>
+where
+    R: BufRead + Send + 'static,
+{
+    let io_error = |err| ParseError::Io {
+        path: path.to_string(),
+        source: err,
+    };
+    let compression = Compression::detect(path, &mut reader).map_err(io_error)?;
+    let mut reader = compression.decompress(reader).map_err(io_error)?;
+    if is_binary(&mut reader).map_err(io_error)? {
+        Ok(Box::new(BinaryScanReader::new(reader, path)?))
+    } else {
+        Ok(Box::new(
+            ScanReader::new(reader, path).recovering(recovering),
+        ))
+    }
+}
+
+// insert scans into target, in recovering mode only io errors
+// are returned and anything else is collected as diagnostics
+pub(crate) fn collect_scans<I, T>(
+    reader: &mut I,
+    target: &mut T,
+    recovering: bool,
+) -> Result<(u32, Vec<ParseError>), ParseError>
+where
+    I: Iterator<Item = Result<Scan, ParseError>>,
+    T: Extend<Scan>,
+{
+    let mut file_entry_num: u32 = 0;
+    let mut diagnostics = Vec::new();
+    for scan in reader {
+        match scan {
+            Ok(scan) => {
+                target.extend(Some(scan));
+                file_entry_num += 1;
+            }
+            Err(err) if recovering && !matches!(err, ParseError::Io { .. }) => {
+                diagnostics.push(err)
+            }
+            Err(err) => return Err(err),
+        }
+    }
+    Ok((file_entry_num, diagnostics))
+}
+
+// the file name of the path without compression extension,
+// or the path itself if it has no file name
+pub(crate) fn source_name(path: &str) -> String {
+    match Path::new(path).file_name().and_then(|name| name.to_str()) {
+        Some(name) => strip_compression_extension(name).to_string(),
+        None => path.to_string(),
+    }
+}
+
+// parse the point count line that starts every scan
+fn parse_count(path: &str, line_num: usize, line: &str) -> Result<usize, ParseError> {
+    match line.trim().parse::<usize>() {
+        Ok(0) => Err(ParseError::ZeroCount {
+            path: path.to_string(),
+            line: line_num,
+        }),
+        Ok(length) => Ok(length),
+        Err(_) => Err(ParseError::BadCount {
+            path: path.to_string(),
+            line: line_num,
+            text: line.to_string(),
+        }),
+    }
+}
+
+// parse a `#key=value` line, None for comments without a `=`
+fn parse_metadata(
+    path: &str,
+    line_num: usize,
+    line: &str,
+) -> Result<Option<(String, String)>, ParseError> {
+    let (key, value) = match line[1..].split_once('=') {
+        Some((key, value)) => (key.trim(), value.trim()),
+        None => return Ok(None),
+    };
+    if !valid_metadata(key, value) {
+        return Err(ParseError::BadMetadata {
+            path: path.to_string(),
+            line: line_num,
+            text: line.to_string(),
+        });
+    }
+    Ok(Some((key.to_string(), value.to_string())))
+}
+
+// keys with a meaning of their own need a value that parses as it
+pub(crate) fn valid_metadata(key: &str, value: &str) -> bool {
+    match key {
+        "" => false,
+        TIMESTAMP_KEY | RPM_KEY => value.parse::<f64>().is_ok(),
+        POSE_KEY => Pose::parse(value).is_some(),
+        _ => true,
+    }
+}
+
+// parse a `angle, range[, intensity[, flags]]` line,
+// the intensity can be left empty to only give flags
+fn parse_point(path: &str, line_num: usize, line: &str) -> Result<Point, ParseError> {
+    let fields: Vec<&str> = line.split(',').collect();
+    if !(2..=4).contains(&fields.len()) {
+        return Err(ParseError::FieldCount {
+            path: path.to_string(),
+            line: line_num,
+            found: fields.len(),
+            text: line.to_string(),
+        });
+    }
+
+    let mut point = Point::new(
+        parse_float(path, line_num, line, fields[0])?,
+        parse_float(path, line_num, line, fields[1])?,
+    );
+    if let Some(field) = fields.get(2).filter(|field| !field.trim().is_empty()) {
+        point.intensity = Some(parse_float(path, line_num, line, field)?);
+    }
+    if let Some(field) = fields.get(3) {
+        point.flags = Some(parse_flags(path, line_num, line, field)?);
+    }
+    Ok(point)
+}
+
+// flags in decimal or `0x` prefixed hex
+fn parse_flags(path: &str, line_num: usize, line: &str, field: &str) -> Result<u32, ParseError> {
+    let field = field.trim();
+    let parsed = match field.strip_prefix("0x") {
+        Some(hex) => u32::from_str_radix(hex, 16),
+        None => field.parse(),
+    };
+    parsed.map_err(|_| ParseError::BadFlags {
+        path: path.to_string(),
+        line: line_num,
+        column: field.as_ptr() as usize - line.as_ptr() as usize + 1,
+        text: field.to_string(),
+    })
+}
+
+fn parse_float(path: &str, line_num: usize, line: &str, field: &str) -> Result<f64, ParseError> {
+    let field = field.trim();
+    match field.parse() {
+        Ok(value) => Ok(value),
+        Err(_) => Err(ParseError::BadFloat {
+            path: path.to_string(),
+            line: line_num,
+            // field is a subslice of line, so their distance is the column
+            column: field.as_ptr() as usize - line.as_ptr() as usize + 1,
+            text: field.to_string(),
+        }),
     }
 }
@@ -0,0 +1,116 @@
+use crate::ptfl_parse::valid_metadata;
+use crate::scan::{Scan, TIMESTAMP_KEY};
+use std::io;
+use std::io::Write;
+
+/// Writes scans in the same count then `angle, range[, intensity[, flags]]` text format
+/// that [`crate::PtflParser`] reads, each scan followed by an empty line.
+/// The timestamp and metadata of a scan are written as `#key=value` lines before it,
+/// metadata that wouldn't read back the same that way is refused, like a `timestamp`
+/// key that belongs in [`Scan::timestamp`] or a `pose` the reader can't parse.
+pub struct PtflWriter<W: Write> {
+    writer: W,
+    precision: usize,
+}
+
+impl<W: Write> PtflWriter<W> {
+    pub fn new(writer: W) -> PtflWriter<W> {
+        PtflWriter {
+            writer,
+            precision: 6,
+        }
+    }
+
+    /// Digits written after the decimal point, 6 by default
+    /// which is what the recorder writes.
+    pub fn precision(mut self, precision: usize) -> PtflWriter<W> {
+        self.precision = precision;
+        self
+    }
+
+    pub fn write_scan(&mut self, scan: &Scan) -> io::Result<()> {
+        // a zero count can't be read back
+        if scan.points().is_empty() {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!(
+                    "{}-{:04} has no points to write",
+                    scan.source(),
+                    scan.index()
+                ),
+            ));
+        }
+
+        for (key, value) in &scan.metadata {
+            if !writable_metadata(key, value) {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    format!(
+                        "{}-{:04} has metadata {:?}={:?} that wouldn't read back the same",
+                        scan.source(),
+                        scan.index(),
+                        key,
+                        value
+                    ),
+                ));
+            }
+        }
+
+        if let Some(timestamp) = scan.timestamp {
+            writeln!(self.writer, "#{}={}", TIMESTAMP_KEY, timestamp)?;
+        }
+        for (key, value) in &scan.metadata {
+            writeln!(self.writer, "#{}={}", key, value)?;
+        }
+
+        let precision = self.precision;
+        writeln!(self.writer, "{}", scan.points().len())?;
+        for point in scan.points() {
+            write!(
+                self.writer,
+                "{:.precision$}, {:.precision$}",
+                point.angle, point.range
+            )?;
+            // intensities are written as is, they're mostly integers
+            match (point.intensity, point.flags) {
+                (Some(intensity), Some(flags)) => {
+                    write!(self.writer, ", {}, {}", intensity, flags)?
+                }
+                (None, Some(flags)) => write!(self.writer, ", , {}", flags)?,
+                (Some(intensity), None) => write!(self.writer, ", {}", intensity)?,
+                (None, None) => {}
+            }
+            writeln!(self.writer)?;
+        }
+        writeln!(self.writer)
+    }
+
+    pub fn write_scans<'a, I>(&mut self, scans: I) -> io::Result<()>
+    where
+        I: IntoIterator<Item = &'a Scan>,
+    {
+        for scan in scans {
+            self.write_scan(scan)?;
+        }
+        Ok(())
+    }
+
+    pub fn flush(&mut self) -> io::Result<()> {
+        self.writer.flush()
+    }
+
+    pub fn into_inner(self) -> W {
+        self.writer
+    }
+}
+
+// keys end at the first `=`, and both sides are trimmed when read,
+// the timestamp is read into the scan instead of its metadata
+fn writable_metadata(key: &str, value: &str) -> bool {
+    let line_safe = |text: &str| text.trim() == text && !text.contains(['\n', '\r']);
+    !key.contains('=')
+        && key != TIMESTAMP_KEY
+        && line_safe(key)
+        && line_safe(value)
+        && valid_metadata(key, value)
+}
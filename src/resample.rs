@@ -0,0 +1,186 @@
+use crate::geometry::sort_by_angle;
+use crate::scan::{Point, Scan};
+use std::f64::consts::{PI, TAU};
+
+/// Grid spacing used unless told otherwise, 0.1 degree.
+pub const DEFAULT_RESOLUTION: f64 = PI / 1800.0;
+
+/// How a range on the grid is found from the points around it.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Interpolation {
+    /// Take the closest point.
+    Nearest,
+    /// Interpolate between the points on both sides.
+    Linear,
+}
+
+/// Resamples scans onto a fixed grid of angles going from -π,
+/// so scans can be compared index by index.
+///
+/// Grid angles more than `max_gap` away from the points around them,
+/// or next to a missing return, get a zero range (no return) rather
+/// than a made up one. Linear interpolation falls back to the nearest
+/// point when only one side is missing.
+pub struct Resampler {
+    resolution: f64,
+    interpolation: Interpolation,
+    max_gap: Option<f64>,
+}
+
+impl Default for Resampler {
+    fn default() -> Resampler {
+        Resampler::new()
+    }
+}
+
+impl Resampler {
+    pub fn new() -> Resampler {
+        Resampler {
+            resolution: DEFAULT_RESOLUTION,
+            interpolation: Interpolation::Nearest,
+            max_gap: None,
+        }
+    }
+
+    /// Spacing of the grid in radians, rounded so a whole number
+    /// of steps fit into a turn, [`DEFAULT_RESOLUTION`] by default.
+    pub fn resolution(mut self, resolution: f64) -> Resampler {
+        self.resolution = resolution;
+        self
+    }
+
+    pub fn interpolation(mut self, interpolation: Interpolation) -> Resampler {
+        self.interpolation = interpolation;
+        self
+    }
+
+    /// Widest gap in radians between points that's filled in,
+    /// four grid steps by default.
+    pub fn max_gap(mut self, max_gap: f64) -> Resampler {
+        self.max_gap = Some(max_gap);
+        self
+    }
+
+    /// Number of points on the grid.
+    pub fn grid_len(&self) -> usize {
+        ((TAU / self.resolution).round() as usize).max(1)
+    }
+
+    /// The angles of the grid, from -π up to but not including π.
+    pub fn grid(&self) -> Vec<f64> {
+        let len = self.grid_len();
+        let step = TAU / len as f64;
+        (0..len).map(|i| -PI + i as f64 * step).collect()
+    }
+
+    /// A copy of the scan with its points on the grid, keeping
+    /// the source, index and metadata.
+    pub fn resample(&self, scan: &Scan) -> Scan {
+        let mut points = scan.points().to_vec();
+        sort_by_angle(&mut points);
+        let grid = self.grid();
+        let max_gap = self.max_gap.unwrap_or(4.0 * TAU / grid.len() as f64);
+
+        let resampled = grid
+            .iter()
+            .map(|&angle| {
+                if points.is_empty() {
+                    return Point::new(angle, 0.0);
+                }
+                // the points on both sides, wrapping around ±π
+                let upper = points.partition_point(|point| point.angle < angle);
+                let (before, before_angle) = match upper {
+                    0 => (
+                        points[points.len() - 1],
+                        points[points.len() - 1].angle - TAU,
+                    ),
+                    _ => (points[upper - 1], points[upper - 1].angle),
+                };
+                let (after, after_angle) = match points.get(upper) {
+                    Some(after) => (*after, after.angle),
+                    None => (points[0], points[0].angle + TAU),
+                };
+                self.interpolate(angle, (before, before_angle), (after, after_angle), max_gap)
+            })
+            .collect();
+
+        let mut resampled_scan = scan.clone();
+        *resampled_scan.points_mut() = resampled;
+        resampled_scan
+    }
+
+    fn interpolate(
+        &self,
+        angle: f64,
+        (before, before_angle): (Point, f64),
+        (after, after_angle): (Point, f64),
+        max_gap: f64,
+    ) -> Point {
+        let span = after_angle - before_angle;
+        let nearest = if angle - before_angle <= after_angle - angle {
+            before
+        } else {
+            after
+        };
+        let linear = self.interpolation == Interpolation::Linear
+            && before.range != 0.0
+            && after.range != 0.0
+            && span > 0.0;
+
+        let mut point = Point::new(angle, 0.0);
+        if span > max_gap {
+            return point;
+        }
+        if linear {
+            let t = (angle - before_angle) / span;
+            point.range = before.range + t * (after.range - before.range);
+            point.intensity = match (before.intensity, after.intensity) {
+                (Some(a), Some(b)) => Some(a + t * (b - a)),
+                _ => nearest.intensity,
+            };
+        } else {
+            point.range = nearest.range;
+            point.intensity = nearest.intensity;
+        }
+        point.flags = nearest.flags;
+        point
+    }
+}
+
+/// Averages scans on the same grid index by index, leaving out missing
+/// returns, for a steadier scan out of a few taken in place.
+/// None if the scans aren't on the same grid.
+pub fn average(scans: &[&Scan]) -> Option<Vec<Point>> {
+    let first = scans.first()?;
+    if scans.iter().any(|scan| {
+        scan.points().len() != first.points().len()
+            || scan
+                .points()
+                .iter()
+                .zip(first.points())
+                .any(|(a, b)| a.angle != b.angle)
+    }) {
+        return None;
+    }
+
+    Some(
+        first
+            .points()
+            .iter()
+            .enumerate()
+            .map(|(i, point)| {
+                let ranges: Vec<f64> = scans
+                    .iter()
+                    .map(|scan| scan.points()[i].range)
+                    .filter(|&range| range != 0.0)
+                    .collect();
+                let range = if ranges.is_empty() {
+                    0.0
+                } else {
+                    ranges.iter().sum::<f64>() / ranges.len() as f64
+                };
+                Point::new(point.angle, range)
+            })
+            .collect(),
+    )
+}
@@ -0,0 +1,330 @@
+use crate::cartesian::CartesianScan;
+use indexmap::map::{Iter, IterMut, Keys, Values, ValuesMut};
+use indexmap::IndexMap;
+use rayon::prelude::*;
+use std::cmp::Ordering;
+use std::fmt;
+use std::sync::OnceLock;
+
+/// A single lidar return, `angle` in radians and `range` in meters.
+/// A `range` of zero means the sensor got no return at that angle.
+/// `intensity` (or quality) and `flags` are only set by sensors reporting them.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Point {
+    pub angle: f64,
+    pub range: f64,
+    pub intensity: Option<f64>,
+    pub flags: Option<u32>,
+}
+
+impl Point {
+    pub fn new(angle: f64, range: f64) -> Point {
+        Point {
+            angle,
+            range,
+            intensity: None,
+            flags: None,
+        }
+    }
+
+    pub fn with_intensity(mut self, intensity: f64) -> Point {
+        self.intensity = Some(intensity);
+        self
+    }
+
+    pub fn with_flags(mut self, flags: u32) -> Point {
+        self.flags = Some(flags);
+        self
+    }
+
+    // order by angle first, then by range
+    pub fn polar_cmp(&self, other: &Point) -> Ordering {
+        self.angle
+            .total_cmp(&other.angle)
+            .then(self.range.total_cmp(&other.range))
+    }
+}
+
+/// Position and heading of the robot, `theta` in radians.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Pose {
+    pub x: f64,
+    pub y: f64,
+    pub theta: f64,
+}
+
+impl Pose {
+    pub fn new(x: f64, y: f64, theta: f64) -> Pose {
+        Pose { x, y, theta }
+    }
+
+    // parse the `x,y,theta` form used in pointfile headers
+    pub fn parse(text: &str) -> Option<Pose> {
+        let values: Vec<f64> = text
+            .split(',')
+            .map(|value| value.trim().parse().ok())
+            .collect::<Option<_>>()?;
+        match values[..] {
+            [x, y, theta] => Some(Pose::new(x, y, theta)),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for Pose {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{},{},{}", self.x, self.y, self.theta)
+    }
+}
+
+/// Metadata keys with a meaning of their own,
+/// anything else is kept in the metadata as is.
+pub const SENSOR_KEY: &str = "sensor";
+pub const POSE_KEY: &str = "pose";
+pub const RPM_KEY: &str = "rpm";
+/// Read into [`Scan::timestamp`] instead of the metadata.
+pub const TIMESTAMP_KEY: &str = "timestamp";
+
+/// One full revolution of the sensor as read from a pointfile.
+/// `source` and `index` identify the scan as `source-index`,
+/// the same way entries are named in the tui. They are the key of the
+/// scan in a [`ScanSet`], so they can only be changed on an owned scan.
+/// Points are changed through [`Scan::points_mut`], which keeps the
+/// cached [`Scan::cartesian`] view in sync.
+#[derive(Clone, Default)]
+pub struct Scan {
+    source: String,
+    index: u32,
+    points: Vec<Point>,
+    cartesian: OnceLock<CartesianScan>,
+    pub timestamp: Option<f64>,
+    pub metadata: IndexMap<String, String>,
+}
+
+impl Scan {
+    pub fn new(source: &str, index: u32, points: Vec<Point>) -> Scan {
+        Scan {
+            source: source.to_string(),
+            index,
+            points,
+            cartesian: OnceLock::new(),
+            timestamp: None,
+            metadata: IndexMap::new(),
+        }
+    }
+
+    pub fn source(&self) -> &str {
+        &self.source
+    }
+
+    pub fn index(&self) -> u32 {
+        self.index
+    }
+
+    /// The same scan under another `(source, index)` key.
+    pub fn with_key(mut self, source: &str, index: u32) -> Scan {
+        self.source = source.to_string();
+        self.index = index;
+        self
+    }
+
+    pub fn key(&self) -> (String, u32) {
+        (self.source.clone(), self.index)
+    }
+
+    pub fn points(&self) -> &[Point] {
+        &self.points
+    }
+
+    /// The points for changing, dropping the cached cartesian view.
+    pub fn points_mut(&mut self) -> &mut Vec<Point> {
+        self.cartesian.take();
+        &mut self.points
+    }
+
+    /// The points in x/y, converted on first use and
+    /// kept until the points are changed.
+    pub fn cartesian(&self) -> &CartesianScan {
+        self.cartesian
+            .get_or_init(|| CartesianScan::from_points(&self.points))
+    }
+
+    pub fn len(&self) -> usize {
+        self.points.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.points.is_empty()
+    }
+
+    pub fn sensor_id(&self) -> Option<&str> {
+        self.metadata.get(SENSOR_KEY).map(|sensor| sensor.as_str())
+    }
+
+    pub fn set_sensor_id(&mut self, sensor: &str) {
+        self.metadata
+            .insert(SENSOR_KEY.to_string(), sensor.to_string());
+    }
+
+    /// Pose of the robot when the scan was taken.
+    pub fn pose(&self) -> Option<Pose> {
+        self.metadata
+            .get(POSE_KEY)
+            .and_then(|pose| Pose::parse(pose))
+    }
+
+    pub fn set_pose(&mut self, pose: Pose) {
+        self.metadata.insert(POSE_KEY.to_string(), pose.to_string());
+    }
+
+    /// Rotation speed of the sensor in revolutions per minute.
+    pub fn rpm(&self) -> Option<f64> {
+        self.metadata.get(RPM_KEY).and_then(|rpm| rpm.parse().ok())
+    }
+
+    pub fn set_rpm(&mut self, rpm: f64) {
+        self.metadata.insert(RPM_KEY.to_string(), rpm.to_string());
+    }
+}
+
+// the cached cartesian view is left out, it only mirrors the points
+impl fmt::Debug for Scan {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Scan")
+            .field("source", &self.source)
+            .field("index", &self.index)
+            .field("points", &self.points)
+            .field("timestamp", &self.timestamp)
+            .field("metadata", &self.metadata)
+            .finish()
+    }
+}
+
+impl PartialEq for Scan {
+    fn eq(&self, other: &Scan) -> bool {
+        self.source == other.source
+            && self.index == other.index
+            && self.points == other.points
+            && self.timestamp == other.timestamp
+            && self.metadata == other.metadata
+    }
+}
+
+/// Scans keyed by `(source, index)`, kept in insertion order.
+#[derive(Clone, Debug, Default)]
+pub struct ScanSet {
+    scans: IndexMap<(String, u32), Scan>,
+}
+
+impl ScanSet {
+    pub fn new() -> ScanSet {
+        ScanSet {
+            scans: IndexMap::new(),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.scans.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.scans.is_empty()
+    }
+
+    pub fn get(&self, source: &str, index: u32) -> Option<&Scan> {
+        self.scans.get(&(source.to_string(), index))
+    }
+
+    pub fn get_mut(&mut self, source: &str, index: u32) -> Option<&mut Scan> {
+        self.scans.get_mut(&(source.to_string(), index))
+    }
+
+    pub fn contains(&self, source: &str, index: u32) -> bool {
+        self.scans.contains_key(&(source.to_string(), index))
+    }
+
+    // true if any scan is read from the given source
+    pub fn contains_source(&self, source: &str) -> bool {
+        self.scans.keys().any(|(name, _)| name == source)
+    }
+
+    /// Inserts a scan under its own `(source, index)` key,
+    /// returning the scan previously stored under that key.
+    pub fn insert(&mut self, scan: Scan) -> Option<Scan> {
+        self.scans.insert(scan.key(), scan)
+    }
+
+    /// Removes a scan while keeping the order of the remaining ones.
+    pub fn remove(&mut self, source: &str, index: u32) -> Option<Scan> {
+        self.scans.shift_remove(&(source.to_string(), index))
+    }
+
+    pub fn keys(&self) -> Keys<'_, (String, u32), Scan> {
+        self.scans.keys()
+    }
+
+    pub fn iter(&self) -> Values<'_, (String, u32), Scan> {
+        self.scans.values()
+    }
+
+    pub fn iter_mut(&mut self) -> ValuesMut<'_, (String, u32), Scan> {
+        self.scans.values_mut()
+    }
+
+    pub fn entries(&self) -> Iter<'_, (String, u32), Scan> {
+        self.scans.iter()
+    }
+
+    pub fn entries_mut(&mut self) -> IterMut<'_, (String, u32), Scan> {
+        self.scans.iter_mut()
+    }
+
+    pub fn par_iter(&self) -> impl ParallelIterator<Item = &Scan> {
+        self.scans.par_values()
+    }
+
+    pub fn par_iter_mut(&mut self) -> impl ParallelIterator<Item = &mut Scan> {
+        self.scans.par_values_mut()
+    }
+
+    // all scans read from the given source, in order
+    pub fn by_source<'a>(&'a self, source: &'a str) -> impl Iterator<Item = &'a Scan> + 'a {
+        self.scans
+            .values()
+            .filter(move |scan| scan.source == source)
+    }
+}
+
+impl<'a> IntoIterator for &'a ScanSet {
+    type Item = &'a Scan;
+    type IntoIter = Values<'a, (String, u32), Scan>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+impl IntoIterator for ScanSet {
+    type Item = Scan;
+    type IntoIter = indexmap::map::IntoValues<(String, u32), Scan>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.scans.into_values()
+    }
+}
+
+impl FromIterator<Scan> for ScanSet {
+    fn from_iter<I: IntoIterator<Item = Scan>>(iter: I) -> ScanSet {
+        let mut set = ScanSet::new();
+        set.extend(iter);
+        set
+    }
+}
+
+impl Extend<Scan> for ScanSet {
+    fn extend<I: IntoIterator<Item = Scan>>(&mut self, iter: I) {
+        for scan in iter {
+            self.insert(scan);
+        }
+    }
+}
@@ -0,0 +1,135 @@
+use crate::cartesian::CartesianPoint;
+
+/// A 2D kd-tree over a fixed set of points, for neighbor lookups.
+/// Points are referred to by their index in the slice it's built from.
+#[derive(Clone, Debug, Default)]
+pub struct KdTree {
+    points: Vec<CartesianPoint>,
+    // indices into points, every range is split at its middle node,
+    // by x at even depths and by y at odd ones
+    nodes: Vec<usize>,
+}
+
+impl KdTree {
+    pub fn new(points: &[CartesianPoint]) -> KdTree {
+        let mut nodes: Vec<usize> = (0..points.len()).collect();
+        build(points, &mut nodes, 0);
+        KdTree {
+            points: points.to_vec(),
+            nodes,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.points.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.points.is_empty()
+    }
+
+    pub fn point(&self, index: usize) -> CartesianPoint {
+        self.points[index]
+    }
+
+    /// Index of the closest point and its distance, None if the tree is empty.
+    pub fn nearest(&self, target: &CartesianPoint) -> Option<(usize, f64)> {
+        self.k_nearest(target, 1).into_iter().next()
+    }
+
+    /// Indices and distances of the `k` closest points, closest first.
+    pub fn k_nearest(&self, target: &CartesianPoint, k: usize) -> Vec<(usize, f64)> {
+        let mut best = Vec::with_capacity(k + 1);
+        if k > 0 {
+            self.search_nearest(&self.nodes, 0, target, k, &mut best);
+        }
+        best
+    }
+
+    /// Indices of every point at most `radius` away, in no particular order.
+    pub fn within(&self, target: &CartesianPoint, radius: f64) -> Vec<usize> {
+        let mut found = Vec::new();
+        self.search_within(&self.nodes, 0, target, radius, &mut found);
+        found
+    }
+
+    fn search_nearest(
+        &self,
+        nodes: &[usize],
+        depth: usize,
+        target: &CartesianPoint,
+        k: usize,
+        best: &mut Vec<(usize, f64)>,
+    ) {
+        if nodes.is_empty() {
+            return;
+        }
+        let mid = nodes.len() / 2;
+        let point = &self.points[nodes[mid]];
+        let distance = point.distance(target);
+        if best.len() < k || distance < best[best.len() - 1].1 {
+            let at = best.partition_point(|&(_, best)| best <= distance);
+            best.insert(at, (nodes[mid], distance));
+            best.truncate(k);
+        }
+
+        let diff = axis(target, depth) - axis(point, depth);
+        let (near, far) = if diff < 0.0 {
+            (&nodes[..mid], &nodes[mid + 1..])
+        } else {
+            (&nodes[mid + 1..], &nodes[..mid])
+        };
+        self.search_nearest(near, depth + 1, target, k, best);
+        // the other side can only be closer than the splitting line
+        if best.len() < k || diff.abs() < best[best.len() - 1].1 {
+            self.search_nearest(far, depth + 1, target, k, best);
+        }
+    }
+
+    fn search_within(
+        &self,
+        nodes: &[usize],
+        depth: usize,
+        target: &CartesianPoint,
+        radius: f64,
+        found: &mut Vec<usize>,
+    ) {
+        if nodes.is_empty() {
+            return;
+        }
+        let mid = nodes.len() / 2;
+        let point = &self.points[nodes[mid]];
+        if point.distance(target) <= radius {
+            found.push(nodes[mid]);
+        }
+
+        let diff = axis(target, depth) - axis(point, depth);
+        if diff <= radius {
+            self.search_within(&nodes[..mid], depth + 1, target, radius, found);
+        }
+        if diff >= -radius {
+            self.search_within(&nodes[mid + 1..], depth + 1, target, radius, found);
+        }
+    }
+}
+
+fn axis(point: &CartesianPoint, depth: usize) -> f64 {
+    if depth % 2 == 0 {
+        point.x
+    } else {
+        point.y
+    }
+}
+
+fn build(points: &[CartesianPoint], nodes: &mut [usize], depth: usize) {
+    if nodes.len() <= 1 {
+        return;
+    }
+    let mid = nodes.len() / 2;
+    nodes.select_nth_unstable_by(mid, |&a, &b| {
+        axis(&points[a], depth).total_cmp(&axis(&points[b], depth))
+    });
+    let (left, right) = nodes.split_at_mut(mid);
+    build(points, left, depth + 1);
+    build(points, &mut right[1..], depth + 1);
+}
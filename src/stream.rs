@@ -0,0 +1,166 @@
+use crate::error::ParseError;
+use crate::ptfl_parse::{open_scans, read_scans};
+use crate::scan::Scan;
+use std::fs::File;
+use std::io;
+use std::io::{BufReader, Read};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc;
+use std::sync::mpsc::{Receiver, TryRecvError};
+use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
+
+// how often a followed file is checked for new data
+const FOLLOW_INTERVAL: Duration = Duration::from_millis(200);
+
+type ScanIterator = Box<dyn Iterator<Item = Result<Scan, ParseError>> + Send>;
+
+/// Reads scans on a background thread as they arrive, for named pipes
+/// and other sources that are written while being read.
+/// Scans and errors are handed over through [`ScanStream::receive`].
+pub struct ScanStream {
+    path: String,
+    receiver: Receiver<Result<Scan, ParseError>>,
+    stop: Arc<AtomicBool>,
+    finished: bool,
+}
+
+impl ScanStream {
+    /// Starts reading the path, `recovering` is passed on to [`open_scans`].
+    pub fn open(path: &str, recovering: bool) -> ScanStream {
+        // opening a pipe blocks until it has a writer, so it's done on the thread too
+        ScanStream::spawn(path, move |path, _| open_scans(path, recovering), |_| {})
+    }
+
+    /// Reads the file like `tail -f`, every scan already in it and then
+    /// every scan appended to it until [`ScanStream::stop`] is called.
+    /// A scan is only handed over once all its lines are written,
+    /// one still being written when stopped is dropped without an error.
+    pub fn follow(path: &str, recovering: bool) -> ScanStream {
+        ScanStream::follow_with(path, recovering, |_| {})
+    }
+
+    /// Follows the file like [`ScanStream::follow`], calling `on_scan`
+    /// on the reading thread with every scan as soon as it's read.
+    pub fn follow_with<F>(path: &str, recovering: bool, on_scan: F) -> ScanStream
+    where
+        F: FnMut(&Scan) + Send + 'static,
+    {
+        ScanStream::spawn(
+            path,
+            move |path, stop| {
+                let file = File::open(path).map_err(|err| ParseError::Io {
+                    path: path.to_string(),
+                    source: err,
+                })?;
+                let ended = Arc::new(AtomicBool::new(false));
+                let reader = BufReader::new(TailReader {
+                    file,
+                    stop,
+                    ended: ended.clone(),
+                });
+                let scans = read_scans(reader, path, recovering)?;
+                // errors after the end come from the lines cut off by stopping
+                let scans =
+                    scans.take_while(move |scan| scan.is_ok() || !ended.load(Ordering::Relaxed));
+                Ok(Box::new(scans) as ScanIterator)
+            },
+            on_scan,
+        )
+    }
+
+    fn spawn<O, F>(path: &str, open: O, mut on_scan: F) -> ScanStream
+    where
+        O: FnOnce(&str, Arc<AtomicBool>) -> Result<ScanIterator, ParseError> + Send + 'static,
+        F: FnMut(&Scan) + Send + 'static,
+    {
+        let (sender, receiver) = mpsc::channel();
+        let stop = Arc::new(AtomicBool::new(false));
+        let thread_path = path.to_string();
+        let thread_stop = stop.clone();
+        thread::spawn(move || match open(&thread_path, thread_stop) {
+            Ok(scans) => {
+                for scan in scans {
+                    if let Ok(scan) = &scan {
+                        on_scan(scan);
+                    }
+                    if sender.send(scan).is_err() {
+                        break;
+                    }
+                }
+            }
+            Err(err) => {
+                let _ = sender.send(Err(err));
+            }
+        });
+
+        ScanStream {
+            path: path.to_string(),
+            receiver,
+            stop,
+            finished: false,
+        }
+    }
+
+    pub fn path(&self) -> &str {
+        &self.path
+    }
+
+    /// Everything read since the last call, without blocking.
+    pub fn receive(&mut self) -> Vec<Result<Scan, ParseError>> {
+        let mut received = Vec::new();
+        loop {
+            match self.receiver.try_recv() {
+                Ok(scan) => received.push(scan),
+                Err(TryRecvError::Empty) => break,
+                Err(TryRecvError::Disconnected) => {
+                    self.finished = true;
+                    break;
+                }
+            }
+        }
+        received
+    }
+
+    /// Stops following the file, the stream finishes once
+    /// the reading thread notices. Pipes end with their writer instead.
+    pub fn stop(&self) {
+        self.stop.store(true, Ordering::Relaxed);
+    }
+
+    /// The source ended and everything read from it has been received.
+    pub fn is_finished(&self) -> bool {
+        self.finished
+    }
+}
+
+impl Drop for ScanStream {
+    fn drop(&mut self) {
+        self.stop();
+    }
+}
+
+// waits for more data at the end of the file instead of ending,
+// until told to stop, setting ended once it does end
+struct TailReader {
+    file: File,
+    stop: Arc<AtomicBool>,
+    ended: Arc<AtomicBool>,
+}
+
+impl Read for TailReader {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        loop {
+            let read = self.file.read(buf)?;
+            if read > 0 || buf.is_empty() {
+                return Ok(read);
+            }
+            if self.stop.load(Ordering::Relaxed) {
+                self.ended.store(true, Ordering::Relaxed);
+                return Ok(0);
+            }
+            thread::sleep(FOLLOW_INTERVAL);
+        }
+    }
+}
@@ -2,12 +2,18 @@ use std::io::ErrorKind;
 use std::io::{BufRead, BufReader};
 use std::net::TcpStream;
 use std::process::{Child, Command, Stdio};
-use tev_client::{PacketOpenImage, TevClient};
+use tev_client::{PacketOpenImage, PacketReloadImage, TevClient};
 
 pub struct TevWrappedClient {
     client: Option<(TevClient, Child)>,
 }
 
+impl Default for TevWrappedClient {
+    fn default() -> TevWrappedClient {
+        TevWrappedClient::new()
+    }
+}
+
 impl TevWrappedClient {
     pub fn new() -> TevWrappedClient {
         TevWrappedClient { client: None }
@@ -17,8 +23,8 @@ impl TevWrappedClient {
     // modified to better handle tev sub process
     pub fn start_client(&mut self) -> Result<(), String> {
         // check self is already connected to a running client
-        match &mut self.client {
-            Some((_, child)) => match child.try_wait() {
+        if let Some((_, child)) = &mut self.client {
+            match child.try_wait() {
                 Ok(Some(_)) => {
                     self.client = None;
                 }
@@ -31,13 +37,12 @@ impl TevWrappedClient {
                         Ok(_) => {}
                         Err(err) => match err.kind() {
                             ErrorKind::InvalidInput => {}
-                            _ => {return Err("Failed ending previous process".to_string())},
+                            _ => return Err("Failed ending previous process".to_string()),
                         },
                     };
                     self.client = None;
                 }
-            },
-            None => {}
+            }
         }
 
         println!("Starting new tev client...");
@@ -49,7 +54,7 @@ impl TevWrappedClient {
         {
             Ok(child) => child,
             Err(err) => {
-                return Err(format!("Failed spawning tev, {}", err.to_string()));
+                return Err(format!("Failed spawning tev, {}", err));
             }
         };
 
@@ -75,7 +80,7 @@ impl TevWrappedClient {
                     let socket = match TcpStream::connect(host) {
                         Ok(socker) => socker,
                         Err(err) => {
-                            return Err(format!("Failed starting TcpStream, {}", err.to_string()));
+                            return Err(format!("Failed starting TcpStream, {}", err));
                         }
                     };
                     self.client = Some((TevClient::wrap(socket), child));
@@ -111,4 +116,23 @@ impl TevWrappedClient {
             Err(err) => Err(err),
         }
     }
+
+    // reload an image opened before after it's overwritten
+    pub fn reload_image(&mut self, path: String) -> Result<(), String> {
+        self.start_client()?;
+        match &mut self.client {
+            Some((tev_client, _)) => {
+                match tev_client.send(PacketReloadImage {
+                    image_name: &path,
+                    grab_focus: false,
+                }) {
+                    Ok(_) => Ok(()),
+                    Err(err) => Err(err.to_string()),
+                }
+            }
+            None => {
+                panic!("Get a None for self.client after successful call to start_client");
+            }
+        }
+    }
 }
@@ -0,0 +1,338 @@
+use crate::geometry::{normalize_angle, sort_by_angle};
+use crate::scan::{Point, Scan, ScanSet};
+use rayon::prelude::*;
+use std::f64::consts::PI;
+use std::fmt;
+use std::str::FromStr;
+
+/// Points per revolution the recorder usually writes.
+pub const TYPICAL_POINT_COUNT: usize = 5290;
+
+// slack for angles written with 6 decimals
+const ANGLE_EPSILON: f64 = 1e-6;
+
+/// What to do with scans and points failing validation.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ValidationMode {
+    /// Only report the issues.
+    Report,
+    /// Remove whole scans with any issue other than [`Issue::SuspiciousCount`].
+    Reject,
+    /// Bring bad values back into range, invalid and negative ranges become
+    /// zero (no return) and angles are wrapped into `[-π, π]`, after which
+    /// the points are sorted by angle again.
+    /// Points with a non finite angle can't be fixed and are dropped.
+    Clamp,
+    /// Remove every offending point.
+    Drop,
+}
+
+impl FromStr for ValidationMode {
+    type Err = String;
+
+    fn from_str(mode: &str) -> Result<ValidationMode, String> {
+        match mode {
+            "report" => Ok(ValidationMode::Report),
+            "reject" => Ok(ValidationMode::Reject),
+            "clamp" => Ok(ValidationMode::Clamp),
+            "drop" => Ok(ValidationMode::Drop),
+            _ => Err(format!(
+                "Expect report, reject, clamp or drop as validation mode, got {}",
+                mode
+            )),
+        }
+    }
+}
+
+/// A problem found in a scan, `point` is the index of the offending point.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Issue {
+    // NaN or infinite angle or range
+    InvalidValue {
+        point: usize,
+        angle: f64,
+        range: f64,
+    },
+    NegativeRange {
+        point: usize,
+        range: f64,
+    },
+    // range above the configured maximum
+    RangeTooFar {
+        point: usize,
+        range: f64,
+    },
+    // angle outside [-π, π]
+    AngleOutOfRange {
+        point: usize,
+        angle: f64,
+    },
+    DuplicateAngle {
+        point: usize,
+        angle: f64,
+    },
+    // angle smaller than the one before it
+    NonMonotonic {
+        point: usize,
+        angle: f64,
+        previous: f64,
+    },
+    // point count far from the expected one, only ever reported
+    SuspiciousCount {
+        count: usize,
+        expected: usize,
+    },
+}
+
+impl fmt::Display for Issue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Issue::InvalidValue {
+                point,
+                angle,
+                range,
+            } => write!(f, "point {point}: invalid value ({angle}, {range})"),
+            Issue::NegativeRange { point, range } => {
+                write!(f, "point {point}: negative range {range}")
+            }
+            Issue::RangeTooFar { point, range } => {
+                write!(f, "point {point}: range {range} above maximum")
+            }
+            Issue::AngleOutOfRange { point, angle } => {
+                write!(f, "point {point}: angle {angle} outside [-pi, pi]")
+            }
+            Issue::DuplicateAngle { point, angle } => {
+                write!(f, "point {point}: duplicate angle {angle}")
+            }
+            Issue::NonMonotonic {
+                point,
+                angle,
+                previous,
+            } => write!(f, "point {point}: angle {angle} after {previous}"),
+            Issue::SuspiciousCount { count, expected } => {
+                write!(f, "{count} points, expected about {expected}")
+            }
+        }
+    }
+}
+
+/// Result of validating a single scan.
+#[derive(Clone, Debug)]
+pub struct ScanReport {
+    pub source: String,
+    pub index: u32,
+    pub issues: Vec<Issue>,
+    /// Points clamped or dropped.
+    pub fixed: usize,
+    /// The scan should be, or was, removed.
+    pub rejected: bool,
+}
+
+/// Sanity checks for scans, see [`Issue`] for what's checked.
+pub struct Validator {
+    mode: ValidationMode,
+    max_range: Option<f64>,
+    expected_count: usize,
+    count_tolerance: f64,
+}
+
+impl Default for Validator {
+    fn default() -> Validator {
+        Validator::new()
+    }
+}
+
+impl Validator {
+    pub fn new() -> Validator {
+        Validator {
+            mode: ValidationMode::Report,
+            max_range: None,
+            expected_count: TYPICAL_POINT_COUNT,
+            count_tolerance: 0.05,
+        }
+    }
+
+    pub fn mode(mut self, mode: ValidationMode) -> Validator {
+        self.mode = mode;
+        self
+    }
+
+    /// Ranges above this are out of range, unchecked by default.
+    pub fn max_range(mut self, max_range: f64) -> Validator {
+        self.max_range = Some(max_range);
+        self
+    }
+
+    /// Point count of a normal scan, [`TYPICAL_POINT_COUNT`] by default.
+    pub fn expected_count(mut self, expected_count: usize) -> Validator {
+        self.expected_count = expected_count;
+        self
+    }
+
+    /// How far off the expected count, as a fraction, a scan can be
+    /// before it's suspicious, 5% by default.
+    pub fn count_tolerance(mut self, count_tolerance: f64) -> Validator {
+        self.count_tolerance = count_tolerance;
+        self
+    }
+
+    /// Checks the scan and fixes it according to the mode.
+    /// Rejected scans are only marked in the report, not removed.
+    pub fn validate(&self, scan: &mut Scan) -> ScanReport {
+        let mut report = ScanReport {
+            source: scan.source().to_string(),
+            index: scan.index(),
+            issues: Vec::new(),
+            fixed: 0,
+            rejected: false,
+        };
+
+        let count = scan.points().len();
+        let expected = self.expected_count;
+        if expected > 0
+            && (count as f64 - expected as f64).abs() > expected as f64 * self.count_tolerance
+        {
+            report
+                .issues
+                .push(Issue::SuspiciousCount { count, expected });
+        }
+
+        let mut kept: Vec<Point> = Vec::with_capacity(scan.points().len());
+        let mut wrapped = false;
+        // last angle kept, to check ordering against
+        let mut previous: Option<f64> = None;
+        for (i, point) in scan.points().iter().enumerate() {
+            let mut point = *point;
+            let issues_before = report.issues.len();
+            let mut unfixable = false;
+
+            if !point.angle.is_finite() || !point.range.is_finite() {
+                report.issues.push(Issue::InvalidValue {
+                    point: i,
+                    angle: point.angle,
+                    range: point.range,
+                });
+                unfixable = !point.angle.is_finite();
+                point.range = 0.0;
+            } else if point.range < 0.0 {
+                report.issues.push(Issue::NegativeRange {
+                    point: i,
+                    range: point.range,
+                });
+                point.range = 0.0;
+            } else if let Some(max_range) = self.max_range.filter(|&max| point.range > max) {
+                report.issues.push(Issue::RangeTooFar {
+                    point: i,
+                    range: point.range,
+                });
+                point.range = max_range;
+            }
+
+            if point.angle.is_finite() {
+                if point.angle.abs() > PI + ANGLE_EPSILON {
+                    report.issues.push(Issue::AngleOutOfRange {
+                        point: i,
+                        angle: point.angle,
+                    });
+                    point.angle = normalize_angle(point.angle);
+                    wrapped = true;
+                }
+                match previous {
+                    Some(previous) if point.angle == previous => {
+                        report.issues.push(Issue::DuplicateAngle {
+                            point: i,
+                            angle: point.angle,
+                        })
+                    }
+                    Some(previous) if point.angle < previous => {
+                        report.issues.push(Issue::NonMonotonic {
+                            point: i,
+                            angle: point.angle,
+                            previous,
+                        })
+                    }
+                    _ => {}
+                }
+            }
+
+            let offending = report.issues.len() > issues_before;
+            match self.mode {
+                ValidationMode::Clamp if unfixable => report.fixed += 1,
+                ValidationMode::Clamp => {
+                    if point != scan.points()[i] {
+                        report.fixed += 1;
+                    }
+                    previous = Some(point.angle);
+                    kept.push(point);
+                }
+                ValidationMode::Drop if offending => report.fixed += 1,
+                _ => {
+                    if point.angle.is_finite() {
+                        previous = Some(point.angle);
+                    }
+                    kept.push(scan.points()[i]);
+                }
+            }
+        }
+
+        match self.mode {
+            ValidationMode::Clamp => {
+                if wrapped {
+                    sort_by_angle(&mut kept);
+                }
+                *scan.points_mut() = kept;
+            }
+            ValidationMode::Drop => *scan.points_mut() = kept,
+            ValidationMode::Reject => {
+                report.rejected = report
+                    .issues
+                    .iter()
+                    .any(|issue| !matches!(issue, Issue::SuspiciousCount { .. }))
+            }
+            ValidationMode::Report => {}
+        }
+        report
+    }
+
+    /// Validates every scan in parallel, removing rejected ones
+    /// and, when clamping or dropping, scans left without any point.
+    /// Nothing is removed in report mode.
+    pub fn validate_set(&self, scans: &mut ScanSet) -> Vec<ScanReport> {
+        let mut reports: Vec<ScanReport> = scans
+            .par_iter_mut()
+            .map(|scan| self.validate(scan))
+            .collect();
+        self.remove_rejected(scans, &mut reports);
+        reports
+    }
+
+    /// Validates the scans with the given keys like [`Validator::validate_set`],
+    /// skipping keys not in the set.
+    pub fn validate_entries(&self, scans: &mut ScanSet, keys: &[(String, u32)]) -> Vec<ScanReport> {
+        let mut reports: Vec<ScanReport> = keys
+            .iter()
+            .filter_map(|(source, index)| {
+                scans
+                    .get_mut(source, *index)
+                    .map(|scan| self.validate(scan))
+            })
+            .collect();
+        self.remove_rejected(scans, &mut reports);
+        reports
+    }
+
+    // removes the scans validate_set would, marking them rejected
+    fn remove_rejected(&self, scans: &mut ScanSet, reports: &mut [ScanReport]) {
+        for report in reports.iter_mut() {
+            let fixing = matches!(self.mode, ValidationMode::Clamp | ValidationMode::Drop);
+            let emptied = fixing
+                && scans
+                    .get(&report.source, report.index)
+                    .is_some_and(|scan| scan.is_empty());
+            if report.rejected || emptied {
+                report.rejected = true;
+                scans.remove(&report.source, report.index);
+            }
+        }
+    }
+}
@@ -0,0 +1,45 @@
+// fixtures shared by the integration tests, not every test uses all of them
+#![allow(dead_code)]
+
+use ptfl_reader::{Point, Scan};
+use std::f64::consts::PI;
+
+pub fn scan(points: &[(f64, f64)]) -> Scan {
+    let points = points
+        .iter()
+        .map(|&(angle, range)| Point::new(angle, range))
+        .collect();
+    Scan::new("a", 0, points)
+}
+
+// walls of an uneven room around the sensor, every 2cm
+pub fn room() -> Scan {
+    let mut points = Vec::new();
+    for i in 0..200 {
+        let along = -2.0 + i as f64 * 0.02;
+        for (x, y) in [
+            (along, 1.5),
+            (along, -1.0),
+            (3.0, along * 0.6),
+            (-2.0, along * 0.6),
+        ] {
+            let mut point = Point::new(0.0, 0.0);
+            point.set_xy(x, y);
+            points.push(point);
+        }
+    }
+    let mut scan = Scan::new("room", 0, points);
+    scan.sort_by_angle();
+    scan
+}
+
+// a 2m square room around the sensor, a point every half degree
+pub fn square_room() -> Scan {
+    let points = (0..720)
+        .map(|i| {
+            let angle = -PI + i as f64 * PI / 360.0;
+            Point::new(angle, 1.0 / angle.cos().abs().max(angle.sin().abs()))
+        })
+        .collect();
+    Scan::new("room", 0, points)
+}
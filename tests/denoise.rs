@@ -0,0 +1,96 @@
+mod common;
+
+use common::scan;
+use ptfl_reader::{
+    median_filter, remove_isolated, remove_statistical_outliers, CartesianPoint, KdTree,
+};
+
+// a wall 1m away, one point every 0.01 radian
+fn wall(count: usize) -> Vec<(f64, f64)> {
+    (0..count).map(|i| (i as f64 * 0.01, 1.0)).collect()
+}
+
+#[test]
+fn kd_tree_matches_brute_force() {
+    // a deterministic spread of points, some on top of each other
+    let points: Vec<CartesianPoint> = (0..200)
+        .map(|i| {
+            let i = i as f64;
+            CartesianPoint::new((i * 0.37).sin() * 3.0, (i * 0.73).cos() * 2.0)
+        })
+        .collect();
+    let tree = KdTree::new(&points);
+    let targets = [
+        CartesianPoint::new(0.0, 0.0),
+        CartesianPoint::new(2.5, -1.0),
+        CartesianPoint::new(-4.0, 3.0),
+    ];
+    for target in &targets {
+        let mut distances: Vec<f64> = points.iter().map(|point| point.distance(target)).collect();
+        distances.sort_by(f64::total_cmp);
+
+        let nearest: Vec<f64> = tree
+            .k_nearest(target, 5)
+            .iter()
+            .map(|&(_, distance)| distance)
+            .collect();
+        assert_eq!(nearest, distances[..5]);
+        assert_eq!(tree.nearest(target).unwrap().1, distances[0]);
+
+        let within = distances
+            .iter()
+            .filter(|&&distance| distance <= 1.0)
+            .count();
+        assert_eq!(tree.within(target, 1.0).len(), within);
+    }
+    assert!(KdTree::new(&[]).nearest(&targets[0]).is_none());
+}
+
+#[test]
+fn median_filter_flattens_spikes() {
+    let mut points = wall(20);
+    points[10].1 = 5.0;
+    points[15].1 = 0.0;
+    let mut scan = scan(&points);
+    assert_eq!(median_filter(&mut scan, 5), 1);
+    assert!(scan
+        .points()
+        .iter()
+        .all(|point| point.range == 1.0 || point.range == 0.0));
+    // missing returns stay missing
+    assert_eq!(scan.points()[15].range, 0.0);
+}
+
+#[test]
+fn median_filter_windows_are_ordered_by_angle() {
+    // a step from 1m to 2m, stored alternating between both sides
+    let points: Vec<(f64, f64)> = (0..10)
+        .flat_map(|i| [(i as f64 * 0.01, 1.0), ((i + 10) as f64 * 0.01, 2.0)])
+        .collect();
+    let mut scan = scan(&points);
+    assert_eq!(median_filter(&mut scan, 3), 0);
+    // the points keep their order
+    let stored: Vec<(f64, f64)> = scan
+        .points()
+        .iter()
+        .map(|point| (point.angle, point.range))
+        .collect();
+    assert_eq!(stored, points);
+}
+
+#[test]
+fn outliers_and_isolated_points_are_removed() {
+    let mut points = wall(100);
+    points.push((0.5, 3.0));
+    points.push((2.0, 0.0));
+    let original = scan(&points);
+
+    let mut scan = original.clone();
+    assert_eq!(remove_statistical_outliers(&mut scan, 4, 2.0), 1);
+    assert!(scan.points().iter().all(|point| point.range != 3.0));
+    assert_eq!(scan.len(), 101);
+
+    let mut scan = original.clone();
+    assert_eq!(remove_isolated(&mut scan, 0.1, 2), 1);
+    assert!(scan.points().iter().all(|point| point.range != 3.0));
+}
@@ -0,0 +1,52 @@
+mod common;
+
+use common::scan;
+use ptfl_reader::{PointFilter, Scan};
+use std::f64::consts::PI;
+
+fn angles(scan: &Scan) -> Vec<f64> {
+    scan.points().iter().map(|point| point.angle).collect()
+}
+
+#[test]
+fn range_window_keeps_missing_returns() {
+    let mut scan = scan(&[(-1.0, 0.1), (0.0, 0.0), (1.0, 1.0), (2.0, 9.0)]);
+    let removed = PointFilter::new()
+        .min_range(0.5)
+        .max_range(5.0)
+        .apply(&mut scan);
+    assert_eq!(removed, 2);
+    assert_eq!(angles(&scan), vec![0.0, 1.0]);
+
+    PointFilter::new().drop_zero(true).apply(&mut scan);
+    assert_eq!(angles(&scan), vec![1.0]);
+}
+
+#[test]
+fn sector_wraps_around_pi() {
+    let original = scan(&[(-PI + 0.05, 1.0), (-1.0, 1.0), (0.0, 1.0), (PI - 0.05, 1.0)]);
+
+    let mut behind = original.clone();
+    PointFilter::new()
+        .sector(PI - 0.1, -PI + 0.1)
+        .apply(&mut behind);
+    assert_eq!(angles(&behind), vec![-PI + 0.05, PI - 0.05]);
+
+    let mut front = original.clone();
+    PointFilter::new().sector(-1.0, 0.5).apply(&mut front);
+    assert_eq!(angles(&front), vec![-1.0, 0.0]);
+}
+
+#[test]
+fn sector_of_a_full_turn_keeps_everything() {
+    let original = scan(&[(-PI + 0.05, 1.0), (-1.0, 1.0), (0.0, 1.0), (PI, 1.0)]);
+    for (from, to) in [(-PI, PI), (0.0, 2.0 * PI), (1.0, 1.0 - 2.0 * PI)] {
+        let mut kept = original.clone();
+        assert_eq!(PointFilter::new().sector(from, to).apply(&mut kept), 0);
+    }
+
+    // the same angle for both ends is a single direction
+    let mut single = original.clone();
+    PointFilter::new().sector(0.0, 0.0).apply(&mut single);
+    assert_eq!(angles(&single), vec![0.0]);
+}
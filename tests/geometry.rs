@@ -0,0 +1,181 @@
+mod common;
+
+use common::scan;
+use ptfl_reader::{
+    angle_difference, normalize_angle, normalize_angle_positive, CartesianPoint, Point, Pose, Scan,
+};
+use std::f64::consts::{FRAC_PI_2, PI, TAU};
+
+const EPSILON: f64 = 1e-9;
+
+fn assert_close(a: f64, b: f64) {
+    assert!((a - b).abs() < EPSILON, "{a} != {b}");
+}
+
+#[test]
+fn normalize_angle_wraps_into_half_open_range() {
+    assert_close(normalize_angle(0.0), 0.0);
+    assert_close(normalize_angle(PI), PI);
+    assert_close(normalize_angle(-PI), PI);
+    assert_close(normalize_angle(3.0 * PI), PI);
+    assert_close(normalize_angle(-3.0 * FRAC_PI_2), FRAC_PI_2);
+    assert_close(normalize_angle(3.0 * FRAC_PI_2), -FRAC_PI_2);
+    assert_close(normalize_angle(10.0 * TAU + 0.5), 0.5);
+    assert_close(normalize_angle(-10.0 * TAU - 0.5), -0.5);
+}
+
+#[test]
+fn normalize_angle_positive_wraps_into_full_turn() {
+    assert_close(normalize_angle_positive(-FRAC_PI_2), 3.0 * FRAC_PI_2);
+    assert_close(normalize_angle_positive(TAU), 0.0);
+    assert!(normalize_angle_positive(-1e-20) < TAU);
+}
+
+#[test]
+fn angle_difference_takes_shortest_turn() {
+    assert_close(angle_difference(3.0, -3.0), TAU - 6.0);
+    assert_close(angle_difference(-3.0, 3.0), 6.0 - TAU);
+    assert_close(angle_difference(0.25, 0.5), 0.25);
+}
+
+#[test]
+fn negative_rotation_stays_in_range() {
+    let mut scan = scan(&[(-3.0, 1.0), (0.0, 2.0), (3.0, 3.0)]);
+    scan.rotate(-1.0);
+    for point in scan.points() {
+        assert!(point.angle > -PI && point.angle <= PI, "{}", point.angle);
+    }
+    // -3.0 wrapped around to the end
+    assert_close(scan.points()[0].angle, -1.0);
+    assert_close(scan.points()[2].angle, TAU - 4.0);
+    assert_eq!(scan.points()[2].range, 1.0);
+}
+
+#[test]
+fn repeated_rotation_does_not_drift() {
+    let original = scan(&[(-3.1, 1.0), (-1.0, 2.0), (0.5, 3.0), (3.1, 4.0)]);
+    let mut rotated = original.clone();
+    for _ in 0..360 {
+        rotated.rotate(1f64.to_radians());
+    }
+    for (a, b) in original.points().iter().zip(rotated.points()) {
+        assert!(
+            (a.angle - b.angle).abs() < 1e-6,
+            "{} != {}",
+            a.angle,
+            b.angle
+        );
+        assert_eq!(a.range, b.range);
+    }
+}
+
+#[test]
+fn rotation_keeps_points_sorted() {
+    let mut scan = scan(&[(-2.0, 1.0), (-1.0, 1.0), (1.0, 1.0), (2.0, 1.0)]);
+    scan.rotate(2.5);
+    assert!(scan
+        .points()
+        .windows(2)
+        .all(|pair| pair[0].angle <= pair[1].angle));
+}
+
+#[test]
+fn translate_moves_points_and_skips_missing_returns() {
+    let mut scan = scan(&[(0.0, 1.0), (FRAC_PI_2, 0.0)]);
+    scan.translate(0.0, 1.0);
+    // (1, 0) moved to (1, 1)
+    let moved = scan
+        .points()
+        .iter()
+        .find(|point| point.range != 0.0)
+        .unwrap();
+    assert_close(moved.angle, PI / 4.0);
+    assert_close(moved.range, 2f64.sqrt());
+    assert!(scan
+        .points()
+        .iter()
+        .any(|point| point.range == 0.0 && point.angle == FRAC_PI_2));
+
+    scan.translate(0.0, -1.0);
+    let back = scan
+        .points()
+        .iter()
+        .find(|point| point.range != 0.0)
+        .unwrap();
+    assert_close(back.angle, 0.0);
+    assert_close(back.range, 1.0);
+}
+
+#[test]
+fn mirror_twice_is_identity() {
+    let original = scan(&[(-2.0, 1.0), (0.5, 2.0), (3.0, 3.0)]);
+    let mut mirrored = original.clone();
+    mirrored.mirror(0.0);
+    assert_close(mirrored.points()[0].angle, -3.0);
+    assert_close(mirrored.points()[2].angle, 2.0);
+    mirrored.mirror(0.0);
+    for (a, b) in original.points().iter().zip(mirrored.points()) {
+        assert_close(a.angle, b.angle);
+        assert_eq!(a.range, b.range);
+    }
+}
+
+#[test]
+fn transforms_keep_extra_columns() {
+    let mut scan = Scan::new(
+        "a",
+        0,
+        vec![Point::new(0.0, 1.0).with_intensity(10.0).with_flags(2)],
+    );
+    scan.translate(1.0, 1.0);
+    scan.rotate(1.0);
+    scan.mirror(FRAC_PI_2);
+    assert_eq!(scan.points()[0].intensity, Some(10.0));
+    assert_eq!(scan.points()[0].flags, Some(2));
+}
+
+#[test]
+fn transform_rotates_then_translates() {
+    let mut scan = scan(&[(0.0, 1.0), (PI, 0.0)]);
+    scan.transform(Pose::new(1.0, 0.0, FRAC_PI_2));
+    // (1, 0) rotated to (0, 1), then moved to (1, 1)
+    let moved = scan
+        .points()
+        .iter()
+        .find(|point| point.range != 0.0)
+        .unwrap();
+    assert_close(moved.angle, PI / 4.0);
+    assert_close(moved.range, 2f64.sqrt());
+    // missing returns are only rotated
+    let missing = scan
+        .points()
+        .iter()
+        .find(|point| point.range == 0.0)
+        .unwrap();
+    assert_close(missing.angle, -FRAC_PI_2);
+}
+
+#[test]
+fn cartesian_round_trip_and_bounds() {
+    let scan = scan(&[(-FRAC_PI_2, 1.0), (0.0, 2.0), (1.0, 0.0), (FRAC_PI_2, 3.0)]);
+    let cartesian = scan.cartesian();
+    assert_eq!(cartesian.len(), 4);
+    for (a, b) in scan.points().iter().zip(cartesian.to_points()) {
+        assert_close(a.range, b.range);
+        if a.range != 0.0 {
+            assert_close(a.angle, b.angle);
+        }
+    }
+
+    // the missing return is left out
+    let bounds = cartesian.bounding_box().unwrap();
+    assert_close(bounds.min.x, 0.0);
+    assert_close(bounds.min.y, -1.0);
+    assert_close(bounds.max.x, 2.0);
+    assert_close(bounds.max.y, 3.0);
+    let centroid = cartesian.centroid().unwrap();
+    assert_close(centroid.x, 2.0 / 3.0);
+    assert_close(centroid.y, 2.0 / 3.0);
+    assert!(bounds.contains(&centroid));
+    assert!(!bounds.contains(&CartesianPoint::new(-1.0, 0.0)));
+}
@@ -0,0 +1,83 @@
+mod common;
+
+use common::room;
+use ptfl_reader::{open_scans, Icp, IcpMethod, Pose, Scan};
+
+fn assert_recovers(method: IcpMethod, motion: Pose) {
+    let target = room();
+    let mut source = target.clone();
+    source.transform(motion);
+
+    let result = Icp::new().method(method).align(&source, &target);
+    assert!(result.converged, "{result:?}");
+    assert!(result.residual < 1e-6, "{result:?}");
+    // the inverse of the motion applied
+    let (sin, cos) = motion.theta.sin_cos();
+    let x = -cos * motion.x - sin * motion.y;
+    let y = sin * motion.x - cos * motion.y;
+    assert!((result.pose.x - x).abs() < 1e-6, "{result:?}");
+    assert!((result.pose.y - y).abs() < 1e-6, "{result:?}");
+    assert!(
+        (result.pose.theta + motion.theta).abs() < 1e-6,
+        "{result:?}"
+    );
+}
+
+#[test]
+fn point_to_point_recovers_motion() {
+    // points only find their own match when they move less
+    // than half the spacing between them
+    assert_recovers(IcpMethod::PointToPoint, Pose::new(0.004, -0.002, 0.001));
+}
+
+#[test]
+fn point_to_line_recovers_motion() {
+    // points slide along the walls, so matching to lines reaches
+    // much further than matching to points
+    assert_recovers(IcpMethod::PointToLine, Pose::new(0.1, -0.05, 0.05));
+}
+
+#[test]
+fn nothing_to_match_does_not_converge() {
+    let empty = Scan::new("empty", 0, Vec::new());
+    let result = Icp::new().align(&room(), &empty);
+    assert!(!result.converged);
+    assert_eq!(result.matches, 0);
+}
+
+#[test]
+fn diverging_step_does_not_converge() {
+    // a meter off, most points match the wrong walls and the
+    // first step moves the scan further away from the best fit
+    let initial = Pose::new(1.0, 1.0, 0.0);
+    let before = Icp::new()
+        .method(IcpMethod::PointToLine)
+        .initial(initial)
+        .max_iterations(0)
+        .align(&room(), &room());
+    let result = Icp::new()
+        .method(IcpMethod::PointToLine)
+        .initial(initial)
+        .align(&room(), &room());
+    assert!(!result.converged, "{result:?}");
+    assert_eq!(result.iterations, 1);
+    assert_eq!(result.pose, initial);
+    assert_eq!(result.residual, before.residual);
+}
+
+#[test]
+fn point_to_line_settles_on_recorded_scans() {
+    // matches flip between neighbors on this pair, so the pose ends up
+    // cycling between two poses a few micrometers apart
+    let scans: Vec<Scan> = open_scans("pointfiles/2022072600", false)
+        .unwrap()
+        .collect::<Result<_, _>>()
+        .unwrap();
+    let result = Icp::new()
+        .method(IcpMethod::PointToLine)
+        .tolerance(1e-6)
+        .align(&scans[1], &scans[0]);
+    assert!(result.converged, "{result:?}");
+    assert!(result.iterations < 50, "{result:?}");
+    assert!(result.residual < 0.02, "{result:?}");
+}
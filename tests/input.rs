@@ -0,0 +1,85 @@
+use flate2::write::GzEncoder;
+use ptfl_reader::{open_input, open_scans, read_scans, Compression, Scan};
+use std::fs;
+use std::io::{Read, Write};
+
+const TEXT: &str = "2\n0.1, 1.0\n0.2, 1.0\n\n1\n0.3, 1.0\n\n";
+
+fn gzip(data: &[u8]) -> Vec<u8> {
+    let mut encoder = GzEncoder::new(Vec::new(), flate2::Compression::default());
+    encoder.write_all(data).unwrap();
+    encoder.finish().unwrap()
+}
+
+// a single segment zstd frame holding the data in one raw block
+fn zstd_frame(data: &[u8]) -> Vec<u8> {
+    assert!(data.len() < 256);
+    let mut frame = vec![0x28, 0xb5, 0x2f, 0xfd, 0x20, data.len() as u8];
+    let block_header = 1 | (data.len() as u32) << 3;
+    frame.extend_from_slice(&block_header.to_le_bytes()[..3]);
+    frame.extend_from_slice(data);
+    frame
+}
+
+fn read_file(name: &str, data: &[u8]) -> Vec<Scan> {
+    let path = std::env::temp_dir().join(name);
+    fs::write(&path, data).unwrap();
+    let scans = open_scans(path.to_str().unwrap(), false)
+        .unwrap()
+        .collect::<Result<_, _>>();
+    fs::remove_file(&path).unwrap();
+    scans.unwrap()
+}
+
+fn keys(scans: &[Scan]) -> Vec<(String, u32)> {
+    scans.iter().map(Scan::key).collect()
+}
+
+#[test]
+fn gzip_file_is_read_under_its_stripped_name() {
+    let scans = read_file("input_gzip.gz", &gzip(TEXT.as_bytes()));
+    assert_eq!(
+        keys(&scans),
+        vec![("input_gzip".to_string(), 0), ("input_gzip".to_string(), 1)]
+    );
+    assert_eq!(scans[0].len(), 2);
+}
+
+#[test]
+fn gzip_is_detected_without_extension() {
+    let mut data = gzip(&TEXT.as_bytes()[..21]);
+    // concatenated members are read as one stream
+    data.extend(gzip(&TEXT.as_bytes()[21..]));
+    let mut reader = &data[..];
+    assert_eq!(
+        Compression::detect("input", &mut reader).unwrap(),
+        Compression::Gzip
+    );
+    assert_eq!(read_file("input_gzip_members", &data).len(), 2);
+}
+
+#[test]
+fn multi_frame_zstd() {
+    let (first, second) = TEXT.as_bytes().split_at(15);
+    let mut data = zstd_frame(first);
+    data.extend(zstd_frame(second));
+
+    let path = std::env::temp_dir().join("input_frames.zst");
+    fs::write(&path, &data).unwrap();
+    let mut decompressed = String::new();
+    open_input(path.to_str().unwrap())
+        .unwrap()
+        .read_to_string(&mut decompressed)
+        .unwrap();
+    fs::remove_file(&path).unwrap();
+    assert_eq!(decompressed, TEXT);
+
+    let scans: Vec<Scan> = read_scans(std::io::Cursor::new(data), "frames.zst", false)
+        .unwrap()
+        .collect::<Result<_, _>>()
+        .unwrap();
+    assert_eq!(
+        keys(&scans),
+        vec![("frames".to_string(), 0), ("frames".to_string(), 1)]
+    );
+}
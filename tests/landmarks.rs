@@ -0,0 +1,70 @@
+mod common;
+
+use common::square_room;
+use ptfl_reader::{LandmarkDetector, LandmarkKind, LineExtractor, LineMethod, Point, Scan};
+use std::f64::consts::PI;
+
+// points along a circle between two angles around its center, in scan order
+fn arc(x: f64, y: f64, radius: f64, from: f64, to: f64) -> Scan {
+    let points = (0..=40)
+        .map(|i| {
+            let angle = from + (to - from) * i as f64 / 40.0;
+            let mut point = Point::new(0.0, 0.0);
+            point.set_xy(x + radius * angle.cos(), y + radius * angle.sin());
+            point
+        })
+        .collect();
+    Scan::new("arc", 0, points)
+}
+
+#[test]
+fn corners_of_a_room() {
+    let landmarks = LandmarkDetector::new().detect(&square_room());
+    assert_eq!(landmarks.len(), 4, "{landmarks:?}");
+    for landmark in &landmarks {
+        assert_eq!(landmark.kind, LandmarkKind::Corner);
+        assert!(
+            (landmark.position.x.abs() - 1.0).abs() < 0.01,
+            "{landmark:?}"
+        );
+        assert!(
+            (landmark.position.y.abs() - 1.0).abs() < 0.01,
+            "{landmark:?}"
+        );
+        assert!(landmark.confidence > 0.9, "{landmark:?}");
+    }
+}
+
+#[test]
+fn corners_between_the_segments_of_the_given_extractor() {
+    let room = square_room();
+    let ransac = LineExtractor::new().method(LineMethod::Ransac);
+    let detector = LandmarkDetector::new().lines(ransac);
+    let (landmarks, segments) = detector.detect_with_segments(&room);
+    assert_eq!(segments, ransac.extract(&room));
+    assert_eq!(landmarks, detector.detect(&room));
+    assert_eq!(landmarks.len(), 4, "{landmarks:?}");
+}
+
+#[test]
+fn pillars_but_not_bends() {
+    let detector = LandmarkDetector::new();
+    // the side of a pillar facing the sensor
+    let pillar = arc(1.0, 0.5, 0.1, 1.5 * PI, 0.5 * PI);
+    let landmarks = detector.circles(&pillar);
+    assert_eq!(landmarks.len(), 1, "{landmarks:?}");
+    let LandmarkKind::Circle { radius } = landmarks[0].kind else {
+        panic!("{landmarks:?}");
+    };
+    assert!((radius - 0.1).abs() < 1e-6);
+    assert!((landmarks[0].position.x - 1.0).abs() < 1e-6);
+    assert!((landmarks[0].position.y - 0.5).abs() < 1e-6);
+    assert!(landmarks[0].confidence > 0.9, "{landmarks:?}");
+
+    // the inside of a bend curves away from the sensor
+    let bend = arc(0.8, 0.0, 0.2, -0.5 * PI, 0.5 * PI);
+    assert!(detector.circles(&bend).is_empty());
+    // too big to be a pillar
+    let wall = arc(3.0, 0.0, 2.0, 1.1 * PI, 0.9 * PI);
+    assert!(detector.circles(&wall).is_empty());
+}
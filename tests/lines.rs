@@ -0,0 +1,66 @@
+mod common;
+
+use common::square_room;
+use ptfl_reader::{CartesianPoint, LineExtractor, LineMethod};
+
+fn assert_walls(extractor: LineExtractor, distance: f64) {
+    let scan = square_room();
+    let segments = extractor.extract(&scan);
+    assert_eq!(segments.len(), 4, "{segments:?}");
+    for segment in &segments {
+        assert!((segment.length() - 2.0).abs() < 0.1, "{segment:?}");
+        assert!(segment.error < 5e-3, "{segment:?}");
+        for end in [&segment.start, &segment.end] {
+            assert!((end.x.abs() - 1.0).abs() < 0.05, "{segment:?}");
+            assert!((end.y.abs() - 1.0).abs() < 0.05, "{segment:?}");
+        }
+        // within the distance of their line
+        for &i in &segment.points {
+            let point = CartesianPoint::from_polar(&scan.points()[i]);
+            assert!(
+                segment.distance_to(&point) <= distance + 1e-9,
+                "{segment:?}"
+            );
+        }
+    }
+    let supported: usize = segments.iter().map(|segment| segment.points.len()).sum();
+    assert!(supported >= 700, "{supported}");
+}
+
+#[test]
+fn split_and_merge_finds_walls() {
+    // the wall behind the sensor crosses ±π, but stays one segment
+    assert_walls(LineExtractor::new(), 0.03);
+}
+
+#[test]
+fn ransac_finds_walls() {
+    // the first wall found takes the corner points within distance
+    // of it from the next one, keep that small
+    let extractor = LineExtractor::new()
+        .method(LineMethod::Ransac)
+        .distance(0.01);
+    assert_walls(extractor, 0.01);
+    let scan = square_room();
+    assert_eq!(
+        extractor.seed(7).extract(&scan),
+        extractor.seed(7).extract(&scan)
+    );
+}
+
+#[test]
+fn gaps_and_short_runs_are_left_out() {
+    let mut scan = square_room();
+    // a doorway in the wall ahead and a missing return
+    for point in scan.points_mut().iter_mut() {
+        if point.angle.abs() < 0.3 {
+            point.range = 0.0;
+        }
+    }
+    let segments = LineExtractor::new().min_length(0.5).extract(&scan);
+    assert_eq!(segments.len(), 5, "{segments:?}");
+    assert!(segments.iter().all(|segment| segment
+        .points
+        .iter()
+        .all(|&i| scan.points()[i].range != 0.0)));
+}
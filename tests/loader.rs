@@ -0,0 +1,123 @@
+use ptfl_reader::{Loader, ParseError, ScanSet};
+use std::fs;
+
+// loads the file with more threads than cores, so that it's
+// cut into a chunk per scan when split
+fn load(path: &str, split: bool) -> ScanSet {
+    let loader = Loader::new()
+        .recovering(true)
+        .split_threshold(if split { 0 } else { u64::MAX });
+    let mut scans = ScanSet::new();
+    rayon::ThreadPoolBuilder::new()
+        .num_threads(4)
+        .build()
+        .unwrap()
+        .install(|| loader.load(&[path.to_string()], &mut scans));
+    scans
+}
+
+#[test]
+fn split_and_sequential_loading_agree_on_indices() {
+    // the second scan has every point line skipped
+    let path = std::env::temp_dir().join("loader_dropped_scan");
+    fs::write(
+        &path,
+        "2\n0.1, 1\n0.2, 1\n\n2\nbad\nworse\n\n2\n0.3, 1\n0.4, 1\n\n",
+    )
+    .unwrap();
+    let path = path.to_str().unwrap();
+    let sequential = load(path, false);
+    let split = load(path, true);
+    fs::remove_file(path).unwrap();
+
+    let keys = |scans: &ScanSet| -> Vec<u32> { scans.iter().map(|scan| scan.index()).collect() };
+    assert_eq!(keys(&sequential), vec![0, 2]);
+    assert_eq!(keys(&split), keys(&sequential));
+}
+
+#[test]
+fn paths_named_alike_are_not_loaded_over_each_other() {
+    let dir = std::env::temp_dir();
+    let plain = dir.join("loader_alike");
+    let compressed = dir.join("loader_alike.gz");
+    fs::write(&plain, "1\n0.1, 1\n\n").unwrap();
+    let mut encoder = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+    std::io::Write::write_all(&mut encoder, b"1\n0.2, 2\n\n").unwrap();
+    fs::write(&compressed, encoder.finish().unwrap()).unwrap();
+
+    let paths = [
+        plain.to_str().unwrap().to_string(),
+        compressed.to_str().unwrap().to_string(),
+    ];
+    let mut scans = ScanSet::new();
+    let reports = Loader::new().load(&paths, &mut scans);
+    fs::remove_file(&plain).unwrap();
+    fs::remove_file(&compressed).unwrap();
+
+    assert_eq!(scans.len(), 1);
+    assert_eq!(scans.get("loader_alike", 0).unwrap().points()[0].range, 1.0);
+    assert!(reports[0].result.is_ok());
+    match &reports[1].result {
+        Err(ParseError::DuplicateSource { name, other, .. }) => {
+            assert_eq!(name, "loader_alike");
+            assert_eq!(other, &paths[0]);
+        }
+        other => panic!("unexpected result {other:?}"),
+    }
+}
+
+fn assert_same(split: &ScanSet, sequential: &ScanSet) {
+    assert_eq!(split.len(), sequential.len());
+    for ((split_key, split), (key, scan)) in split.entries().zip(sequential.entries()) {
+        assert_eq!(split_key, key);
+        assert_eq!(split, scan);
+    }
+}
+
+#[test]
+fn split_and_sequential_loading_give_the_same_scans() {
+    let dir = std::path::PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("pointfiles");
+    let mut total = 0;
+    for entry in fs::read_dir(dir).unwrap() {
+        let path = entry.unwrap().path();
+        let path = path.to_str().unwrap();
+        let sequential = load(path, false);
+        total += sequential.len();
+        assert_same(&load(path, true), &sequential);
+    }
+    assert!(total > 0);
+}
+
+#[test]
+fn split_loading_keeps_metadata_and_line_endings() {
+    let path = std::env::temp_dir().join("loader_metadata");
+    fs::write(
+        &path,
+        "#timestamp=1\r\n#sensor=front\r\n2\r\n0.1, 1\r\n0.2, 1\r\n\r\n\
+         #timestamp=2\r\n1\r\n0.3, 1\r\n\r\n# comment\n1\n0.4, 1",
+    )
+    .unwrap();
+    let path = path.to_str().unwrap();
+    let sequential = load(path, false);
+    let split = load(path, true);
+    fs::remove_file(path).unwrap();
+
+    assert_eq!(sequential.len(), 3);
+    assert_eq!(sequential.iter().next().unwrap().sensor_id(), Some("front"));
+    assert_same(&split, &sequential);
+}
+
+#[test]
+fn compressed_files_are_not_split() {
+    let path = std::env::temp_dir().join("loader_compressed.gz");
+    let mut encoder = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+    std::io::Write::write_all(&mut encoder, b"1\n0.1, 1\n\n1\n0.2, 1\n\n").unwrap();
+    fs::write(&path, encoder.finish().unwrap()).unwrap();
+    let path = path.to_str().unwrap();
+    let sequential = load(path, false);
+    let split = load(path, true);
+    fs::remove_file(path).unwrap();
+
+    assert_eq!(sequential.len(), 2);
+    assert_same(&split, &sequential);
+}
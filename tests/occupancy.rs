@@ -0,0 +1,64 @@
+use ptfl_reader::{CartesianPoint, Occupancy, OccupancyGrid, Point, Pose, Scan};
+use std::f64::consts::FRAC_PI_2;
+
+fn occupancy(grid: &OccupancyGrid, x: f64, y: f64) -> Occupancy {
+    let (column, row) = grid.cell_at(&CartesianPoint::new(x, y)).unwrap();
+    grid.occupancy_at(column, row)
+}
+
+#[test]
+fn rays_mark_free_space_and_hits() {
+    let scan = Scan::new(
+        "a",
+        0,
+        vec![Point::new(0.0, 0.45), Point::new(FRAC_PI_2, 0.0)],
+    );
+    let mut grid = OccupancyGrid::new().resolution(0.1).max_range(0.5);
+    for _ in 0..5 {
+        grid.insert(&scan, Pose::default());
+    }
+
+    for i in 0..4 {
+        let x = i as f64 * 0.1 + 0.05;
+        assert_eq!(occupancy(&grid, x, 0.05), Occupancy::Free);
+    }
+    assert_eq!(occupancy(&grid, 0.45, 0.05), Occupancy::Occupied);
+    assert_eq!(occupancy(&grid, 0.55, 0.05), Occupancy::Unknown);
+    // the missing return is free up to the maximum range, without a hit
+    assert_eq!(occupancy(&grid, 0.05, 0.45), Occupancy::Free);
+    assert_eq!(occupancy(&grid, 0.05, 0.55), Occupancy::Free);
+    assert_eq!(occupancy(&grid, 0.05, 0.65), Occupancy::Unknown);
+    assert_eq!(occupancy(&grid, -0.55, 0.05), Occupancy::Unknown);
+}
+
+#[test]
+fn scans_are_placed_at_their_pose() {
+    let scan = Scan::new("a", 0, vec![Point::new(0.0, 1.05)]);
+    let mut grid = OccupancyGrid::new().resolution(0.1);
+    for _ in 0..5 {
+        grid.insert(&scan, Pose::new(-3.0, 2.0, FRAC_PI_2));
+    }
+    assert_eq!(occupancy(&grid, -2.95, 3.05), Occupancy::Occupied);
+    assert_eq!(occupancy(&grid, -2.95, 2.55), Occupancy::Free);
+}
+
+#[test]
+fn exports_pgm_and_descriptor() {
+    let scan = Scan::new("a", 0, vec![Point::new(0.0, 1.0)]);
+    let mut grid = OccupancyGrid::new().resolution(0.1);
+    grid.insert(&scan, Pose::default());
+
+    let mut pgm = Vec::new();
+    grid.write_pgm(&mut pgm).unwrap();
+    let header = format!("P5\n{} {}\n255\n", grid.width(), grid.height());
+    assert!(pgm.starts_with(header.as_bytes()));
+    assert_eq!(pgm.len(), header.len() + grid.width() * grid.height());
+
+    let mut yaml = Vec::new();
+    grid.write_yaml(&mut yaml, "map.pgm").unwrap();
+    let yaml = String::from_utf8(yaml).unwrap();
+    let origin = grid.origin();
+    assert!(yaml.starts_with("image: map.pgm\nresolution: 0.1\n"));
+    assert!(yaml.contains(&format!("origin: [{}, {}, 0.0]", origin.x, origin.y)));
+    assert_eq!(grid.to_pixmap().unwrap().width() as usize, grid.width());
+}
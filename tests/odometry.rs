@@ -0,0 +1,57 @@
+mod common;
+
+use common::room;
+use ptfl_reader::{Icp, IcpMethod, Pose, Scan, Trajectory};
+
+// the room as seen from the pose
+fn seen_from(pose: Pose, index: u32) -> Scan {
+    let (sin, cos) = pose.theta.sin_cos();
+    let inverse = Pose::new(
+        -(cos * pose.x + sin * pose.y),
+        sin * pose.x - cos * pose.y,
+        -pose.theta,
+    );
+    let mut scan = room().with_key("room", index);
+    scan.timestamp = Some(index as f64 * 0.1);
+    scan.transform(inverse);
+    scan
+}
+
+#[test]
+fn trajectory_chains_registrations() {
+    let poses = [
+        Pose::new(0.0, 0.0, 0.0),
+        Pose::new(0.05, 0.0, 0.02),
+        Pose::new(0.1, 0.03, 0.05),
+    ];
+    let scans: Vec<Scan> = poses
+        .iter()
+        .enumerate()
+        .map(|(i, &pose)| seen_from(pose, i as u32))
+        .collect();
+    let scans: Vec<&Scan> = scans.iter().collect();
+
+    let icp = Icp::new().method(IcpMethod::PointToLine).tolerance(1e-9);
+    let trajectory = Trajectory::from_scans(&icp, &scans);
+    assert_eq!(trajectory.len(), 3);
+    assert_eq!(trajectory.unconverged(), 0);
+    assert!(trajectory.points[0].registration.is_none());
+    for (point, expected) in trajectory.points.iter().zip(&poses) {
+        assert!((point.pose.x - expected.x).abs() < 1e-6, "{point:?}");
+        assert!((point.pose.y - expected.y).abs() < 1e-6, "{point:?}");
+        assert!(
+            (point.pose.theta - expected.theta).abs() < 1e-6,
+            "{point:?}"
+        );
+    }
+
+    let mut csv = Vec::new();
+    trajectory.write_csv(&mut csv).unwrap();
+    let csv = String::from_utf8(csv).unwrap();
+    let lines: Vec<&str> = csv.lines().collect();
+    assert_eq!(lines.len(), 4);
+    assert_eq!(lines[0], "index,timestamp,x,y,theta,residual,converged");
+    assert_eq!(lines[1], "0,0,0.000000,0.000000,0.000000,,");
+    assert!(lines[2].starts_with("1,0.1,0.050000,0.000000,0.020000,"));
+    assert!(lines[2].ends_with(",true"));
+}
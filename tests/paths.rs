@@ -0,0 +1,128 @@
+use ptfl_reader::{glob_match, natural_cmp, PathExpander};
+use std::cmp::Ordering;
+use std::fs;
+use std::path::PathBuf;
+
+#[test]
+fn star_and_question_mark() {
+    assert!(glob_match("*", "2022080101"));
+    assert!(glob_match("2022*", "2022080101"));
+    assert!(glob_match("*01", "2022080101"));
+    assert!(glob_match("a*b*c", "axxbyyc"));
+    assert!(!glob_match("a*b*c", "axxbyy"));
+    assert!(glob_match("2?", "23"));
+    assert!(!glob_match("2?", "2"));
+    assert!(!glob_match("2?", "234"));
+    assert!(glob_match("", ""));
+    assert!(!glob_match("", "a"));
+}
+
+#[test]
+fn character_classes() {
+    assert!(glob_match("[a-z]", "q"));
+    assert!(!glob_match("[a-z]", "Q"));
+    assert!(glob_match("file[0-9][0-9]", "file42"));
+    assert!(glob_match("[abc]x", "bx"));
+    assert!(!glob_match("[abc]x", "dx"));
+    assert!(glob_match("[!x]", "y"));
+    assert!(!glob_match("[!x]", "x"));
+    assert!(glob_match("[!0-9]*", "log1"));
+    assert!(!glob_match("[!0-9]*", "1log"));
+    // a leading ] is part of the set, an unclosed bracket is literal
+    assert!(glob_match("[]]", "]"));
+    assert!(glob_match("a[b", "a[b"));
+}
+
+#[test]
+fn numbers_sort_by_value() {
+    assert_eq!(natural_cmp("2", "10"), Ordering::Less);
+    assert_eq!(natural_cmp("file10", "file2"), Ordering::Greater);
+    assert_eq!(natural_cmp("a2b10", "a2b9"), Ordering::Greater);
+    assert_eq!(natural_cmp("b1", "a2"), Ordering::Greater);
+    assert_eq!(natural_cmp("7", "007"), Ordering::Less);
+    assert_eq!(natural_cmp("same", "same"), Ordering::Equal);
+
+    let mut names = vec!["10", "9", "1", "100", "02"];
+    names.sort_by(|a, b| natural_cmp(a, b));
+    assert_eq!(names, vec!["1", "02", "9", "10", "100"]);
+}
+
+// a directory tree of empty files, removed when dropped
+struct Tree(PathBuf);
+
+impl Tree {
+    fn new(name: &str, files: &[&str]) -> Tree {
+        let root = std::env::temp_dir().join(name);
+        let _ = fs::remove_dir_all(&root);
+        for file in files {
+            let path = root.join(file);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(path, "").unwrap();
+        }
+        Tree(root)
+    }
+
+    fn path(&self, rest: &str) -> String {
+        format!("{}/{}", self.0.to_str().unwrap(), rest)
+    }
+}
+
+impl Drop for Tree {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.0);
+    }
+}
+
+#[test]
+fn patterns_expand_in_natural_order() {
+    let tree = Tree::new("paths_expand", &["10", "2", "1", "a/3", "a/b/4", ".hidden"]);
+    let expanded = PathExpander::new().expand(&[tree.path("*")]).unwrap();
+    // the directory matched by the pattern is loaded whole
+    let expected: Vec<String> = ["1", "2", "10", "a/3", "a/b/4"]
+        .iter()
+        .map(|name| tree.path(name))
+        .collect();
+    assert_eq!(expanded, expected);
+
+    let expanded = PathExpander::new().expand(&[tree.path("?")]).unwrap();
+    assert_eq!(expanded.len(), 4);
+    assert!(!expanded.contains(&tree.path("10")));
+}
+
+#[test]
+fn double_star_matches_any_depth() {
+    let tree = Tree::new("paths_double_star", &["x1", "a/x2", "a/b/x3", "a/b/y"]);
+    let expanded = PathExpander::new().expand(&[tree.path("**/x*")]).unwrap();
+    assert_eq!(
+        expanded,
+        vec![tree.path("a/b/x3"), tree.path("a/x2"), tree.path("x1")]
+    );
+}
+
+#[test]
+fn include_and_exclude() {
+    let tree = Tree::new("paths_filters", &["1.gz", "2", "3.gz", "4.zst"]);
+    let expanded = PathExpander::new()
+        .include("*.gz")
+        .include("*.zst")
+        .exclude("[3]*")
+        .expand(&[tree.0.to_str().unwrap().to_string()])
+        .unwrap();
+    assert_eq!(expanded, vec![tree.path("1.gz"), tree.path("4.zst")]);
+}
+
+#[test]
+fn unmatched_patterns_are_errors() {
+    let tree = Tree::new("paths_unmatched", &["1"]);
+    assert!(PathExpander::new().expand(&[tree.path("[2-9]")]).is_err());
+    assert!(PathExpander::new().expand(&[tree.path("2")]).is_err());
+}
+
+#[test]
+fn stdin_is_kept_once() {
+    let expanded = PathExpander::new().expand(&["-".to_string()]).unwrap();
+    assert_eq!(expanded, vec!["-".to_string()]);
+    assert!(PathExpander::new()
+        .expand(&["-".to_string(), "-".to_string()])
+        .is_err());
+}
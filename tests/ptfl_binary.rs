@@ -0,0 +1,191 @@
+use ptfl_reader::{BinaryScanReader, BinaryWriter, ParseError, Point, Pose, Scan};
+
+fn scans() -> Vec<Scan> {
+    (0..3)
+        .map(|index| {
+            let points = (0..10)
+                .map(|i| Point::new(i as f64 * 0.1 + 0.01, 1.0 / (i + index + 1) as f64))
+                .collect();
+            let mut scan = Scan::new("binary", index, points);
+            scan.timestamp = Some(index as f64 * 0.1);
+            scan.set_pose(Pose::new(0.5, -1.5, 0.25));
+            scan
+        })
+        .collect()
+}
+
+fn write(scans: &[Scan], single_precision: bool) -> Vec<u8> {
+    let mut writer = BinaryWriter::new(Vec::new()).single_precision(single_precision);
+    writer.write_scans(scans).unwrap();
+    writer.finish().unwrap()
+}
+
+fn read(data: &[u8]) -> Vec<Result<Scan, ParseError>> {
+    BinaryScanReader::new(data, "dir/binary").unwrap().collect()
+}
+
+// header, count and timestamp, then the metadata count
+const METADATA_COUNT_AT: usize = 12 + 4 + 9;
+
+#[test]
+fn round_trip() {
+    let scans = scans();
+    let reread: Vec<Scan> = read(&write(&scans, false))
+        .into_iter()
+        .collect::<Result<_, _>>()
+        .unwrap();
+    assert_eq!(reread, scans);
+}
+
+#[test]
+fn single_precision_round_trip() {
+    let scans = scans();
+    let data = write(&scans, true);
+    assert!(data.len() < write(&scans, false).len());
+
+    let reread: Vec<Scan> = read(&data).into_iter().collect::<Result<_, _>>().unwrap();
+    assert_eq!(reread.len(), scans.len());
+    for (scan, reread) in scans.iter().zip(&reread) {
+        assert_eq!(reread.timestamp, scan.timestamp);
+        assert_eq!(reread.metadata, scan.metadata);
+        for (point, reread) in scan.points().iter().zip(reread.points()) {
+            assert_eq!(reread.angle, point.angle as f32 as f64);
+            assert_eq!(reread.range, point.range as f32 as f64);
+        }
+    }
+}
+
+#[test]
+fn checksum_mismatch_skips_the_scan() {
+    let mut data = write(&scans(), false);
+    // a byte in the angles of the first scan
+    data[METADATA_COUNT_AT + 40] ^= 0xff;
+
+    let results = read(&data);
+    assert_eq!(results.len(), 3);
+    assert!(matches!(
+        results[0],
+        Err(ParseError::ChecksumMismatch {
+            offset: 12,
+            index: 0,
+            ..
+        })
+    ));
+    assert_eq!(results[1].as_ref().unwrap().index(), 1);
+    assert_eq!(results[2].as_ref().unwrap().index(), 2);
+}
+
+#[test]
+fn unknown_version_is_rejected() {
+    let mut data = write(&scans(), false);
+    for version in [0u16, 2] {
+        data[8..10].copy_from_slice(&version.to_le_bytes());
+        match BinaryScanReader::new(data.as_slice(), "binary") {
+            Err(ParseError::UnsupportedVersion { version: found, .. }) => {
+                assert_eq!(found, version)
+            }
+            other => panic!("unexpected result {:?}", other.map(|_| ())),
+        }
+    }
+}
+
+#[test]
+fn unknown_flags_are_corrupt() {
+    let mut data = write(&scans(), false);
+    data[10..12].copy_from_slice(&(1u16 << 2).to_le_bytes());
+    assert!(matches!(
+        BinaryScanReader::new(data.as_slice(), "binary"),
+        Err(ParseError::Corrupt { offset: 10, .. })
+    ));
+}
+
+#[test]
+fn huge_lengths_are_corrupt() {
+    let data = write(&scans()[..1], false);
+
+    let mut count = data.clone();
+    count[12..16].copy_from_slice(&u32::MAX.to_le_bytes());
+    assert!(matches!(
+        read(&count)[..],
+        [Err(ParseError::Corrupt { .. })]
+    ));
+
+    let mut metadata = data;
+    metadata[METADATA_COUNT_AT + 4..METADATA_COUNT_AT + 8].copy_from_slice(&u32::MAX.to_le_bytes());
+    match &read(&metadata)[..] {
+        [Err(err @ ParseError::Corrupt { .. })] => {
+            assert!(err.to_string().ends_with("file ended inside a scan"))
+        }
+        other => panic!("unexpected result {other:?}"),
+    }
+}
+
+// 20 points so the bitmaps take three bytes
+fn column_scan() -> Scan {
+    let points = (0..20)
+        .map(|i| {
+            let point = Point::new(i as f64 * 0.1, 1.0);
+            let point = match i {
+                0 | 7 | 8 | 19 => point.with_intensity(i as f64 + 0.5),
+                _ => point,
+            };
+            match i {
+                1 | 8 | 16 => point.with_flags(i * 3),
+                _ => point,
+            }
+        })
+        .collect();
+    Scan::new("binary", 0, points)
+}
+
+#[test]
+fn column_bitmaps() {
+    let scan = column_scan();
+    let mut writer = BinaryWriter::new(Vec::new()).checksum(false);
+    writer.write_scan(&scan).unwrap();
+    let data = writer.finish().unwrap();
+
+    // header, count, no timestamp, no metadata, then angles and ranges
+    let columns_at = 12 + 4 + 1 + 4 + 20 * 16;
+    assert_eq!(data[columns_at], 0b11);
+    let intensities = &data[columns_at + 1..];
+    assert_eq!(intensities[..3], [0b1000_0001, 0b0000_0001, 0b0000_1000]);
+    let flags = &intensities[3 + 20 * 8..];
+    assert_eq!(flags[..3], [0b0000_0010, 0b0000_0001, 0b0000_0001]);
+    assert_eq!(flags.len(), 3 + 20 * 4);
+
+    let reread: Vec<Scan> = read(&data).into_iter().collect::<Result<_, _>>().unwrap();
+    assert_eq!(reread, vec![scan]);
+}
+
+#[test]
+fn single_column_round_trip() {
+    let mut flags_only = column_scan();
+    for point in flags_only.points_mut() {
+        point.intensity = None;
+    }
+    let mut intensities_only = column_scan();
+    for point in intensities_only.points_mut() {
+        point.flags = None;
+    }
+    for (scan, columns) in [(flags_only, 0b10), (intensities_only, 0b01)] {
+        let data = write(std::slice::from_ref(&scan), true);
+        assert_eq!(data[12 + 4 + 1 + 4 + 20 * 8], columns);
+        let reread: Vec<Scan> = read(&data).into_iter().collect::<Result<_, _>>().unwrap();
+        // the values are all exact in f32
+        assert_eq!(reread[0].points().len(), 20);
+        for (point, reread) in scan.points().iter().zip(reread[0].points()) {
+            assert_eq!(reread.intensity, point.intensity);
+            assert_eq!(reread.flags, point.flags);
+        }
+    }
+}
+
+#[test]
+fn unknown_columns_are_corrupt() {
+    let mut writer = BinaryWriter::new(Vec::new()).checksum(false);
+    writer.write_scan(&scans()[0]).unwrap();
+    let mut data = writer.finish().unwrap();
+    *data.last_mut().unwrap() = 0b100;
+    assert!(matches!(read(&data)[..], [Err(ParseError::Corrupt { .. })]));
+}
@@ -0,0 +1,307 @@
+use ptfl_reader::{ParseError, ParseReport, Point, PtflParser, Scan, ScanReader, ScanSet};
+use std::fs;
+use std::io::{BufReader, Read};
+
+fn read(text: &str) -> Vec<Result<Scan, ParseError>> {
+    ScanReader::new(BufReader::new(text.as_bytes()), "test").collect()
+}
+
+fn first_error(text: &str) -> ParseError {
+    read(text)
+        .into_iter()
+        .find_map(Result::err)
+        .expect("expected a parse error")
+}
+
+#[test]
+fn bad_float_points_at_its_field() {
+    let err = first_error("2\n0.1, 1.0\n0.2,  1.x\n\n");
+    match &err {
+        ParseError::BadFloat {
+            path,
+            line,
+            column,
+            text,
+        } => {
+            assert_eq!(path, "test");
+            assert_eq!(*line, 3);
+            assert_eq!(*column, 7);
+            assert_eq!(text, "1.x");
+        }
+        other => panic!("unexpected error {other:?}"),
+    }
+    assert_eq!(err.line(), Some(3));
+    assert_eq!(err.to_string(), "test:3:7: expected float, got \"1.x\"");
+}
+
+#[test]
+fn bad_float_in_first_field() {
+    let err = first_error("1\nangle, 1.0\n\n");
+    assert_eq!(err.to_string(), "test:2:1: expected float, got \"angle\"");
+}
+
+#[test]
+fn bad_flags_points_at_its_field() {
+    let err = first_error("1\n0.1, 1.0, 3, 0xzz\n\n");
+    assert!(matches!(err, ParseError::BadFlags { column: 14, .. }));
+    assert_eq!(
+        err.to_string(),
+        "test:2:14: expected integer flags, got \"0xzz\""
+    );
+}
+
+#[test]
+fn header_errors() {
+    let err = first_error("two\n0.1, 1.0\n");
+    assert_eq!(err.to_string(), "test:1: expected point count, got \"two\"");
+
+    let err = first_error("0\n");
+    assert!(matches!(err, ParseError::ZeroCount { line: 1, .. }));
+    assert_eq!(
+        err.to_string(),
+        "test:1: expected non-zero point count, got 0"
+    );
+}
+
+#[test]
+fn field_count_error() {
+    let err = first_error("1\n0.1\n\n");
+    assert_eq!(err.line(), Some(2));
+    assert_eq!(
+        err.to_string(),
+        "test:2: expected 2 to 4 comma separated fields, got 1 in \"0.1\""
+    );
+}
+
+#[test]
+fn truncated_scan_error() {
+    let err = first_error("3\n0.1, 1.0\n0.2, 1.0\n");
+    assert!(matches!(
+        err,
+        ParseError::Truncated {
+            expected: 3,
+            found: 2,
+            ..
+        }
+    ));
+    assert_eq!(err.path(), "test");
+}
+
+fn parse_recovering(name: &str, text: &str) -> (ScanSet, ParseReport) {
+    let path = std::env::temp_dir().join(name);
+    fs::write(&path, text).unwrap();
+    let mut scans = ScanSet::new();
+    let report = PtflParser::new()
+        .parse_recovering(path.to_str().unwrap(), &mut scans)
+        .unwrap();
+    fs::remove_file(&path).unwrap();
+    (scans, report)
+}
+
+#[test]
+fn recovering_keeps_scans_around_bad_lines() {
+    let (scans, report) = parse_recovering(
+        "ptfl_parse_recovering",
+        "2\n0.1, 1.0\n0.2, 1.0\n\nnot a count\n0.3, 1.0\n\n1\n0.4, 1.0\n\n",
+    );
+    assert_eq!(report.scans, 2);
+    assert_eq!(scans.len(), 2);
+    assert_eq!(report.diagnostics.len(), 1);
+    assert!(matches!(
+        report.diagnostics[0],
+        ParseError::BadCount { line: 5, .. }
+    ));
+    let lengths: Vec<usize> = scans.iter().map(|scan| scan.len()).collect();
+    assert_eq!(lengths, vec![2, 1]);
+}
+
+#[test]
+fn recovering_reports_short_scans() {
+    let (scans, report) = parse_recovering(
+        "ptfl_parse_short_scan",
+        "3\n0.1, 1.0\n0.2, x\n0.3, 1.0\n\n1\n0.4, 1.0\n\n",
+    );
+    assert_eq!(report.scans, 2);
+    assert_eq!(scans.iter().next().unwrap().len(), 2);
+    assert_eq!(report.diagnostics.len(), 2);
+    assert!(matches!(
+        report.diagnostics[0],
+        ParseError::BadFloat { line: 3, .. }
+    ));
+    assert!(matches!(
+        report.diagnostics[1],
+        ParseError::ShortScan {
+            line: 4,
+            expected: 3,
+            found: 2,
+            ..
+        }
+    ));
+    assert!(report.diagnostics[1]
+        .to_string()
+        .ends_with(":4: scan kept with 2 of its 3 points"));
+}
+
+#[test]
+fn recovering_drops_cut_scans() {
+    let (scans, report) = parse_recovering(
+        "ptfl_parse_cut_scan",
+        "3\n0.1, 1.0\n2\n0.2, 1.0\n0.3, 1.0\n\n",
+    );
+    assert_eq!(report.scans, 1);
+    assert_eq!(scans.iter().next().unwrap().index(), 1);
+    assert!(matches!(
+        report.diagnostics[..],
+        [ParseError::Truncated {
+            expected: 3,
+            found: 1,
+            ..
+        }]
+    ));
+}
+
+#[test]
+fn strict_parsing_stops_at_the_first_bad_line() {
+    let path = std::env::temp_dir().join("ptfl_parse_strict");
+    fs::write(&path, "2\n0.1, 1.0\n0.2, x\n\n").unwrap();
+    let mut scans = ScanSet::new();
+    let result = PtflParser::new().parse(path.to_str().unwrap(), &mut scans);
+    fs::remove_file(&path).unwrap();
+    assert!(matches!(result, Err(ParseError::BadFloat { line: 3, .. })));
+    assert!(scans.is_empty());
+}
+
+// hands out its data a few bytes per read
+struct Trickle<'a>(&'a [u8]);
+
+impl Read for Trickle<'_> {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        let count = buf.len().min(self.0.len()).min(3);
+        buf[..count].copy_from_slice(&self.0[..count]);
+        self.0 = &self.0[count..];
+        Ok(count)
+    }
+}
+
+#[test]
+fn scan_split_across_reads() {
+    let text = "#timestamp=1.5\n2\n0.1, 1.0\n0.2, 1.0\n\n1\n0.3, 1.0\n\n";
+    let reader = BufReader::with_capacity(4, Trickle(text.as_bytes()));
+    let scans: Vec<Scan> = ScanReader::new(reader, "dir/trickle")
+        .collect::<Result<_, _>>()
+        .unwrap();
+    assert_eq!(scans.len(), 2);
+    assert_eq!(scans[0].key(), ("trickle".to_string(), 0));
+    assert_eq!(scans[0].timestamp, Some(1.5));
+    assert_eq!(scans[0].len(), 2);
+    assert_eq!(scans[1].key(), ("trickle".to_string(), 1));
+}
+
+#[test]
+fn feed_line_hands_back_completed_scans() {
+    let mut parser = PtflParser::new();
+    let lines = ["2", "0.1, 1.0", "0.2, 1.0", "", "1"];
+    let mut completed = Vec::new();
+    for (i, line) in lines.iter().enumerate() {
+        if let Some(scan) = parser.feed_line("test", i + 1, line).unwrap() {
+            completed.push((i + 1, scan.len()));
+        }
+    }
+    assert_eq!(completed, vec![(3, 2)]);
+    assert_eq!(parser.scans_started(), 2);
+
+    // the input ends in the middle of the second scan
+    let err = parser.finish("test", lines.len()).unwrap_err();
+    assert!(matches!(
+        err,
+        ParseError::Truncated {
+            line: 5,
+            expected: 1,
+            found: 0,
+            ..
+        }
+    ));
+    // and the parser starts over afterwards
+    assert!(parser.finish("test", lines.len()).is_ok());
+}
+
+#[test]
+fn truncated_at_end_of_input() {
+    let results = read("1\n0.1, 1.0\n\n3\n0.1, 1.0\n0.2, 1.0");
+    assert_eq!(results.len(), 2);
+    assert_eq!(results[0].as_ref().unwrap().len(), 1);
+    assert!(matches!(
+        results[1],
+        Err(ParseError::Truncated {
+            line: 6,
+            expected: 3,
+            found: 2,
+            ..
+        })
+    ));
+}
+
+#[test]
+fn feeding_goes_on_after_an_error() {
+    let mut parser = PtflParser::new();
+    assert!(parser.feed_line("test", 1, "2").unwrap().is_none());
+    assert!(parser.feed_line("test", 2, "0.1, x").is_err());
+    // the rest of the broken scan is skipped up to the next header
+    assert!(parser.feed_line("test", 3, "0.2, 1.0").unwrap().is_none());
+    assert!(parser.feed_line("test", 4, "1").unwrap().is_none());
+    let scan = parser.feed_line("test", 5, "0.3, 1.0").unwrap().unwrap();
+    assert_eq!(scan.points(), vec![Point::new(0.3, 1.0)]);
+}
+
+#[test]
+fn resync_drops_metadata_of_broken_scans() {
+    let text = "#sensor=front\ncount\n0.1, 1.0\n#sensor=rear\n1\n0.2, 1.0\n";
+    let results: Vec<Result<Scan, ParseError>> =
+        ScanReader::new(BufReader::new(text.as_bytes()), "test")
+            .recovering(true)
+            .collect();
+    assert_eq!(results.len(), 2);
+    assert!(matches!(
+        results[0],
+        Err(ParseError::BadCount { line: 2, .. })
+    ));
+    let scan = results[1].as_ref().unwrap();
+    assert_eq!(scan.sensor_id(), Some("rear"));
+    assert_eq!(scan.index(), 0);
+}
+
+#[test]
+fn header_inside_a_scan_starts_over() {
+    let text = "3\n0.1, 1.0\n0\n0.2, 1.0\n2\n0.3, 1.0\n0.4, 1.0\n";
+    let results: Vec<Result<Scan, ParseError>> =
+        ScanReader::new(BufReader::new(text.as_bytes()), "test")
+            .recovering(true)
+            .collect();
+    // the zero count cuts the first scan and resyncs past the stray point
+    assert_eq!(results.len(), 2);
+    assert!(matches!(
+        results[0],
+        Err(ParseError::Truncated {
+            line: 2,
+            expected: 3,
+            found: 1,
+            ..
+        })
+    ));
+    let scan = results[1].as_ref().unwrap();
+    assert_eq!(scan.len(), 2);
+    assert_eq!(scan.index(), 1);
+}
+
+#[test]
+fn huge_count_header_is_not_preallocated() {
+    let results = read("4000000000000\n0.1, 1.0\n");
+    assert!(matches!(
+        results[..],
+        [Err(ParseError::Truncated {
+            expected: 4000000000000,
+            found: 1,
+            ..
+        })]
+    ));
+}
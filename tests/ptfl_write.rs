@@ -0,0 +1,170 @@
+use ptfl_reader::{Pose, PtflParser, PtflWriter, Scan, ScanReader, ScanSet};
+use std::fs;
+use std::io::BufReader;
+use std::path::PathBuf;
+
+fn pointfiles() -> Vec<PathBuf> {
+    let dir = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("pointfiles");
+    let mut paths: Vec<PathBuf> = fs::read_dir(dir)
+        .unwrap()
+        .map(|entry| entry.unwrap().path())
+        .collect();
+    paths.sort();
+    paths
+}
+
+#[test]
+fn round_trip_pointfiles_byte_for_byte() {
+    for path in pointfiles() {
+        let original = fs::read(&path).unwrap();
+        let path = path.to_str().unwrap();
+        let scans: Vec<Scan> = ScanReader::new(BufReader::new(original.as_slice()), path)
+            .collect::<Result<_, _>>()
+            .unwrap();
+
+        let mut writer = PtflWriter::new(Vec::new());
+        writer.write_scans(&scans).unwrap();
+        assert!(
+            writer.into_inner() == original,
+            "{path} differs after round trip"
+        );
+    }
+}
+
+#[test]
+fn round_trip_through_parser() {
+    let path = pointfiles()
+        .into_iter()
+        .find(|path| path.ends_with("2022080101"))
+        .unwrap();
+    let mut original = ScanSet::new();
+    PtflParser::new()
+        .parse(path.to_str().unwrap(), &mut original)
+        .unwrap();
+
+    let output = std::env::temp_dir().join("ptfl_write_round_trip");
+    let mut writer = PtflWriter::new(fs::File::create(&output).unwrap());
+    writer.write_scans(&original).unwrap();
+    writer.flush().unwrap();
+
+    let mut reread = ScanSet::new();
+    let count = PtflParser::new()
+        .parse(output.to_str().unwrap(), &mut reread)
+        .unwrap();
+    fs::remove_file(&output).unwrap();
+
+    assert_eq!(count as usize, original.len());
+    for (scan, reread) in original.iter().zip(reread.iter()) {
+        assert_eq!(scan.points(), reread.points());
+    }
+}
+
+#[test]
+fn precision_is_configurable() {
+    let scan = Scan::new("a", 0, vec![ptfl_reader::Point::new(-1.0, 0.25)]);
+    let mut writer = PtflWriter::new(Vec::new()).precision(2);
+    writer.write_scan(&scan).unwrap();
+    assert_eq!(writer.into_inner(), b"1\n-1.00, 0.25\n\n");
+}
+
+#[test]
+fn empty_scan_is_rejected() {
+    let mut writer = PtflWriter::new(Vec::new());
+    assert!(writer.write_scan(&Scan::new("a", 0, Vec::new())).is_err());
+}
+
+#[test]
+fn metadata_round_trip() {
+    let mut scan = Scan::new("a", 0, vec![ptfl_reader::Point::new(0.5, 1.0)]);
+    scan.timestamp = Some(1659312000.125);
+    scan.set_sensor_id("front");
+    scan.set_pose(Pose::new(1.5, -2.0, 0.25));
+    scan.set_rpm(600.0);
+    scan.metadata.insert("note".to_string(), "a=b".to_string());
+
+    let mut writer = PtflWriter::new(Vec::new());
+    writer.write_scan(&scan).unwrap();
+    let written = writer.into_inner();
+    assert!(written.starts_with(b"#timestamp=1659312000.125\n#sensor=front\n"));
+
+    let reread: Vec<Scan> = ScanReader::new(BufReader::new(written.as_slice()), "a")
+        .collect::<Result<_, _>>()
+        .unwrap();
+    assert_eq!(reread, vec![scan]);
+    assert_eq!(reread[0].pose(), Some(Pose::new(1.5, -2.0, 0.25)));
+    assert_eq!(reread[0].rpm(), Some(600.0));
+}
+
+#[test]
+fn extra_columns_round_trip() {
+    let points = vec![
+        ptfl_reader::Point::new(0.0, 1.0).with_intensity(47.0),
+        ptfl_reader::Point::new(0.1, 1.0)
+            .with_intensity(0.5)
+            .with_flags(3),
+        ptfl_reader::Point::new(0.2, 1.0).with_flags(1),
+        ptfl_reader::Point::new(0.3, 1.0),
+    ];
+    let scan = Scan::new("a", 0, points);
+    let mut writer = PtflWriter::new(Vec::new()).precision(1);
+    writer.write_scan(&scan).unwrap();
+    let written = writer.into_inner();
+    assert_eq!(
+        written,
+        b"4\n0.0, 1.0, 47\n0.1, 1.0, 0.5, 3\n0.2, 1.0, , 1\n0.3, 1.0\n\n"
+    );
+
+    let reread: Vec<Scan> = ScanReader::new(BufReader::new(written.as_slice()), "a")
+        .collect::<Result<_, _>>()
+        .unwrap();
+    assert_eq!(reread, vec![scan]);
+}
+
+#[test]
+fn unwritable_metadata_is_rejected() {
+    for (key, value) in [
+        ("note", "two\nlines"),
+        ("note", "carriage\rreturn"),
+        ("note", " padded"),
+        ("a=b", "c"),
+        ("", "empty key"),
+        ("key ", "value"),
+    ] {
+        let mut scan = Scan::new("a", 0, vec![ptfl_reader::Point::new(0.5, 1.0)]);
+        scan.metadata.insert(key.to_string(), value.to_string());
+        let mut writer = PtflWriter::new(Vec::new());
+        assert!(writer.write_scan(&scan).is_err(), "{key:?}={value:?}");
+        // nothing of the scan is written
+        assert!(writer.into_inner().is_empty());
+    }
+}
+
+#[test]
+fn metadata_the_reader_would_change_is_rejected() {
+    let write = |key: &str, value: &str| {
+        let mut scan = Scan::new("a", 0, vec![ptfl_reader::Point::new(0.5, 1.0)]);
+        scan.metadata.insert(key.to_string(), value.to_string());
+        let mut writer = PtflWriter::new(Vec::new());
+        writer
+            .write_scan(&scan)
+            .map(|_| (scan, writer.into_inner()))
+    };
+
+    // read back into the timestamp, or not at all
+    for (key, value) in [
+        ("timestamp", "1.5"),
+        ("timestamp", "soon"),
+        ("rpm", "fast"),
+        ("pose", "1,2"),
+    ] {
+        assert!(write(key, value).is_err(), "{key:?}={value:?}");
+    }
+
+    for (key, value) in [("rpm", "600"), ("pose", "1,2,0.5"), ("sensor", "front")] {
+        let (scan, written) = write(key, value).unwrap();
+        let reread: Vec<Scan> = ScanReader::new(BufReader::new(written.as_slice()), "a")
+            .collect::<Result<_, _>>()
+            .unwrap();
+        assert_eq!(reread, vec![scan]);
+    }
+}
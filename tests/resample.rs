@@ -0,0 +1,59 @@
+mod common;
+
+use common::scan;
+use ptfl_reader::{average, Interpolation, Resampler};
+use std::f64::consts::{FRAC_PI_2, PI};
+
+const EPSILON: f64 = 1e-9;
+
+#[test]
+fn grid_covers_a_turn_from_minus_pi() {
+    let resampler = Resampler::new().resolution(FRAC_PI_2);
+    let grid = resampler.grid();
+    assert_eq!(resampler.grid_len(), 4);
+    assert!((grid[0] + PI).abs() < EPSILON);
+    assert!((grid[3] - FRAC_PI_2).abs() < EPSILON);
+}
+
+#[test]
+fn nearest_and_linear_interpolation() {
+    // irregular spacing around the grid angle 0
+    let original = scan(&[(-0.3, 1.0), (-0.1, 2.0), (0.3, 4.0)]);
+    let grid = |resampler: Resampler| {
+        let resampled = resampler
+            .resolution(FRAC_PI_2)
+            .max_gap(1.0)
+            .resample(&original);
+        assert_eq!(resampled.points().len(), 4);
+        resampled.points()[2].range
+    };
+    assert_eq!(grid(Resampler::new()), 2.0);
+    let linear = grid(Resampler::new().interpolation(Interpolation::Linear));
+    assert!((linear - 2.5).abs() < EPSILON, "{linear}");
+}
+
+#[test]
+fn gaps_and_missing_returns_stay_empty() {
+    let original = scan(&[(-0.1, 2.0), (0.05, 0.0), (0.1, 4.0), (2.0, 1.0)]);
+    let resampled = Resampler::new()
+        .resolution(FRAC_PI_2)
+        .interpolation(Interpolation::Linear)
+        .max_gap(0.5)
+        .resample(&original);
+    // next to a missing return, the nearest point is taken
+    assert_eq!(resampled.points()[2].range, 0.0);
+    // between 0.1 and 2.0, too far apart to fill in
+    assert_eq!(resampled.points()[3].range, 0.0);
+}
+
+#[test]
+fn average_leaves_out_missing_returns() {
+    let a = scan(&[(0.0, 1.0), (1.0, 0.0)]);
+    let b = scan(&[(0.0, 2.0), (1.0, 3.0)]);
+    let averaged = average(&[&a, &b]).unwrap();
+    assert_eq!(averaged[0].range, 1.5);
+    assert_eq!(averaged[1].range, 3.0);
+
+    let other_grid = scan(&[(0.0, 1.0), (0.5, 1.0)]);
+    assert!(average(&[&a, &other_grid]).is_none());
+}
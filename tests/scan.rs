@@ -0,0 +1,107 @@
+use ptfl_reader::{Point, Scan, ScanSet};
+
+fn scan(source: &str, index: u32) -> Scan {
+    Scan::new(source, index, vec![Point::new(0.0, index as f64)])
+}
+
+fn keys(scans: &ScanSet) -> Vec<(String, u32)> {
+    scans.keys().cloned().collect()
+}
+
+#[test]
+fn insert_keys_scans_by_source_and_index() {
+    let mut scans = ScanSet::new();
+    assert!(scans.insert(scan("a", 1)).is_none());
+    assert!(scans.insert(scan("b", 0)).is_none());
+    assert!(scans.insert(scan("a", 0)).is_none());
+
+    assert_eq!(scans.len(), 3);
+    assert!(scans.contains("a", 0));
+    assert!(!scans.contains("b", 1));
+    assert!(scans.contains_source("b"));
+    assert!(!scans.contains_source("c"));
+    assert_eq!(scans.get("a", 1).unwrap().points()[0].range, 1.0);
+    assert_eq!(
+        keys(&scans),
+        vec![
+            ("a".to_string(), 1),
+            ("b".to_string(), 0),
+            ("a".to_string(), 0)
+        ]
+    );
+}
+
+#[test]
+fn insert_replaces_in_place() {
+    let mut scans: ScanSet = [scan("a", 0), scan("a", 1), scan("a", 2)]
+        .into_iter()
+        .collect();
+    let mut replacement = scan("a", 1);
+    replacement.points_mut().push(Point::new(1.0, 1.0));
+
+    let replaced = scans.insert(replacement).unwrap();
+    assert_eq!(replaced.len(), 1);
+    assert_eq!(scans.get("a", 1).unwrap().len(), 2);
+    assert_eq!(
+        keys(&scans),
+        vec![
+            ("a".to_string(), 0),
+            ("a".to_string(), 1),
+            ("a".to_string(), 2)
+        ]
+    );
+}
+
+#[test]
+fn remove_keeps_order() {
+    let mut scans: ScanSet = (0..4).map(|index| scan("a", index)).collect();
+    assert_eq!(scans.remove("a", 1).unwrap().index(), 1);
+    assert!(scans.remove("a", 1).is_none());
+    let indices: Vec<u32> = scans.iter().map(|scan| scan.index()).collect();
+    assert_eq!(indices, vec![0, 2, 3]);
+}
+
+#[test]
+fn by_source_filters_in_order() {
+    let scans: ScanSet = [scan("a", 2), scan("b", 0), scan("a", 0)]
+        .into_iter()
+        .collect();
+    let indices: Vec<u32> = scans.by_source("a").map(|scan| scan.index()).collect();
+    assert_eq!(indices, vec![2, 0]);
+}
+
+#[test]
+fn with_key_renames_an_owned_scan() {
+    let mut scans = ScanSet::new();
+    scans.insert(scan("a", 0));
+    let copy = scans.get("a", 0).unwrap().clone().with_key("b", 7);
+    assert_eq!(copy.key(), ("b".to_string(), 7));
+    scans.insert(copy);
+
+    assert!(scans.contains("a", 0));
+    assert_eq!(scans.get("b", 7).unwrap().source(), "b");
+    // mutable access can change the points but never the key
+    for scan in scans.iter_mut() {
+        scan.points_mut().clear();
+    }
+    for ((source, index), scan) in scans.entries() {
+        assert_eq!((source.as_str(), *index), (scan.source(), scan.index()));
+        assert!(scan.is_empty());
+    }
+}
+
+#[test]
+fn cartesian_view_is_cached_until_points_change() {
+    let mut scan = Scan::new("a", 0, vec![Point::new(0.0, 1.0), Point::new(0.0, 0.0)]);
+    let first = scan.cartesian() as *const _;
+    assert!(std::ptr::eq(first, scan.cartesian()));
+    assert_eq!(scan.cartesian().returns().count(), 1);
+
+    scan.points_mut()[1].range = 2.0;
+    assert_eq!(scan.cartesian().returns().count(), 2);
+    assert!((scan.cartesian().points[1].x - 2.0).abs() < 1e-12);
+
+    // the cache doesn't take part in comparisons
+    let uncached = Scan::new("a", 0, scan.points().to_vec());
+    assert_eq!(scan, uncached);
+}
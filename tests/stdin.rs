@@ -0,0 +1,43 @@
+use std::io::Write;
+use std::process::{Command, Stdio};
+
+// runs the tui with the given args, feeding it stdin
+fn run(args: &[&str], stdin: &str) -> String {
+    let mut child = Command::new(env!("CARGO_BIN_EXE_ptfl_reader"))
+        .arg("--no-prompt")
+        .args(args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .spawn()
+        .unwrap();
+    child
+        .stdin
+        .take()
+        .unwrap()
+        .write_all(stdin.as_bytes())
+        .unwrap();
+    let output = child.wait_with_output().unwrap();
+    String::from_utf8(output.stdout).unwrap()
+}
+
+#[test]
+fn scans_and_commands_share_stdin() {
+    let output = run(
+        &["-"],
+        "2\n0.1, 1.0\n0.2, 1.0\n\n.\nload -\n1\n0.3, 1.0\n.\nlist\nexit\n",
+    );
+    assert!(output.contains("stdin-0000:        2 points"), "{output}");
+    assert!(output.contains("stdin-0001:        1 points"), "{output}");
+}
+
+#[test]
+fn stdin_without_terminator_is_read_to_the_end() {
+    let output = run(&["-"], "1\n0.1, 1.0\n");
+    assert!(output.contains("Read 1 from -."), "{output}");
+}
+
+#[test]
+fn stdin_can_only_be_given_once() {
+    let output = run(&["-", "-"], "1\n0.1, 1.0\n.\nexit\n");
+    assert!(output.contains("Stdin can only be read once"), "{output}");
+}
@@ -0,0 +1,98 @@
+use ptfl_reader::{ParseError, Scan, ScanStream};
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::PathBuf;
+use std::thread;
+use std::time::{Duration, Instant};
+
+struct TempFile(PathBuf);
+
+impl TempFile {
+    fn new(name: &str, data: &str) -> TempFile {
+        let path = std::env::temp_dir().join(name);
+        fs::write(&path, data).unwrap();
+        TempFile(path)
+    }
+
+    fn path(&self) -> &str {
+        self.0.to_str().unwrap()
+    }
+
+    fn append(&self, data: &str) {
+        let mut file = OpenOptions::new().append(true).open(&self.0).unwrap();
+        file.write_all(data.as_bytes()).unwrap();
+    }
+}
+
+impl Drop for TempFile {
+    fn drop(&mut self) {
+        let _ = fs::remove_file(&self.0);
+    }
+}
+
+// receives until `done` holds for what was received, or fails after a while
+fn receive_until<F>(stream: &mut ScanStream, done: F) -> Vec<Result<Scan, ParseError>>
+where
+    F: Fn(&ScanStream, &[Result<Scan, ParseError>]) -> bool,
+{
+    let start = Instant::now();
+    let mut received = Vec::new();
+    while !done(stream, &received) {
+        assert!(start.elapsed() < Duration::from_secs(10), "{received:?}");
+        received.append(&mut stream.receive());
+        thread::sleep(Duration::from_millis(20));
+    }
+    received
+}
+
+#[test]
+fn follow_reads_appended_scans() {
+    let file = TempFile::new("stream_follow", "1\n0.1, 1.0\n\n");
+    let mut stream = ScanStream::follow(file.path(), false);
+    let received = receive_until(&mut stream, |_, received| received.len() == 1);
+    assert_eq!(received[0].as_ref().unwrap().index(), 0);
+
+    // a scan written in pieces is only handed over once complete
+    file.append("2\n0.2, 1.0\n");
+    thread::sleep(Duration::from_millis(500));
+    assert!(stream.receive().is_empty());
+    file.append("0.3, 1.0\n\n");
+    let received = receive_until(&mut stream, |_, received| received.len() == 1);
+    let scan = received[0].as_ref().unwrap();
+    assert_eq!((scan.index(), scan.points().len()), (1, 2));
+
+    stream.stop();
+    let received = receive_until(&mut stream, |stream, _| stream.is_finished());
+    assert!(received.is_empty(), "{received:?}");
+}
+
+#[test]
+fn stopping_mid_scan_is_not_an_error() {
+    let file = TempFile::new("stream_stop_mid_scan", "1\n0.1, 1.0\n\n3\n0.2, 1.0\n0.3,");
+    let mut stream = ScanStream::follow(file.path(), false);
+    let received = receive_until(&mut stream, |_, received| !received.is_empty());
+    assert!(received[0].is_ok());
+
+    stream.stop();
+    let received = receive_until(&mut stream, |stream, _| stream.is_finished());
+    assert!(received.is_empty(), "{received:?}");
+}
+
+#[test]
+fn errors_before_stopping_are_reported() {
+    let file = TempFile::new("stream_bad_line", "2\n0.1, 1.0\nbad line\n");
+    let mut stream = ScanStream::follow(file.path(), false);
+    let received = receive_until(&mut stream, |_, received| !received.is_empty());
+    assert!(received[0].is_err());
+    stream.stop();
+    receive_until(&mut stream, |stream, _| stream.is_finished());
+}
+
+#[test]
+fn open_reads_a_whole_file() {
+    let file = TempFile::new("stream_open", "1\n0.1, 1.0\n\n1\n0.2, 1.0\n");
+    let mut stream = ScanStream::open(file.path(), false);
+    let received = receive_until(&mut stream, |stream, _| stream.is_finished());
+    assert_eq!(received.len(), 2);
+    assert!(received.iter().all(Result::is_ok));
+}
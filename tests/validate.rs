@@ -0,0 +1,181 @@
+use ptfl_reader::{Issue, Point, Scan, ScanSet, ValidationMode, Validator};
+
+// one of every issue but the point count
+fn scan() -> Scan {
+    let points = [
+        (-3.0, 1.0),
+        (-1.0, -2.0),
+        (0.0, 1.0),
+        (0.0, 1.0),
+        (1.0, f64::NAN),
+        (3.5, 1.0),
+        (f64::NAN, 1.0),
+    ]
+    .iter()
+    .map(|&(angle, range)| Point::new(angle, range))
+    .collect();
+    Scan::new("a", 0, points)
+}
+
+fn clean() -> Scan {
+    let points = (0..10).map(|i| Point::new(i as f64 * 0.1, 1.0)).collect();
+    Scan::new("a", 1, points)
+}
+
+fn validator(mode: ValidationMode) -> Validator {
+    Validator::new().mode(mode).expected_count(0)
+}
+
+fn issue_points(issues: &[Issue]) -> Vec<usize> {
+    issues
+        .iter()
+        .filter_map(|issue| match *issue {
+            Issue::InvalidValue { point, .. }
+            | Issue::NegativeRange { point, .. }
+            | Issue::RangeTooFar { point, .. }
+            | Issue::AngleOutOfRange { point, .. }
+            | Issue::DuplicateAngle { point, .. }
+            | Issue::NonMonotonic { point, .. } => Some(point),
+            Issue::SuspiciousCount { .. } => None,
+        })
+        .collect()
+}
+
+#[test]
+fn report_leaves_scans_alone() {
+    let mut scan = scan();
+    let report = validator(ValidationMode::Report).validate(&mut scan);
+    // the wrapped angle also comes before the one it follows
+    assert_eq!(issue_points(&report.issues), vec![1, 3, 4, 5, 5, 6]);
+    assert_eq!(report.fixed, 0);
+    assert!(!report.rejected);
+    assert_eq!(scan.points().len(), 7);
+    assert_eq!(scan.points()[5].angle, 3.5);
+
+    let mut scans: ScanSet = [scan, Scan::new("a", 2, Vec::new())].into_iter().collect();
+    let reports = validator(ValidationMode::Report).validate_set(&mut scans);
+    assert_eq!(reports.len(), 2);
+    assert_eq!(scans.len(), 2);
+}
+
+#[test]
+fn listed_entries_are_removed_like_the_whole_set() {
+    let keys = vec![("a".to_string(), 2), ("a".to_string(), 3)];
+    let set = || -> ScanSet {
+        [clean(), Scan::new("a", 2, Vec::new())]
+            .into_iter()
+            .collect()
+    };
+
+    // an empty scan is only reported, not removed
+    let mut scans = set();
+    let reports = validator(ValidationMode::Report).validate_entries(&mut scans, &keys);
+    assert_eq!(reports.len(), 1);
+    assert!(!reports[0].rejected);
+    assert_eq!(scans.len(), 2);
+
+    let mut scans = set();
+    let reports = validator(ValidationMode::Drop).validate_entries(&mut scans, &keys);
+    assert!(reports[0].rejected);
+    assert_eq!(scans.len(), 1);
+    assert!(scans.contains("a", 1));
+}
+
+#[test]
+fn reject_removes_scans_with_issues() {
+    let mut scans: ScanSet = [scan(), clean()].into_iter().collect();
+    let reports = validator(ValidationMode::Reject).validate_set(&mut scans);
+    assert!(reports[0].rejected);
+    assert!(!reports[1].rejected);
+    assert_eq!(scans.len(), 1);
+    assert!(scans.contains("a", 1));
+}
+
+#[test]
+fn suspicious_count_is_only_reported() {
+    let mut scans: ScanSet = [clean()].into_iter().collect();
+    let reports = Validator::new()
+        .mode(ValidationMode::Reject)
+        .expected_count(100)
+        .validate_set(&mut scans);
+    assert_eq!(
+        reports[0].issues,
+        vec![Issue::SuspiciousCount {
+            count: 10,
+            expected: 100
+        }]
+    );
+    assert!(!reports[0].rejected);
+    assert_eq!(scans.len(), 1);
+}
+
+#[test]
+fn clamp_fixes_values_and_keeps_angles_sorted() {
+    let mut scan = scan();
+    let report = validator(ValidationMode::Clamp).validate(&mut scan);
+    assert_eq!(report.fixed, 4);
+    let points: Vec<(f64, f64)> = scan
+        .points()
+        .iter()
+        .map(|point| (point.angle, point.range))
+        .collect();
+    let wrapped = 3.5 - 2.0 * std::f64::consts::PI;
+    assert_eq!(
+        points,
+        vec![
+            (-3.0, 1.0),
+            (wrapped, 1.0),
+            (-1.0, 0.0),
+            (0.0, 1.0),
+            (0.0, 1.0),
+            (1.0, 0.0),
+        ]
+    );
+}
+
+#[test]
+fn clamp_to_max_range() {
+    let mut scan = clean();
+    scan.points_mut()[3].range = 12.0;
+    let report = validator(ValidationMode::Clamp)
+        .max_range(8.0)
+        .validate(&mut scan);
+    assert_eq!(
+        report.issues,
+        vec![Issue::RangeTooFar {
+            point: 3,
+            range: 12.0
+        }]
+    );
+    assert_eq!(scan.points()[3].range, 8.0);
+}
+
+#[test]
+fn drop_removes_offending_points() {
+    let mut scan = scan();
+    let report = validator(ValidationMode::Drop).validate(&mut scan);
+    assert_eq!(report.fixed, 5);
+    assert_eq!(
+        scan.points(),
+        vec![Point::new(-3.0, 1.0), Point::new(0.0, 1.0)]
+    );
+
+    // a scan left without points is removed
+    let broken = Scan::new(
+        "b",
+        0,
+        vec![Point::new(0.0, -1.0), Point::new(f64::NAN, 1.0)],
+    );
+    let mut scans: ScanSet = [broken, clean()].into_iter().collect();
+    let reports = validator(ValidationMode::Drop).validate_set(&mut scans);
+    assert!(reports[0].rejected);
+    assert!(!scans.contains("b", 0));
+    assert!(scans.contains("a", 1));
+}
+
+#[test]
+fn modes_parse() {
+    assert_eq!("clamp".parse(), Ok(ValidationMode::Clamp));
+    assert_eq!("drop".parse(), Ok(ValidationMode::Drop));
+    assert!("bogus".parse::<ValidationMode>().is_err());
+}